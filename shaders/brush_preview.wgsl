@@ -0,0 +1,58 @@
+// shaders/brush_preview.wgsl
+// ブラシで置かれるドットの半透明のプレビュー (合成後のサーフェスに重ねて描く)
+
+@group(0) @binding(0) var<uniform> uniforms: DotUniforms;
+
+struct DotUniforms {
+    time: f32,
+    max_entropy_bias: f32,
+    camera_center: vec2<f32>, // ワールド座標での画面中心
+    camera_zoom: f32,
+    _padding: f32,
+    _padding2: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) local_pos: vec2<f32>,
+    @location(2) state: f32,
+}
+
+const PREVIEW_ALPHA: f32 = 0.4;
+
+@vertex
+fn vs_main(
+    @location(0) vertex_offset: vec2<f32>,
+    @location(1) instance_position: vec2<f32>,
+    @location(2) instance_color: vec3<f32>,
+    @location(3) instance_radius: f32,
+    @location(4) instance_state: f32,
+) -> VertexOutput {
+    let world_pos = instance_position + (vertex_offset * instance_radius);
+
+    // カメラ変換 (ワールド座標 -> 画面座標)
+    let screen_pos = (world_pos - uniforms.camera_center) * uniforms.camera_zoom + vec2<f32>(320.0, 240.0);
+    let ndc_pos = vec2<f32>(
+        (screen_pos.x / 640.0) * 2.0 - 1.0,
+        1.0 - (screen_pos.y / 480.0) * 2.0
+    );
+
+    var output: VertexOutput;
+    output.clip_position = vec4<f32>(ndc_pos, 0.0, 1.0);
+    output.color = instance_color;
+    output.local_pos = vertex_offset;
+    output.state = instance_state;
+    return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // 形は dot.wgsl と同じ (固体は四角、液体と気体は円)
+    if (in.state >= 0.5 && dot(in.local_pos, in.local_pos) > 1.0) { discard; }
+
+    // 縁を少し明るくして、暗い物質でも位置が分かるようにする
+    let edge = select(length(in.local_pos), max(abs(in.local_pos.x), abs(in.local_pos.y)), in.state < 0.5);
+    let rim = select(0.0, 0.3, edge > 0.8);
+    return vec4<f32>(in.color + rim, PREVIEW_ALPHA);
+}
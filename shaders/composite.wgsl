@@ -37,8 +37,17 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // シーンとグローを加算合成
     let hdr_color = scene_color + glow_color;
 
+#ifdef TONEMAP_ACES
+    // HDRからSDRへのトーンマッピング (ACES Filmic の近似)
+    let mapped_color = clamp(
+        (hdr_color * (2.51 * hdr_color + vec3<f32>(0.03))) / (hdr_color * (2.43 * hdr_color + vec3<f32>(0.59)) + vec3<f32>(0.14)),
+        vec3<f32>(0.0),
+        vec3<f32>(1.0)
+    );
+#else
     // HDRからSDRへの簡易トーンマッピング (Reinhard)
     let mapped_color = hdr_color / (hdr_color + vec3<f32>(1.0));
+#endif
     
     return vec4<f32>(mapped_color, 1.0);
 }
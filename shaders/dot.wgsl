@@ -3,7 +3,10 @@
 struct DotUniforms {
     time: f32,
     max_entropy_bias: f32,
-    _padding: vec2<f32>,
+    camera_center: vec2<f32>, // ワールド座標での画面中心
+    camera_zoom: f32,
+    _padding: f32,
+    _padding2: vec2<f32>,
 }
 
 struct VertexOutput {
@@ -16,15 +19,21 @@ struct VertexOutput {
     @location(5) state: f32,
     @location(6) cohesion: f32,
     @location(7) entropy_bias: f32,
+    @location(8) @interpolate(flat) pick_id: u32,
+    @location(9) radioactivity: f32,
 }
 
 struct FragmentOutput {
     @location(0) scene: vec4<f32>,
+#ifndef NO_GLOW
     @location(1) glow: vec4<f32>,
+    @location(2) pick_id: u32,
+#else
+    @location(1) pick_id: u32,
+#endif
 }
 
 // 頂点シェーダー
-const DOT_RADIUS: f32 = 2.0;
 
 // xorShift
 fn xorshift32(p: vec2<u32>) -> u32 {
@@ -105,8 +114,11 @@ fn vs_main(
     @location(6) instance_state: f32,
     @location(7) instance_cohesion: f32,
     @location(8) instance_entropy_bias: f32,
+    @location(9) instance_pick_id: u32,
+    @location(10) instance_radius: f32,
+    @location(11) instance_radioactivity: f32,
 ) -> VertexOutput {
-    var final_pos = instance_position + (vertex_offset * DOT_RADIUS);
+    var final_pos = instance_position + (vertex_offset * instance_radius);
 
     // --- 状態やプロパティに応じた揺れ ---
     let seed_pos = vec2<u32>(u32(instance_position.x), u32(instance_position.y));
@@ -132,9 +144,11 @@ fn vs_main(
         final_pos += vec2<f32>(rand_x, rand_y) * total_shake_amount;
     }
     
+    // カメラ変換 (ワールド座標 -> 画面座標)
+    let screen_pos = (final_pos - uniforms.camera_center) * uniforms.camera_zoom + vec2<f32>(320.0, 240.0);
     var ndc_pos = vec2<f32>(
-        (final_pos.x / 640.0) * 2.0 - 1.0,
-        1.0 - (final_pos.y / 480.0) * 2.0
+        (screen_pos.x / 640.0) * 2.0 - 1.0,
+        1.0 - (screen_pos.y / 480.0) * 2.0
     );
     
     var output: VertexOutput;
@@ -147,6 +161,8 @@ fn vs_main(
     output.local_pos = vertex_offset;
     output.cohesion = instance_cohesion;
     output.entropy_bias = instance_entropy_bias;
+    output.pick_id = instance_pick_id;
+    output.radioactivity = instance_radioactivity;
     
     return output;
 }
@@ -213,6 +229,16 @@ fn fs_main(in: VertexOutput) -> FragmentOutput {
         scene_color.b += glow_color.b * 0.2;
     }
 
+    // 放射性の物質のちらつき (ドットごとに、放射能が高いほど頻繁に緑がかった光で瞬く)
+    if (in.radioactivity > 0.05) {
+        let flicker = rand(vec2<f32>(f32(in.pick_id % 4096u), floor(uniforms.time * 12.0)));
+        if (flicker > 1.0 - in.radioactivity * 0.5) {
+            let flash = vec3<f32>(0.4, 1.0, 0.5) * in.radioactivity;
+            scene_color = vec4<f32>(scene_color.rgb + flash * 0.3, scene_color.a);
+            glow_color = vec4<f32>(glow_color.rgb + flash, 1.0);
+        }
+    }
+
     // 選択時の縁取り
     if (in.is_selected > 0.5) {
         let border_thickness = 0.4;
@@ -232,7 +258,10 @@ fn fs_main(in: VertexOutput) -> FragmentOutput {
     }
 
     output.scene = scene_color;
+#ifndef NO_GLOW
     output.glow = glow_color;
+#endif
+    output.pick_id = in.pick_id;
     
     return output;
 }
\ No newline at end of file
@@ -1,9 +1,41 @@
-use crate::material::{from_dna, to_dna, BaseMaterialParams, MaterialDNA};
-use crate::physics::engine::DOT_RADIUS;
-use crate::physics::{engine, Physics};
+use crate::brush::Brush;
+use crate::commands::{Command, CommandRegistry};
+use crate::crash::CrashReporter;
+use crate::gamepad::{Gamepad, GamepadSettings, CURSOR_SPEED};
+use crate::groups::{DotGroups, GroupAction, GroupId};
+use crate::journal::Journal;
+use crate::logging::LogBuffer;
+use crate::macros::{MacroAction, MacroKey, MacroRecorder, MacroStep, Macros};
+use crate::material::library::{LibraryAction, MaterialLibrary, MaterialPreset, LIBRARY_DIR};
+use crate::material::mutation::Mutation;
+use crate::material::{decide_reaction_type, from_dna, to_dna, BaseMaterialParams, MaterialDNA, ReactionType};
+use crate::physics::engine::{CollisionSender, DOT_RADIUS};
+use crate::physics::interaction::{InteractionMatrix, CONFIG_FILE as INTERACTIONS_FILE};
+use crate::physics::conveyor::{self, Conveyor, ConveyorSettings};
+use crate::physics::drain::{self, Drain, DrainFilter};
+use crate::physics::portal::{self, Portal, PortalSettings};
+use crate::physics::thermostat::WallThermostat;
+use crate::physics::{color_diffusion, combustion, convection, electricity, engine, radioactivity, shatter, GameRng, Physics};
+use crate::presets::Preset;
+use crate::puzzle::Puzzle;
+use crate::reaction_graph::{ExportFormat, ReactionGraph};
+use crate::replay::{Replay, ReplayControl, ReplayInput, ReplayMode, ReplayPlayer, ReplayRecorder, ReplaySettings, REPLAY_FILE};
+use crate::scene::{Scene, SCENE_FILE};
+use crate::renderer::camera::Camera;
+use crate::renderer::camera_path::{self, CameraPath, CameraPathAction};
+use crate::renderer::dna_editor::DnaEdit;
+use crate::renderer::wgpu_render::{BrushPreview, PortalRim};
 use crate::renderer::Renderer;
+use crate::seed_bank::{SeedBank, BANK_FILE as SEED_BANK_FILE};
+use crate::snapshot::Snapshot;
+use crate::tabs::{SimState, SimTab, TabAction, Tabs};
+use crate::tools::Tool;
+use crate::weather::{Weather, WeatherSettings};
+use crate::worldgen::Terrain;
 use rand::thread_rng;
+use rayon::prelude::*;
 use rand::Rng;
+use std::collections::{HashMap, HashSet};
 use std::sync::{mpsc, Arc};
 use winit::window::{Window, WindowBuilder};
 
@@ -13,28 +45,168 @@ pub struct Dot {
     pub id: u64,
     pub x: f64,
     pub y: f64,
+    pub prev_x: f64, // 直前の物理ステップ開始時の位置 (描画の補間用)
+    pub prev_y: f64,
     pub vx: f64, // x方向速度
     pub vy: f64, // y方向速度
     pub material: BaseMaterialParams,
     pub material_dna: MaterialDNA, // 物質DNA
     pub name: String,              // 自動生成された名前
     pub reaction_count: u32,
-    pub last_reaction_time: std::time::Instant,
-    pub last_check_time: std::time::Instant, // 最後の確率判定時刻
+    // 以下の時間はすべてシミュレーション時間 (秒) で、update_state で dt ずつ進む
+    pub since_reaction: f64, // 最後の反応からの経過時間
+    pub since_check: f64,    // 最後の確率判定からの経過時間
     pub is_selected: bool,                   // 選択状態
-    pub glowing_since: Option<std::time::Instant>,
-    pub last_heat_exchange_time: std::time::Instant, // 最後の熱交換時刻
+    pub glow_time: Option<f64>,   // 発光してからの経過時間
+    pub since_heat_exchange: f64, // 最後の熱交換からの経過時間
+    pub group: Option<GroupId>,                      // 所属グループ
+    pub hidden: bool,                                // 所属グループが非表示か
+    pub decay_in: Option<f64>,                       // 不安定・放射性の物質が崩壊するまでの残り時間
+    pub burn_time: Option<f64>,                      // 燃え始めてからの経過時間 (燃えていなければ None)
+    pub spark_time: Option<f64>,                     // 放電で光っている残り時間
+    pub scale: f64,                                  // 大きさ (1.0 が通常、砕けた破片は小さくなる)
+    pub impact: f64,                                 // 前回の破砕判定から受けた最大の衝撃
+    pub sleeping: bool,                              // 静止した島の一部として物理更新を止めているか
+    pub rest_anchor: (f64, f64),                     // 静止判定の基準位置
+    pub rest_time: f64,                              // 基準位置の近くに留まっている時間
+    pub sleep_contacts: u32,                         // 眠ったときに接していた固体の数
+    pub note: Option<String>,                        // ユーザーが付けたメモ
 }
 
 /// 非同期ブレンド処理の結果
 #[derive(Debug)]
 pub enum BlendResult {
-    Change { index: usize, new_dna: MaterialDNA },
-    Vanish { index: usize },
+    Change { id: u64, new_dna: MaterialDNA },
+    Vanish { id: u64 },
+    /// 反応ネットワークへの記録用 (ブレンド1回につき1つ)
+    Reaction { parents: Box<[MaterialDNA; 2]>, child: MaterialDNA, reaction_type: ReactionType },
+}
+
+/// 衝突した2つのドットをブレンドした結果 (同じ seed のドット同士はブレンドしない)
+/// ワーカースレッドと、決定論モードの step_physics の両方から使う
+pub fn blend_collision(a: &(u64, MaterialDNA), b: &(u64, MaterialDNA), mutation: Mutation) -> Vec<BlendResult> {
+    let ((id_a, dna_a), (id_b, dna_b)) = (a, b);
+    if dna_a.seed == dna_b.seed {
+        return Vec::new();
+    }
+
+    let params_a = from_dna(dna_a);
+    let params_b = from_dna(dna_b);
+
+    let reaction_type = decide_reaction_type(params_a.state, params_b.state);
+    let new_dna = dna_a.blend(dna_b, 0.5).mutated(mutation);
+
+    let mut results = vec![BlendResult::Reaction {
+        parents: Box::new([dna_a.clone(), dna_b.clone()]),
+        child: new_dna.clone(),
+        reaction_type,
+    }];
+
+    match reaction_type {
+        ReactionType::Reaction => {
+            results.push(BlendResult::Change { id: *id_a, new_dna: new_dna.clone() });
+            results.push(BlendResult::Change { id: *id_b, new_dna });
+        }
+        ReactionType::CatalyticLowChanges => {
+            let energy_a = params_a.state.get_energy_level();
+            let energy_b = params_b.state.get_energy_level();
+            if energy_a < energy_b {
+                results.push(BlendResult::Change { id: *id_a, new_dna });
+            } else {
+                results.push(BlendResult::Change { id: *id_b, new_dna });
+            }
+        }
+        ReactionType::CatalyticHighChangesAndLowVanishes => {
+            let energy_a = params_a.state.get_energy_level();
+            let energy_b = params_b.state.get_energy_level();
+            if energy_a > energy_b {
+                results.push(BlendResult::Change { id: *id_a, new_dna });
+                results.push(BlendResult::Vanish { id: *id_b });
+            } else {
+                results.push(BlendResult::Change { id: *id_b, new_dna });
+                results.push(BlendResult::Vanish { id: *id_a });
+            }
+        }
+    }
+    results
 }
 
 impl Dot {
-    // new関数は使われなくなるので削除、もしくは更新が必要
+    /// 静止した新しいドット (どのグループにも属さない)
+    pub fn new(id: u64, x: f64, y: f64, material: BaseMaterialParams, material_dna: MaterialDNA) -> Self {
+        let name = crate::naming::generate_name(&material_dna);
+        Dot {
+            id,
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            vx: 0.0,
+            vy: 0.0,
+            decay_in: decay_delay(&material_dna, id),
+            burn_time: None,
+            spark_time: None,
+            material,
+            material_dna,
+            name,
+            reaction_count: 0,
+            since_reaction: 0.0,
+            since_check: 0.0,
+            is_selected: false,
+            glow_time: None,
+            since_heat_exchange: 0.0,
+            group: None,
+            hidden: false,
+            scale: 1.0,
+            impact: 0.0,
+            sleeping: false,
+            rest_anchor: (x, y),
+            rest_time: 0.0,
+            sleep_contacts: 0,
+            note: None,
+        }
+    }
+
+    /// シミュレーション時間を dt 秒進める
+    pub fn advance_timers(&mut self, dt: f64) {
+        self.since_reaction += dt;
+        self.since_check += dt;
+        self.since_heat_exchange += dt;
+        if let Some(glow_time) = &mut self.glow_time {
+            *glow_time += dt;
+        }
+        if let Some(decay_in) = &mut self.decay_in {
+            *decay_in -= dt;
+        }
+        if let Some(burn_time) = &mut self.burn_time {
+            *burn_time += dt;
+        }
+        if let Some(spark_time) = &mut self.spark_time {
+            *spark_time -= dt;
+        }
+    }
+
+    /// 眠りから起こし、静止判定を今の位置からやり直す
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.rest_anchor = (self.x, self.y);
+        self.rest_time = 0.0;
+    }
+
+    /// 時間が経つと変化する (止まっていても物理更新を続ける必要がある) か
+    pub fn has_pending_timer(&self) -> bool {
+        self.glow_time.is_some() || self.decay_in.is_some() || self.burn_time.is_some() || self.spark_time.is_some()
+    }
+
+    /// 描画する位置。直前の物理ステップからの位置を alpha (0.0 ~ 1.0) で補間する
+    pub fn render_position(&self, alpha: f64) -> (f64, f64) {
+        (self.prev_x + (self.x - self.prev_x) * alpha, self.prev_y + (self.y - self.prev_y) * alpha)
+    }
+
+    /// 衝突判定と描画に使う半径
+    pub fn radius(&self) -> f64 {
+        DOT_RADIUS * self.scale
+    }
 }
 
 // App構造体
@@ -45,11 +217,19 @@ pub struct App {
     pub dots: Vec<Dot>,                 // ドットリスト
     pub gravity: f64,                   // 重力加速度
     pub last_time: std::time::Instant,  // 時間管理用
+    physics_accumulator: f64,           // まだ物理を進めていない時間 (PHYSICS_DT 未満)
+    render_alpha: f64,                  // 描画する位置の補間率 (前ステップ 0.0 ~ 現在 1.0)
     pub start_time: std::time::Instant, // 経過時間用
     pub physics: Physics,
 
     pub is_updating: bool,                     // 物理更新中かどうかのフラグ
     pub left_mouse_pressed: bool,              // 左クリックが押されているか
+    last_stamp: Option<(f64, f64)>,            // 直前にブラシを当てた位置 (ドラッグの向きに使う)
+    right_mouse_pressed: bool,                 // 右クリックが押されているか (ドラッグで消しゴム)
+    erase_drag_origin: Option<(f64, f64)>,     // 右ボタンを押した画面上の位置 (消しゴムになるまで)
+    erase_spared_id: Option<u64>,              // 右ボタンを押したときに選んだドット (そのストロークでは消さない)
+    erase_stroke: Vec<Dot>,                    // 今のストロークで消したドット
+    erase_history: Vec<Vec<Dot>>,              // 取り消せる消しゴムのストローク (古い順)
     pub last_dot_add_time: std::time::Instant, // 最後にドットを追加した時刻
     pub dot_add_interval: std::time::Duration, // ドット追加の間隔
     pub frame_times: std::collections::VecDeque<f64>,
@@ -57,12 +237,59 @@ pub struct App {
     pub fps: f64,
     pub brush_material: BaseMaterialParams, // 現在選択中の物質
     pub brush_seed: u64,                    // ブラシのシード
+    pub brush: Brush,                       // ブラシの形と大きさ
+    pub tool: Tool,                         // 左クリックで使うツール
     pub selected_dot_id: Option<u64>,       // マウスがクリックしたドットのID
     pub next_dot_id: u64,                   // 次に生成するドットのID
+    pub rng: GameRng,                       // 物理の乱数 (seed を指定すると決定論モード)
+    pub physics_steps: u64,                 // 進めた物理ステップ数 (リプレイの時刻)
+    pub groups: DotGroups,                  // ユーザー定義のドットグループ
+    pub convection_enabled: bool,           // 熱膨張による対流を有効にするか
+    pub thermostat: WallThermostat,         // 一定の温度を保つ壁
+    pub combustion_enabled: bool,           // 可燃性の物質が燃えるか
+    pub electricity_enabled: bool,          // 電荷と磁性による力と放電を有効にするか
+    pub weather: Weather,                   // 上端から降らせる雨・雪
+    pub drains: Vec<Drain>,                 // 入ったドットを消す領域
+    pub drain_filter: DrainFilter,          // 次に置く排水口が消すドット
+    pub conveyors: Vec<Conveyor>,           // 中のドットを一定の向きに運ぶ領域
+    pub conveyor_settings: ConveyorSettings, // 次に置くコンベアの設定
+    held_tool: Option<(Tool, f64, f64)>,    // 使っている掘る・かき混ぜるツールとその位置 (固定ステップごとに適用する)
+    region_start: Option<(f64, f64)>,       // Drain / Conveyor ツールでドラッグを始めた位置
+    pub portals: Vec<Portal>,               // 対になった円の間でドットを移す
+    pub portal_settings: PortalSettings,    // 次に置くポータルの形
+    portal_entrance: Option<(f64, f64)>,    // Portal ツールで置いた、出口がまだない入口
+    pub color_diffusion_enabled: bool,      // 隣り合う同系統の液体の色を馴染ませるか (見た目のみ)
+    pub reaction_graph: ReactionGraph,      // このセッションで起きたブレンドの記録
+    pub reaction_export_status: Option<String>, // 最後のエクスポート結果 (GUI表示用)
+    pub journal: Journal,                   // 節目の自動記録
+    pub journal_status: Option<String>,     // 最後のジャーナル書き出し結果 (GUI表示用)
+    pub scene_status: Option<String>,       // 最後のシーンの保存・読み込み結果 (GUI表示用)
+    pub macros: Macros,                     // ツール操作のマクロ
+    pub macro_status: Option<String>,       // 最後のマクロの保存結果 (GUI表示用)
+    pub material_library: MaterialLibrary,  // 名前を付けて保存した物質
+    pub material_library_status: Option<String>, // 最後の物質の保存・削除結果 (GUI表示用)
+    pub replay: ReplayMode,                 // 入力のリプレイの記録・再生
+    pub replay_status: Option<String>,      // 最後のリプレイの操作結果 (GUI表示用)
+    pub tabs: Tabs,                         // シミュレーションのタブ (表示中のタブの状態は上のフィールド)
+    pub puzzle: Option<Puzzle>,             // 錬金術パズル (None ならパズルモードではない)
+    pub presets: Vec<Preset>,               // 同梱のプリセット (Gallery)
+    pub commands: CommandRegistry,          // コマンドパレットの登録簿
+    pub log_buffer: LogBuffer,              // Log ウィンドウに表示するログ
+    pub crash_reporter: Option<CrashReporter>,
+    pub previous_crash_report: Option<std::path::PathBuf>, // 前回のセッションのクラッシュレポート (未確認)
+    pub pending_snapshot: Option<(Snapshot, u32)>, // 差分の基準になるスナップショットと残りフレーム数
 
     // 非同期処理用
     pub result_rx: mpsc::Receiver<BlendResult>, // ブレンド結果受信
 
+    pub gamepad: Gamepad, // ゲームパッド入力
+
+    // フォトモード (自由カメラ)
+    pub camera: Camera,
+    pub camera_path: CameraPath, // デモ録画用のカメラの動き
+    pub photo_mode: bool,
+    pub pan_keys: [bool; 4], // W, A, S, D が押されているか
+
     // Test features
     pub last_test_dot_add_time: std::time::Instant,
     pub test_dot_add_interval: std::time::Duration,
@@ -72,14 +299,42 @@ pub struct App {
 
 pub const WIDTH: u32 = 640;
 pub const HEIGHT: u32 = 480;
+const BLEND_CHUNK_SIZE: usize = 1024; // ブレンド結果を並列で適用するときの1タスクあたりのドット数
+pub const GRAVITY: f64 = 9.8 * 20.0; // 重力加速度の初期値
+pub const PHYSICS_DT: f64 = 1.0 / 60.0; // 物理の1ステップの時間 (固定)
+const MAX_PHYSICS_STEPS: u32 = 5; // 1フレームで進める最大ステップ数 (これを超える遅れは捨てる)
+const BRUSH_PREVIEW_SEED: u64 = 0x9057; // スプレーのプレビューに使う乱数の seed
 
 impl App {
     pub fn new(
-        collision_tx: mpsc::Sender<((usize, MaterialDNA), (usize, MaterialDNA))>,
+        collision_tx: CollisionSender,
         result_rx: mpsc::Receiver<BlendResult>,
         is_test_mode_enabled: bool,
         max_test_dots: u32,
     ) -> Self {
+        let presets = crate::presets::builtin();
+        let mut commands = CommandRegistry::default();
+        register_commands(&mut commands);
+        crate::presets::register_commands(&mut commands, &presets);
+        crate::reaction_graph::register_commands(&mut commands);
+        crate::seed_bank::register_commands(&mut commands);
+        crate::naming::register_commands(&mut commands);
+        crate::snapshot::register_commands(&mut commands);
+        crate::worldgen::register_commands(&mut commands);
+        crate::tools::register_commands(&mut commands);
+        crate::brush::register_commands(&mut commands);
+        crate::journal::register_commands(&mut commands);
+        crate::scene::register_commands(&mut commands);
+        crate::macros::register_commands(&mut commands);
+        crate::material::library::register_commands(&mut commands);
+        crate::replay::register_commands(&mut commands);
+        crate::tabs::register_commands(&mut commands);
+        camera_path::register_commands(&mut commands);
+        crate::renderer::gui::register_commands(&mut commands);
+
+        let mut physics = Physics::new(collision_tx.clone());
+        physics.interactions = crate::physics::interaction::load_config();
+
         Self {
             window: None,
 
@@ -89,15 +344,23 @@ impl App {
 
             dots: Vec::new(),
 
-            gravity: 9.8 * 20.0,
+            gravity: GRAVITY,
 
             last_time: std::time::Instant::now(),
+            physics_accumulator: 0.0,
+            render_alpha: 1.0,
             start_time: std::time::Instant::now(),
-            physics: Physics::new(collision_tx.clone()),
+            physics,
 
             is_updating: false,
 
             left_mouse_pressed: false,
+            last_stamp: None,
+            right_mouse_pressed: false,
+            erase_drag_origin: None,
+            erase_spared_id: None,
+            erase_stroke: Vec::new(),
+            erase_history: Vec::new(),
 
             last_dot_add_time: std::time::Instant::now(),
 
@@ -112,11 +375,60 @@ impl App {
             brush_material: BaseMaterialParams::default(),
 
             brush_seed: 0,
+            brush: Brush::default(),
 
             selected_dot_id: None,
             next_dot_id: 0,
+            rng: GameRng::from_entropy(),
+            physics_steps: 0,
+            groups: DotGroups::default(),
+            convection_enabled: true,
+            thermostat: WallThermostat::default(),
+            combustion_enabled: true,
+            electricity_enabled: true,
+            weather: Weather::default(),
+            drains: Vec::new(),
+            drain_filter: DrainFilter::default(),
+            conveyors: Vec::new(),
+            conveyor_settings: ConveyorSettings::default(),
+            held_tool: None,
+            region_start: None,
+            portals: Vec::new(),
+            portal_settings: PortalSettings::default(),
+            portal_entrance: None,
+            color_diffusion_enabled: true,
+            reaction_graph: ReactionGraph::default(),
+            reaction_export_status: None,
+            journal: Journal::default(),
+            journal_status: None,
+            scene_status: None,
+            macros: Macros {
+                library: crate::macros::load_library(),
+                ..Macros::default()
+            },
+            macro_status: None,
+            material_library: MaterialLibrary::load(std::path::Path::new(LIBRARY_DIR)),
+            material_library_status: None,
+            replay: ReplayMode::default(),
+            replay_status: None,
+            tabs: Tabs::default(),
+            puzzle: None,
+            presets,
+            commands,
+            log_buffer: LogBuffer::default(),
+            crash_reporter: None,
+            tool: Tool::default(),
+            pending_snapshot: None,
+            previous_crash_report: crate::crash::previous_report(),
             result_rx,
 
+            gamepad: Gamepad::new(GamepadSettings::default()),
+
+            camera: Camera::default(),
+            camera_path: CameraPath::default(),
+            photo_mode: false,
+            pan_keys: [false; 4],
+
             // Test features
             last_test_dot_add_time: std::time::Instant::now(),
             test_dot_add_interval: std::time::Duration::from_millis(1000), // 1000ms = 1秒
@@ -131,6 +443,54 @@ impl App {
         let mut rng = thread_rng();
         self.brush_seed = rng.gen();
         self.brush_material = crate::material::from_seed(self.brush_seed);
+        self.macros.record(MacroStep::SetMaterial(to_dna(&self.brush_material, self.brush_seed)));
+        self.replay.record(self.physics_steps, ReplayInput::Randomize(self.brush_seed));
+    }
+
+    /// ブラシの物質を dna にする
+    fn set_brush_material(&mut self, dna: &MaterialDNA) {
+        self.brush_material = from_dna(dna);
+        self.brush_seed = dna.seed;
+        self.macros.record(MacroStep::SetMaterial(dna.clone()));
+        self.replay.record(self.physics_steps, ReplayInput::SetMaterial(dna.clone()));
+    }
+
+    /// ドットの物質を dna に書き換える (DNA エディタから)
+    fn set_dot_material(&mut self, id: u64, dna: MaterialDNA) {
+        let Some(dot) = self.dots.iter_mut().find(|dot| dot.id == id) else {
+            return;
+        };
+        self.replay.record(self.physics_steps, ReplayInput::EditDot { id, dna: dna.clone() });
+        dot.material = from_dna(&dna);
+        dot.name = crate::naming::generate_name(&dna);
+        dot.decay_in = decay_delay(&dna, dot.id);
+        dot.material_dna = dna;
+        dot.sleeping = false;
+        tracing::info!(target: crate::logging::APP, id, name = %dot.name, "rewrote dot material");
+    }
+
+    /// 雨・雪の設定を変える (降り始めたら止まっていた物理も動かす)
+    fn set_weather(&mut self, settings: WeatherSettings) {
+        if settings.enabled && !self.weather.settings.enabled {
+            tracing::info!(target: crate::logging::APP, kind = settings.kind.label(), rate = settings.rate, "weather started");
+        }
+        self.weather.settings = settings;
+        self.is_updating |= settings.enabled;
+    }
+
+    /// ブラシの形と大きさを変える
+    fn set_brush(&mut self, brush: Brush) {
+        self.brush = brush;
+        self.macros.record(MacroStep::SetBrush(brush));
+    }
+
+    /// 新しいドットを作る (IDを払い出し、ブラシのグループに所属させる)
+    fn new_dot(&mut self, x: f64, y: f64, material: BaseMaterialParams, material_dna: MaterialDNA) -> Dot {
+        let mut dot = Dot::new(self.next_dot_id, x, y, material, material_dna);
+        dot.group = self.groups.brush_group;
+        dot.hidden = !self.groups.is_visible(dot.group);
+        self.next_dot_id += 1;
+        dot
     }
 
     fn add_random_dots(&mut self) {
@@ -144,33 +504,851 @@ impl App {
             let seed: u64 = rng.gen();
             let material = crate::material::from_seed(seed);
             let material_dna = crate::material::to_dna(&material, seed);
-            let name = crate::naming::generate_name(&material_dna);
-
-            let dot = Dot {
-                id: self.next_dot_id,
-                x,
-                y,
-                vx: 0.0,
-                vy: 0.0,
-                material,
-                material_dna,
-                name,
-                reaction_count: 0,
-                last_reaction_time: std::time::Instant::now(),
-                last_check_time: std::time::Instant::now(),
-                is_selected: false,
-                glowing_since: None,
-                last_heat_exchange_time: std::time::Instant::now(),
+
+            let dot = self.new_dot(x, y, material, material_dna);
+            self.dots.push(dot);
+        }
+        self.is_updating = true;
+    }
+
+    /// 現在のドットを消してプリセットの配置に置き換える
+    fn load_preset(&mut self, index: usize) {
+        let Some(preset) = self.presets.get(index).cloned() else {
+            return;
+        };
+        self.stop_recording_before("loading a preset");
+        self.clear_dots();
+        self.selected_dot_id = None;
+        for fill in &preset.fills {
+            let material = fill.material();
+            let material_dna = to_dna(&material, fill.seed);
+            for (x, y) in fill.positions() {
+                let dot = self.new_dot(x, y, material.clone(), material_dna.clone());
+                self.dots.push(dot);
+            }
+        }
+        self.is_updating = true;
+        self.last_time = std::time::Instant::now();
+        tracing::info!(target: crate::logging::APP, preset = %preset.name, dots = self.dots.len(), "loaded preset");
+    }
+
+    /// キャンバスを自動生成した地形で埋め直す
+    fn generate_landscape(&mut self, seed: u64) {
+        self.stop_recording_before("generating a landscape");
+        self.clear_dots();
+        self.selected_dot_id = None;
+        let materials: Vec<(Terrain, BaseMaterialParams, MaterialDNA)> = Terrain::ALL
+            .iter()
+            .map(|&terrain| {
+                let material = terrain.material();
+                let material_dna = to_dna(&material, terrain.seed());
+                (terrain, material, material_dna)
+            })
+            .collect();
+        for (x, y, terrain) in crate::worldgen::generate(seed) {
+            let Some((_, material, material_dna)) = materials.iter().find(|(t, _, _)| *t == terrain) else {
+                continue;
             };
+            let dot = self.new_dot(x, y, material.clone(), material_dna.clone());
             self.dots.push(dot);
+        }
+        self.is_updating = true;
+        self.last_time = std::time::Instant::now();
+        tracing::info!(target: crate::logging::APP, seed, dots = self.dots.len(), "generated landscape");
+    }
+
+    pub fn clear_dots(&mut self) {
+        self.replay.record(self.physics_steps, ReplayInput::Clear);
+        self.dots.clear();
+        self.rng.rewind(); // 決定論モードでは、ここから置いたものが同じなら同じ結果になる
+        self.is_updating = false;
+    }
+
+    /// GUI で操作されたグループ操作を反映する
+    fn apply_group_action(&mut self, action: GroupAction) {
+        if !matches!(action, GroupAction::Create(_) | GroupAction::SetBrushGroup(_)) {
+            self.stop_recording_before("a group change");
+        }
+        match action {
+            GroupAction::Create(name) => {
+                self.groups.create(&name);
+            }
+            GroupAction::Delete(id) => {
+                self.dots.retain(|dot| dot.group != Some(id));
+                self.groups.remove(id);
+                if !self.dots.iter().any(|dot| Some(dot.id) == self.selected_dot_id) {
+                    self.selected_dot_id = None;
+                }
+            }
+            GroupAction::SetVisible(id, visible) => {
+                if let Some(group) = self.groups.get_mut(id) {
+                    group.visible = visible;
+                }
+                for dot in self.dots.iter_mut().filter(|dot| dot.group == Some(id)) {
+                    dot.hidden = !visible;
+                }
+            }
+            GroupAction::SetFrozen(id, frozen) => {
+                if let Some(group) = self.groups.get_mut(id) {
+                    group.frozen = frozen;
+                }
+                // 解凍したドットが再び動き出すように物理更新を再開する
+                self.is_updating = true;
+            }
+            GroupAction::AssignSelected(group) => {
+                let hidden = !self.groups.is_visible(group);
+                if let Some(dot) = self.dots.iter_mut().find(|dot| Some(dot.id) == self.selected_dot_id) {
+                    dot.group = group;
+                    dot.hidden = hidden;
+                }
+                self.is_updating = true;
+            }
+            GroupAction::SetBrushGroup(group) => self.groups.brush_group = group,
+        }
+    }
+
+    /// コマンドパレットで選ばれたコマンドを実行する
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::RandomizeBrush => self.randomize_brush_material(),
+            Command::ClearDots => self.clear_dots(),
+            Command::TogglePhotoMode => self.set_photo_mode(!self.photo_mode),
+            Command::ToggleConvection => self.convection_enabled = !self.convection_enabled,
+            Command::ToggleColorDiffusion => self.color_diffusion_enabled = !self.color_diffusion_enabled,
+            Command::ToggleCombustion => self.combustion_enabled = !self.combustion_enabled,
+            Command::ToggleElectricity => self.electricity_enabled = !self.electricity_enabled,
+            Command::ToggleWeather => self.set_weather(WeatherSettings {
+                enabled: !self.weather.settings.enabled,
+                ..self.weather.settings
+            }),
+            Command::NewPuzzle => self.puzzle = Some(Puzzle::random()),
+            Command::QuitPuzzle => self.puzzle = None,
+            Command::LoadPreset(index) => self.load_preset(index),
+            Command::ExportReactions(format) => self.export_reaction_graph(format),
+            Command::ExportSeedBank => self.export_seed_bank(),
+            Command::ExportJournal => self.export_journal(),
+            Command::SaveScene => self.save_scene(std::path::Path::new(SCENE_FILE)),
+            Command::LoadScene => self.load_scene(std::path::Path::new(SCENE_FILE)),
+            Command::ImportSeedBank => self.import_seed_bank(),
+            Command::SelectTool(tool) => self.tool = tool,
+            Command::ClearDrains => self.drains.clear(),
+            Command::ClearConveyors => self.conveyors.clear(),
+            Command::ClearPortals => {
+                self.portals.clear();
+                self.portal_entrance = None;
+            }
+            Command::SetBrushShape(shape) => self.set_brush(Brush { shape, ..self.brush }),
+            Command::UndoErase => self.undo_erase(),
+            Command::CameraPath(action) => self.apply_camera_path_action(action),
+            Command::Macro(action) => self.apply_macro_action(action),
+            Command::Replay(control) => self.apply_replay_control(control),
+            Command::Tab(action) => self.apply_tab_action(action),
+            Command::MaterialLibrary(action) => self.apply_library_action(action),
+            Command::SetNamingStyle(style) => self.set_naming_style(style),
+            Command::GenerateLandscape => self.generate_landscape(thread_rng().gen()),
+            Command::SnapshotDiff => {
+                let snapshot = Snapshot::capture(&self.dots, self.start_time.elapsed().as_secs_f64());
+                self.pending_snapshot = Some((snapshot, crate::snapshot::DIFF_FRAMES));
+                tracing::info!(target: crate::logging::APP, frames = crate::snapshot::DIFF_FRAMES, "snapshot taken");
+            }
+            // GUI 側で処理済み
+            Command::ToggleGallery
+            | Command::ToggleReactionGraph
+            | Command::ToggleMaterialCompare
+            | Command::ToggleDnaEditor
+            | Command::ToggleFlowField
+            | Command::ToggleSkipComposite
+            | Command::ToggleLog
+            | Command::ToggleCameraPath
+            | Command::ToggleNotes
+            | Command::ToggleJournal
+            | Command::ToggleMacros
+            | Command::ToggleMaterialLibrary => {}
+        }
+    }
+
+    /// 命名スタイルを切り替え、既存のドット・反応ネットワーク・パズルの名前を付け直す
+    fn set_naming_style(&mut self, style: crate::naming::NamingStyle) {
+        crate::naming::set_style(style);
+        for dot in self.dots.iter_mut() {
+            dot.name = crate::naming::generate_name(&dot.material_dna);
+        }
+        self.reaction_graph.rename_all();
+        if let Some(puzzle) = &mut self.puzzle {
+            puzzle.target_name = crate::naming::generate_name(&puzzle.target);
+        }
+        tracing::info!(target: crate::logging::APP, style = style.label(), "naming style changed");
+    }
+
+    /// カメラパスの操作。再生はフォトモードで行う (HUD を隠して録画できるように)
+    fn apply_camera_path_action(&mut self, action: CameraPathAction) {
+        let path = std::path::Path::new(camera_path::PATH_FILE);
+        match action {
+            CameraPathAction::AddKeyframe => {
+                self.camera_path.add_keyframe(&self.camera);
+                tracing::info!(target: crate::logging::APP, keyframes = self.camera_path.keyframes().len(), "camera keyframe added");
+            }
+            CameraPathAction::RemoveKeyframe(index) => self.camera_path.remove_keyframe(index),
+            CameraPathAction::SetKeyframeTime(index, time) => self.camera_path.set_keyframe_time(index, time),
+            CameraPathAction::Play => {
+                if self.camera_path.play() {
+                    self.photo_mode = true;
+                } else {
+                    tracing::warn!(target: crate::logging::APP, "camera path needs at least two keyframes");
+                }
+            }
+            CameraPathAction::Stop => self.camera_path.stop(),
+            CameraPathAction::SetLooping(looping) => self.camera_path.looping = looping,
+            CameraPathAction::Clear => self.camera_path.clear(),
+            CameraPathAction::Save => match self.camera_path.save(path) {
+                Ok(()) => tracing::info!(target: crate::logging::APP, path = %path.display(), "saved camera path"),
+                Err(error) => tracing::warn!(target: crate::logging::APP, path = %path.display(), "camera path save failed: {}", error),
+            },
+            CameraPathAction::Load => match self.camera_path.load(path) {
+                Ok(()) => tracing::info!(target: crate::logging::APP, path = %path.display(), "loaded camera path"),
+                Err(error) => tracing::warn!(target: crate::logging::APP, path = %path.display(), "camera path load failed: {}", error),
+            },
+        }
+    }
+
+    /// マクロの記録・再生・編集。一覧が変わったらユーザーの設定ディレクトリに保存する
+    fn apply_macro_action(&mut self, action: MacroAction) {
+        match action {
+            MacroAction::StartRecording => {
+                let material = to_dna(&self.brush_material, self.brush_seed);
+                self.macros.recording = Some(MacroRecorder::new(material, self.brush));
+                self.macro_status = Some("Recording...".to_string());
+                return;
+            }
+            MacroAction::StopRecording => match self.macros.stop_recording() {
+                Some(recorded) => {
+                    tracing::info!(target: crate::logging::APP, name = %recorded.name, stamps = recorded.stamp_count(), "recorded macro");
+                }
+                None => {
+                    self.macro_status = Some("Nothing recorded".to_string());
+                    return;
+                }
+            },
+            MacroAction::Play(index) => {
+                let (x, y) = self.camera.screen_to_world((WIDTH as f64 / 2.0, HEIGHT as f64 / 2.0));
+                self.play_macro(index, x, y);
+                return;
+            }
+            MacroAction::SetScale(scale) => {
+                self.macros.scale = scale.clamp(crate::macros::MIN_SCALE, crate::macros::MAX_SCALE);
+                return;
+            }
+            MacroAction::Rename(index, name) => {
+                if let Some(recorded) = self.macros.library.macros.get_mut(index) {
+                    recorded.name = name;
+                }
+            }
+            MacroAction::SetHotkey(index, key) => self.macros.library.assign_key(index, key),
+            MacroAction::Delete(index) => {
+                if index < self.macros.library.macros.len() {
+                    self.macros.library.macros.remove(index);
+                }
+            }
+        }
+
+        let path = crate::macros::library_path();
+        self.macro_status = Some(match self.macros.library.save(&path) {
+            Ok(()) => format!("Saved to {}", path.display()),
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, path = %path.display(), "macro save failed: {}", error);
+                format!("Save failed: {}", error)
+            }
+        });
+    }
+
+    /// ゲームパッドのデッドゾーンとボタンの割り当てを保存する
+    fn save_gamepad_settings(&self) {
+        let path = crate::gamepad::settings_path();
+        if let Err(error) = self.gamepad.settings().save(&path) {
+            tracing::warn!(target: crate::logging::APP, path = %path.display(), "gamepad settings save failed: {}", error);
+        }
+    }
+
+    /// index のマクロを (x, y) を原点に再生し、ブラシを再生前の状態に戻す
+    fn play_macro(&mut self, index: usize, x: f64, y: f64) {
+        let Some(recorded) = self.macros.library.macros.get(index) else {
+            return;
+        };
+        let steps: Vec<MacroStep> = recorded.placed(x, y, self.macros.scale).collect();
+        let name = recorded.name.clone();
+        let saved = (self.brush_material.clone(), self.brush_seed, self.brush);
+        let recording = self.macros.recording.take(); // 再生した操作は記録しない
+        let first_new = self.dots.len();
+
+        for step in steps {
+            match step {
+                MacroStep::Stamp { x, y } => self.stamp_brush(x.floor(), y.floor()),
+                MacroStep::SetMaterial(dna) => {
+                    self.brush_material = from_dna(&dna);
+                    self.brush_seed = dna.seed;
+                }
+                MacroStep::SetBrush(brush) => self.brush = brush,
+            }
+        }
+
+        // 再生中に置いたドットは空間グリッドに入っていないので、重なったものをここで取り除く
+        let cell = DOT_RADIUS * 1.5;
+        let mut occupied: HashMap<(i64, i64), Vec<(f64, f64)>> = HashMap::new();
+        for dot in self.dots.split_off(first_new) {
+            let (cx, cy) = ((dot.x / cell).floor() as i64, (dot.y / cell).floor() as i64);
+            let overlaps = (-1..=1)
+                .flat_map(|i| (-1..=1).map(move |j| (cx + i, cy + j)))
+                .filter_map(|key| occupied.get(&key))
+                .flatten()
+                .any(|&(x, y)| (x - dot.x).powi(2) + (y - dot.y).powi(2) < cell * cell);
+            if !overlaps {
+                occupied.entry((cx, cy)).or_default().push((dot.x, dot.y));
+                self.dots.push(dot);
+            }
+        }
+
+        self.macros.recording = recording;
+        (self.brush_material, self.brush_seed, self.brush) = saved;
+        self.last_stamp = None;
+        tracing::info!(target: crate::logging::APP, name = %name, dots = self.dots.len() - first_new, "played macro");
+    }
+
+    /// 物質のライブラリの保存・使用・削除 (ファイルは LIBRARY_DIR に1物質1ファイル)
+    fn apply_library_action(&mut self, action: LibraryAction) {
+        let dir = std::path::Path::new(LIBRARY_DIR);
+        let (name, params, dna) = match action {
+            LibraryAction::SaveBrush(name) => (name, self.brush_material.clone(), to_dna(&self.brush_material, self.brush_seed)),
+            LibraryAction::SaveSelected(name) => {
+                let Some(dot) = self.dots.iter().find(|dot| Some(dot.id) == self.selected_dot_id) else {
+                    self.material_library_status = Some("No dot selected".to_string());
+                    return;
+                };
+                (name, dot.material.clone(), dot.material_dna.clone())
+            }
+            LibraryAction::Use(index) => {
+                if let Some(preset) = self.material_library.presets.get(index) {
+                    let (params, dna, name) = (preset.params.clone(), preset.dna.clone(), preset.name.clone());
+                    self.set_brush_material(&dna);
+                    self.brush_material = params; // 保存したときの特性をそのまま使う
+                    self.material_library_status = Some(format!("Brush: {}", name));
+                }
+                return;
+            }
+            LibraryAction::Duplicate(index) | LibraryAction::Rename(index, _) => {
+                let result = match &action {
+                    LibraryAction::Rename(_, name) => self.material_library.rename(dir, index, name),
+                    _ => self.material_library.duplicate(dir, index),
+                };
+                self.material_library_status = Some(match result {
+                    Ok(path) => {
+                        tracing::info!(target: crate::logging::APP, path = %path.display(), "saved material preset");
+                        format!("Saved to {}", path.display())
+                    }
+                    Err(error) => {
+                        tracing::warn!(target: crate::logging::APP, "material preset save failed: {}", error);
+                        format!("Save failed: {}", error)
+                    }
+                });
+                return;
+            }
+            LibraryAction::Delete(index) => {
+                let in_use = self
+                    .material_library
+                    .presets
+                    .get(index)
+                    .is_some_and(|preset| preset.is_material(&to_dna(&self.brush_material, self.brush_seed)));
+                if in_use {
+                    self.material_library_status = Some("The brush is using this material".to_string());
+                    return;
+                }
+                self.material_library_status = Some(match self.material_library.remove(dir, index) {
+                    Ok(preset) => {
+                        tracing::info!(target: crate::logging::APP, name = %preset.name, "deleted material preset");
+                        format!("Deleted {}", preset.name)
+                    }
+                    Err(error) => {
+                        tracing::warn!(target: crate::logging::APP, "material preset delete failed: {}", error);
+                        format!("Delete failed: {}", error)
+                    }
+                });
+                return;
+            }
+            LibraryAction::UndoDelete => {
+                self.material_library_status = match self.material_library.restore(dir) {
+                    Some(Ok(name)) => {
+                        tracing::info!(target: crate::logging::APP, name = %name, "restored material preset");
+                        Some(format!("Restored {}", name))
+                    }
+                    Some(Err(error)) => {
+                        tracing::warn!(target: crate::logging::APP, "material preset restore failed: {}", error);
+                        Some(format!("Restore failed: {}", error))
+                    }
+                    None => Some("Nothing to undo".to_string()),
+                };
+                return;
+            }
+            LibraryAction::Reload => {
+                let trash = std::mem::take(&mut self.material_library.trash);
+                self.material_library = MaterialLibrary::load(dir);
+                self.material_library.trash = trash;
+                self.material_library_status = Some(format!("Loaded {} materials", self.material_library.presets.len()));
+                return;
+            }
+        };
+
+        // 名前が空なら物質の名前で保存する
+        let name = match name.trim() {
+            "" => crate::naming::generate_name(&dna),
+            name => name.to_string(),
+        };
+        self.material_library_status = Some(match self.material_library.save(dir, MaterialPreset::new(name.clone(), params, dna)) {
+            Ok(path) => {
+                tracing::info!(target: crate::logging::APP, path = %path.display(), name = %name, "saved material preset");
+                format!("Saved to {}", path.display())
+            }
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, name = %name, "material preset save failed: {}", error);
+                format!("Save failed: {}", error)
+            }
+        });
+    }
+
+    /// シミュレーションのタブの追加・切り替え・削除
+    fn apply_tab_action(&mut self, action: TabAction) {
+        match action {
+            TabAction::New => {
+                if self.tabs.tabs.len() >= crate::tabs::MAX_TABS {
+                    tracing::warn!(target: crate::logging::APP, max = crate::tabs::MAX_TABS, "too many simulation tabs");
+                    return;
+                }
+                let mut physics = Physics::new(self.physics.worker_sender());
+                physics.interactions = self.physics.interactions;
+                let rng = self.rng.seed().map_or_else(GameRng::from_entropy, GameRng::seeded);
+                let (name, serial) = self.tabs.next_serial();
+                tracing::info!(target: crate::logging::APP, name = %name, "simulation tab created");
+                self.tabs.tabs.push(SimTab {
+                    name,
+                    state: Some(SimState::new(physics, GRAVITY, rng, serial)),
+                });
+                self.switch_tab(self.tabs.tabs.len() - 1);
+            }
+            TabAction::Switch(index) => self.switch_tab(index),
+            TabAction::Next => self.switch_tab((self.tabs.active + 1) % self.tabs.tabs.len()),
+            TabAction::Close(index) => self.close_tab(index),
+            TabAction::CloseActive => self.close_tab(self.tabs.active),
+            TabAction::SetBackgroundRunning(running) => self.tabs.background_running = running,
+        }
+    }
+
+    /// index のタブを表示する (今のタブの状態はタブに戻す)
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.tabs.active || index >= self.tabs.tabs.len() {
+            return;
+        }
+        self.finish_erase_stroke();
+        // リプレイは表示中のタブの入力なので、タブを変えたら終える
+        if !matches!(self.replay, ReplayMode::Idle) {
+            self.replay = ReplayMode::Idle;
+            self.replay_status = Some("Replay stopped (switched tabs)".to_string());
+        }
+        let Some(mut state) = self.tabs.tabs[index].state.take() else {
+            return;
+        };
+        self.swap_simulation(&mut state);
+        self.tabs.tabs[self.tabs.active].state = Some(state);
+        self.tabs.active = index;
+        self.render_alpha = 1.0;
+        self.last_time = std::time::Instant::now();
+    }
+
+    /// index のタブを閉じる (最後の1つは閉じない)
+    fn close_tab(&mut self, index: usize) {
+        if self.tabs.tabs.len() <= 1 || index >= self.tabs.tabs.len() {
+            return;
+        }
+        if index == self.tabs.active {
+            self.switch_tab(if index == 0 { 1 } else { index - 1 });
+        }
+        let closed = self.tabs.tabs.remove(index);
+        if index < self.tabs.active {
+            self.tabs.active -= 1;
+        }
+        tracing::info!(target: crate::logging::APP, name = %closed.name, "simulation tab closed");
+    }
+
+    /// 表示中のシミュレーションの状態と state を入れ替える
+    fn swap_simulation(&mut self, state: &mut SimState) {
+        std::mem::swap(&mut self.dots, &mut state.dots);
+        std::mem::swap(&mut self.physics, &mut state.physics);
+        std::mem::swap(&mut self.gravity, &mut state.gravity);
+        std::mem::swap(&mut self.is_updating, &mut state.is_updating);
+        std::mem::swap(&mut self.physics_accumulator, &mut state.physics_accumulator);
+        std::mem::swap(&mut self.next_dot_id, &mut state.next_dot_id);
+        std::mem::swap(&mut self.rng, &mut state.rng);
+        std::mem::swap(&mut self.physics_steps, &mut state.physics_steps);
+        std::mem::swap(&mut self.groups, &mut state.groups);
+        std::mem::swap(&mut self.selected_dot_id, &mut state.selected_dot_id);
+        std::mem::swap(&mut self.erase_history, &mut state.erase_history);
+        std::mem::swap(&mut self.thermostat, &mut state.thermostat);
+        std::mem::swap(&mut self.weather, &mut state.weather);
+        std::mem::swap(&mut self.drains, &mut state.drains);
+        std::mem::swap(&mut self.conveyors, &mut state.conveyors);
+        std::mem::swap(&mut self.portals, &mut state.portals);
+        std::mem::swap(&mut self.portal_entrance, &mut state.portal_entrance);
+        std::mem::swap(&mut self.pending_snapshot, &mut state.pending_snapshot);
+    }
+
+    /// 裏のタブを BACKGROUND_SPEED の速さで進める (1フレームに最大1ステップ)
+    fn tick_background_tabs(&mut self, frame_dt: f64) {
+        if !self.tabs.background_running {
+            return;
+        }
+        for index in 0..self.tabs.tabs.len() {
+            let Some(mut state) = self.tabs.tabs[index].state.take() else {
+                continue;
+            };
+            self.swap_simulation(&mut state);
+            if self.is_updating {
+                self.physics_accumulator += frame_dt.min(PHYSICS_DT) * crate::tabs::BACKGROUND_SPEED;
+                if self.physics_accumulator >= PHYSICS_DT {
+                    self.physics_accumulator -= PHYSICS_DT;
+                    self.step_physics(PHYSICS_DT);
+                    self.weather.update(&mut self.dots, PHYSICS_DT, &mut self.next_dot_id, &mut self.rng);
+                    self.physics_steps += 1;
+                    self.update_decay();
+                    if shatter::shatter(&mut self.dots, &mut self.next_dot_id, &mut self.rng) > 0 {
+                        self.is_updating = true;
+                    }
+                }
+            }
+            self.swap_simulation(&mut state);
+            self.tabs.tabs[index].state = Some(state);
+        }
+    }
+
+    /// リプレイの記録・再生の開始と終了
+    fn apply_replay_control(&mut self, control: ReplayControl) {
+        let path = std::path::Path::new(REPLAY_FILE);
+        match control {
+            ReplayControl::StartRecording => {
+                // 同じ状態から再生できるように、空のワールドと決定論モードの乱数から始める
+                let seed = self.rng.seed().unwrap_or_else(|| thread_rng().gen());
+                self.restart_deterministic(seed);
+                let mut recorder = ReplayRecorder::new(seed);
+                recorder.record(0, ReplayInput::SetMaterial(to_dna(&self.brush_material, self.brush_seed)));
+                recorder.record(0, ReplayInput::SetBrush(self.brush));
+                self.replay = ReplayMode::Recording(Box::new(recorder));
+                self.record_world_state();
+                tracing::info!(target: crate::logging::APP, seed, "replay recording started");
+                self.replay_status = Some(format!("Recording (seed {})", seed));
+            }
+            ReplayControl::StopRecording => {
+                let ReplayMode::Recording(recorder) = std::mem::take(&mut self.replay) else {
+                    return;
+                };
+                let replay = recorder.finish();
+                self.replay_status = Some(match replay.save(path) {
+                    Ok(()) => {
+                        tracing::info!(target: crate::logging::APP, path = %path.display(), events = replay.events.len(), "saved replay");
+                        format!("Saved {} inputs to {}", replay.events.len(), path.display())
+                    }
+                    Err(error) => {
+                        tracing::warn!(target: crate::logging::APP, path = %path.display(), "replay save failed: {}", error);
+                        format!("Save failed: {}", error)
+                    }
+                });
+            }
+            ReplayControl::Play => self.play_replay(path),
+            ReplayControl::StopPlayback => {
+                if self.replay.is_playing() {
+                    self.replay = ReplayMode::Idle;
+                    self.replay_status = Some("Playback stopped".to_string());
+                }
+            }
+        }
+    }
+
+    /// 記録中なら、記録できない操作 (input) の直前で記録を終えて保存する
+    fn stop_recording_before(&mut self, input: &str) {
+        if !self.replay.is_recording() {
+            return;
+        }
+        tracing::warn!(target: crate::logging::APP, input, "replay recording stopped before an unrecorded input");
+        self.apply_replay_control(ReplayControl::StopRecording);
+        if let Some(status) = &mut self.replay_status {
+            status.push_str(&format!(" (stopped: {} is not recorded)", input));
+        }
+    }
+
+    /// 物理に効く今の設定
+    fn replay_settings(&self) -> ReplaySettings {
+        ReplaySettings {
+            convection: self.convection_enabled,
+            combustion: self.combustion_enabled,
+            electricity: self.electricity_enabled,
+            thermostat: self.thermostat,
+            weather: self.weather.settings,
+            weather_material: self.weather.material.clone(),
+            interactions: self.physics.interactions,
+            mutation: crate::material::mutation::settings(),
+        }
+    }
+
+    /// 記録中なら、前回から変わった物理の設定と領域を記録する (フレームの最初、物理を進める前に呼ぶ)
+    fn record_world_state(&mut self) {
+        if !self.replay.is_recording() {
+            return;
+        }
+        let settings = self.replay_settings();
+        if let ReplayMode::Recording(recorder) = &mut self.replay {
+            recorder.record_world(self.physics_steps, settings, &self.drains, &self.conveyors, &self.portals);
+        }
+    }
+
+    /// 掘る・かき混ぜるツールを使う位置を変える (記録中なら変わったときに記録する)
+    fn set_held_tool(&mut self, held_tool: Option<(Tool, f64, f64)>) {
+        if held_tool == self.held_tool {
+            return;
+        }
+        let input = match held_tool {
+            Some((tool, x, y)) => ReplayInput::HoldTool { tool, x, y },
+            None => ReplayInput::ReleaseTool,
+        };
+        self.replay.record(self.physics_steps, input);
+        self.held_tool = held_tool;
+    }
+
+    /// リプレイファイルを読み込み、空のワールドから再生を始める
+    pub fn play_replay(&mut self, path: &std::path::Path) {
+        let replay = match Replay::load(path) {
+            Ok(replay) => replay,
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, path = %path.display(), "replay load failed: {}", error);
+                self.replay_status = Some(format!("Load failed: {}", error));
+                return;
+            }
+        };
+        tracing::info!(
+            target: crate::logging::APP,
+            path = %path.display(),
+            seed = replay.seed,
+            events = replay.events.len(),
+            steps = replay.duration_steps(),
+            "replay playback started"
+        );
+        self.replay = ReplayMode::Idle; // 記録中なら破棄する
+        self.restart_deterministic(replay.seed);
+        self.replay = ReplayMode::Playing(ReplayPlayer::new(replay));
+        self.replay_status = Some(format!("Playing {}", path.display()));
+    }
+
+    /// ドットを消し、seed の決定論モードでステップ数 0 からやり直す
+    fn restart_deterministic(&mut self, seed: u64) {
+        self.clear_dots();
+        self.rng = GameRng::seeded(seed);
+        self.physics_steps = 0;
+        // ID は崩壊までの時間とブレンドの順に効くので、タブの範囲の先頭から払い出し直す
+        // (ワーカーから遅れて届く前の ID への結果は、同期ブレンドのタブには適用しない)
+        self.next_dot_id = crate::tabs::id_range_start(self.next_dot_id);
+        self.physics.set_synchronous_blends(true);
+        self.selected_dot_id = None;
+        self.erase_stroke.clear();
+        self.erase_history.clear();
+        self.held_tool = None;
+        self.drains.clear();
+        self.conveyors.clear();
+        self.portals.clear();
+        self.portal_entrance = None;
+    }
+
+    /// 再生中なら、今のステップまでに実行するはずの入力を実行する
+    fn apply_due_replay_inputs(&mut self) {
+        loop {
+            let ReplayMode::Playing(player) = &mut self.replay else {
+                return;
+            };
+            let Some(input) = player.next_due(self.physics_steps) else {
+                if player.is_finished() {
+                    self.replay = ReplayMode::Idle;
+                    tracing::info!(target: crate::logging::APP, steps = self.physics_steps, "replay playback finished");
+                    self.replay_status = Some("Playback finished".to_string());
+                }
+                return;
+            };
+            match input {
+                ReplayInput::Stamp { x, y } => self.stamp_brush(x, y),
+                ReplayInput::SetMaterial(dna) => {
+                    self.brush_material = from_dna(&dna);
+                    self.brush_seed = dna.seed;
+                }
+                ReplayInput::SetBrush(brush) => self.brush = brush,
+                ReplayInput::Randomize(seed) => {
+                    self.brush_seed = seed;
+                    self.brush_material = crate::material::from_seed(seed);
+                }
+                ReplayInput::Clear => self.clear_dots(),
+                ReplayInput::Erase { x, y, radius, spare } => self.erase_at(x, y, radius, spare),
+                ReplayInput::FinishErase => self.finish_erase_stroke(),
+                ReplayInput::UndoErase => self.undo_erase(),
+                ReplayInput::HoldTool { tool, x, y } => {
+                    self.tool = tool;
+                    self.held_tool = Some((tool, x, y));
+                    self.is_updating = true;
+                }
+                ReplayInput::ReleaseTool => self.held_tool = None,
+                ReplayInput::EditDot { id, dna } => self.set_dot_material(id, dna),
+                ReplayInput::SetDrains(drains) => self.drains = drains,
+                ReplayInput::SetConveyors(conveyors) => self.conveyors = conveyors,
+                ReplayInput::SetPortals(portals) => self.portals = portals,
+                ReplayInput::SetSettings(settings) => {
+                    self.convection_enabled = settings.convection;
+                    self.combustion_enabled = settings.combustion;
+                    self.electricity_enabled = settings.electricity;
+                    self.thermostat = settings.thermostat;
+                    self.weather.settings = settings.weather;
+                    self.weather.material = settings.weather_material;
+                    self.physics.interactions = settings.interactions;
+                    crate::material::mutation::set_settings(settings.mutation);
+                }
+            }
+        }
+    }
+
+    /// 反応ネットワークをカレントディレクトリに書き出す
+    fn export_reaction_graph(&mut self, format: ExportFormat) {
+        let path = std::path::PathBuf::from(format!("reaction_graph.{}", format.extension()));
+        self.reaction_export_status = Some(match self.reaction_graph.export(&path, format) {
+            Ok(()) => {
+                tracing::info!(target: crate::logging::APP, path = %path.display(), "exported reaction graph");
+                format!("Exported to {}", path.display())
+            }
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, path = %path.display(), "reaction graph export failed: {}", error);
+                format!("Export failed: {}", error)
+            }
+        });
+    }
+
+    /// すべてのドットとグループをファイルに保存する
+    pub fn save_scene(&mut self, path: &std::path::Path) {
+        let scene = Scene::capture(&self.dots, &self.groups);
+        self.scene_status = Some(match scene.save(path) {
+            Ok(()) => {
+                tracing::info!(target: crate::logging::APP, path = %path.display(), dots = scene.dots.len(), "saved scene");
+                format!("Saved {} dots to {}", scene.dots.len(), path.display())
+            }
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, path = %path.display(), "scene save failed: {}", error);
+                format!("Save failed: {}", error)
+            }
+        });
+    }
+
+    /// 保存したシーンで今のドットとグループを置き換える
+    pub fn load_scene(&mut self, path: &std::path::Path) {
+        let scene = match Scene::load(path) {
+            Ok(scene) => scene,
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, path = %path.display(), "scene load failed: {}", error);
+                self.scene_status = Some(format!("Load failed: {}", error));
+                return;
+            }
+        };
+        self.stop_recording_before("loading a scene");
+        self.clear_dots();
+        self.selected_dot_id = None;
+        self.erase_history.clear();
+        self.groups = scene.groups;
+        for saved in scene.dots {
+            let mut dot = saved.into_dot(self.next_dot_id);
+            dot.hidden = !self.groups.is_visible(dot.group);
             self.next_dot_id += 1;
+            self.dots.push(dot);
+        }
+        self.is_updating = true;
+        self.last_time = std::time::Instant::now();
+        tracing::info!(target: crate::logging::APP, path = %path.display(), dots = self.dots.len(), "loaded scene");
+        self.scene_status = Some(format!("Loaded {} dots from {}", self.dots.len(), path.display()));
+    }
+
+    /// ジャーナルを Markdown に書き出す
+    fn export_journal(&mut self) {
+        self.journal_status = Some(match self.journal.export_markdown() {
+            Ok(path) => {
+                tracing::info!(target: crate::logging::APP, path = %path.display(), entries = self.journal.entries.len(), "exported journal");
+                format!("Exported to {}", path.display())
+            }
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, "journal export failed: {}", error);
+                format!("Export failed: {}", error)
+            }
+        });
+    }
+
+    /// 発見した物質のパレットをカレントディレクトリに書き出す
+    fn export_seed_bank(&mut self) {
+        let path = std::path::Path::new(SEED_BANK_FILE);
+        let bank = SeedBank::from_graph(&self.reaction_graph);
+        self.reaction_export_status = Some(match bank.save(path) {
+            Ok(()) => {
+                tracing::info!(target: crate::logging::APP, path = %path.display(), materials = bank.materials.len(), "exported seed bank");
+                format!("Exported {} materials to {}", bank.materials.len(), path.display())
+            }
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, path = %path.display(), "seed bank export failed: {}", error);
+                format!("Export failed: {}", error)
+            }
+        });
+    }
+
+    /// 状態の組み合わせごとの衝突処理を変更し、設定ファイルに保存する
+    fn set_interactions(&mut self, matrix: InteractionMatrix) {
+        self.physics.interactions = matrix;
+        let path = std::path::Path::new(INTERACTIONS_FILE);
+        match matrix.save(path) {
+            Ok(()) => tracing::info!(target: crate::logging::APP, path = %path.display(), "saved interaction matrix"),
+            Err(error) => tracing::warn!(target: crate::logging::APP, path = %path.display(), "interaction matrix save failed: {}", error),
+        }
+    }
+
+    /// カレントディレクトリのパレットを読み込み、まだ発見していない物質だけを追加する
+    fn import_seed_bank(&mut self) {
+        let path = std::path::Path::new(SEED_BANK_FILE);
+        self.reaction_export_status = Some(match SeedBank::load(path) {
+            Ok(bank) => {
+                let total = bank.materials.len();
+                let added = bank.merge_into(&mut self.reaction_graph);
+                tracing::info!(target: crate::logging::APP, path = %path.display(), added, total, "imported seed bank");
+                format!("Imported {} new of {} materials", added, total)
+            }
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, path = %path.display(), "seed bank import failed: {}", error);
+                format!("Import failed: {}", error)
+            }
+        });
+    }
+
+    /// 待機中のスナップショットのフレーム数を進め、期限が来たら差分をカレントディレクトリに書き出す
+    fn update_snapshot_diff(&mut self) {
+        let Some((_, frames_left)) = &mut self.pending_snapshot else {
+            return;
+        };
+        *frames_left = frames_left.saturating_sub(1);
+        if *frames_left > 0 {
+            return;
         }
-        self.is_updating = true;
-    }
+        let Some((before, _)) = self.pending_snapshot.take() else {
+            return;
+        };
 
-    pub fn clear_dots(&mut self) {
-        self.dots.clear();
-        self.is_updating = false;
+        let after = Snapshot::capture(&self.dots, self.start_time.elapsed().as_secs_f64());
+        let path = std::path::Path::new(crate::snapshot::REPORT_FILE);
+        match std::fs::write(path, before.diff_report(&after)) {
+            Ok(()) => tracing::info!(target: crate::logging::APP, path = %path.display(), "wrote snapshot diff"),
+            Err(error) => {
+                tracing::warn!(target: crate::logging::APP, path = %path.display(), "snapshot diff failed: {}", error)
+            }
+        }
     }
 
     pub fn handle_window_event(
@@ -187,29 +1365,28 @@ impl App {
         }
     }
 
-    pub fn add_dot_if_not_exists(&mut self, x: i32, y: i32) {
+    /// (x, y) にブラシを1回当て、ブラシの物質のドットを置く
+    /// 範囲を持つブラシでは、既にドットがある場所と画面の外には置かない
+    pub fn stamp_brush(&mut self, x: f64, y: f64) {
         let material_dna = to_dna(&self.brush_material, self.brush_seed);
-        let name = crate::naming::generate_name(&material_dna);
-
-        let dot = Dot {
-            id: self.next_dot_id,
-            x: x as f64,
-            y: y as f64,
-            vx: 0.0,
-            vy: 0.0,
-            material: self.brush_material.clone(), // ブラシの物質を適用
-            material_dna,
-            name,
-            reaction_count: 0,
-            last_reaction_time: std::time::Instant::now(),
-            last_check_time: std::time::Instant::now(),
-            is_selected: false,
-            glowing_since: None,
-            last_heat_exchange_time: std::time::Instant::now(),
-        };
+        self.macros.record_stamp(x, y);
+        if let ReplayMode::Recording(recorder) = &mut self.replay {
+            recorder.record_stamp(self.physics_steps, x, y, material_dna.clone(), self.brush);
+        }
 
-        self.dots.push(dot);
-        self.next_dot_id += 1;
+        let drag = self.last_stamp.replace((x, y)).map(|(last_x, last_y)| (x - last_x, y - last_y));
+        let (vx, vy) = self.brush.launch_velocity(drag);
+        let mut material = self.brush_material.clone();
+        material.temperature = (material.temperature + self.brush.temperature_offset).clamp(-1.0, 1.0);
+
+        let positions = self.brush.positions(x, y, &mut self.rng);
+        for (x, y) in self.placeable_positions(positions) {
+            // ブラシの物質を適用
+            let mut dot = self.new_dot(x, y, material.clone(), material_dna.clone());
+            dot.vx = vx;
+            dot.vy = vy;
+            self.dots.push(dot);
+        }
 
         self.is_updating = true;
 
@@ -218,6 +1395,40 @@ impl App {
         self.last_dot_add_time = std::time::Instant::now();
     }
 
+    /// ブラシの位置のうち、実際にドットを置ける位置
+    /// 範囲を持つブラシでは、既にドットがある場所と画面の外を除く
+    fn placeable_positions(&mut self, positions: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+        if self.brush.radius < DOT_RADIUS {
+            return positions;
+        }
+        // 直前のステップの後に置いたり消したりしたドットも数える
+        self.physics.rebuild_grid(&self.dots);
+        positions
+            .into_iter()
+            .filter(|&(x, y)| {
+                let inside = (DOT_RADIUS..=WIDTH as f64 - DOT_RADIUS).contains(&x)
+                    && (DOT_RADIUS..=HEIGHT as f64 - DOT_RADIUS).contains(&y);
+                inside && self.physics.query_circle(&self.dots, x, y, DOT_RADIUS * 1.5).next().is_none()
+            })
+            .collect()
+    }
+
+    /// 今クリックしたときにブラシが置くドット (ブラシを使っていないときは None)
+    fn brush_preview(&mut self) -> Option<BrushPreview> {
+        if self.tool != Tool::Brush || self.photo_mode || self.left_mouse_pressed || self.replay.is_playing() {
+            return None;
+        }
+        let (x, y) = self.cursor_world_position()?;
+        // スプレーの位置は毎回変わるので、物理の乱数を消費しないよう固定の seed の見本を示す
+        let positions = self.brush.positions(x.floor(), y.floor(), &mut GameRng::seeded(BRUSH_PREVIEW_SEED));
+        Some(BrushPreview {
+            positions: self.placeable_positions(positions),
+            color: self.brush_material.get_color_rgb(),
+            radius: DOT_RADIUS,
+            state: self.brush_material.state,
+        })
+    }
+
     pub fn handle_resume(&mut self, event_loop: &winit::event_loop::EventLoopWindowTarget<()>) {
         if self.window.is_none() {
             let window = Arc::new(
@@ -246,6 +1457,128 @@ impl App {
 
     pub fn handle_cursor_moved(&mut self, position: winit::dpi::PhysicalPosition<f64>) {
         self.mouse_position = Some((position.x, position.y));
+
+        if self.right_mouse_pressed && !self.replay.is_playing() {
+            // 選択のクリックで少し動いただけでは消さない
+            if let Some((origin_x, origin_y)) = self.erase_drag_origin {
+                if (position.x - origin_x).hypot(position.y - origin_y) < crate::tools::ERASE_DRAG_THRESHOLD {
+                    return;
+                }
+                self.erase_drag_origin = None;
+            }
+            if let Some((x, y)) = self.cursor_world_position() {
+                let radius = self.brush.radius.max(crate::tools::ERASE_MIN_RADIUS);
+                self.erase_at(x, y, radius, self.erase_spared_id);
+            }
+        }
+    }
+
+    /// (x, y) の周りのドットを spare 以外消しゴムで消し、今のストロークに加える
+    fn erase_at(&mut self, x: f64, y: f64, radius: f64, spare: Option<u64>) {
+        let erased = crate::tools::erase(&mut self.dots, x, y, radius, spare);
+        if erased.is_empty() {
+            return;
+        }
+        self.replay.record(self.physics_steps, ReplayInput::Erase { x, y, radius, spare });
+        if erased.iter().any(|dot| Some(dot.id) == self.selected_dot_id) {
+            self.selected_dot_id = None;
+        }
+        self.erase_stroke.extend(erased);
+        self.is_updating = true; // 支えを失ったドットを落とす
+    }
+
+    /// 右ボタンを離したときに、消しゴムのストロークを取り消し用の履歴に積む
+    fn finish_erase_stroke(&mut self) {
+        if self.erase_stroke.is_empty() {
+            return;
+        }
+        let stroke = std::mem::take(&mut self.erase_stroke);
+        self.replay.record(self.physics_steps, ReplayInput::FinishErase);
+        tracing::info!(target: crate::logging::APP, dots = stroke.len(), "erased dots");
+        self.erase_history.push(stroke);
+        if self.erase_history.len() > crate::tools::MAX_ERASE_HISTORY {
+            self.erase_history.remove(0);
+        }
+    }
+
+    /// 直前の消しゴムのストロークで消したドットを元に戻す
+    fn undo_erase(&mut self) {
+        self.finish_erase_stroke();
+        let Some(stroke) = self.erase_history.pop() else {
+            return;
+        };
+        self.replay.record(self.physics_steps, ReplayInput::UndoErase);
+        tracing::info!(target: crate::logging::APP, dots = stroke.len(), "restored erased dots");
+        for mut dot in stroke {
+            dot.is_selected = false;
+            dot.wake();
+            self.dots.push(dot);
+        }
+        self.is_updating = true;
+    }
+
+    /// マウス位置をカメラ変換してワールド座標で返す
+    fn cursor_world_position(&self) -> Option<(f64, f64)> {
+        self.mouse_position
+            .map(|position| self.camera.screen_to_world(position))
+    }
+
+    pub fn handle_keyboard_input(&mut self, event: &winit::event::KeyEvent) {
+        use winit::keyboard::{KeyCode, PhysicalKey};
+
+        let pressed = event.state == winit::event::ElementState::Pressed;
+        let PhysicalKey::Code(code) = event.physical_key else {
+            return;
+        };
+
+        match code {
+            KeyCode::F2 if pressed && !event.repeat => self.set_photo_mode(!self.photo_mode),
+            KeyCode::Escape if pressed && self.photo_mode => self.set_photo_mode(false),
+            KeyCode::KeyW => self.pan_keys[0] = pressed,
+            KeyCode::KeyA => self.pan_keys[1] = pressed,
+            KeyCode::KeyS => self.pan_keys[2] = pressed,
+            KeyCode::KeyD => self.pan_keys[3] = pressed,
+            KeyCode::Digit1 if pressed => self.tool = Tool::Brush,
+            KeyCode::Digit2 if pressed => self.tool = Tool::Dig,
+            KeyCode::Digit3 if pressed => self.tool = Tool::Stir,
+            KeyCode::Digit4 if pressed => self.tool = Tool::Drain,
+            KeyCode::Digit5 if pressed => self.tool = Tool::Portal,
+            KeyCode::Digit6 if pressed => self.tool = Tool::Conveyor,
+            KeyCode::KeyK if pressed && !event.repeat && self.photo_mode => {
+                self.apply_camera_path_action(CameraPathAction::AddKeyframe)
+            }
+            _ if pressed && !event.repeat => {
+                let index = MacroKey::from_key_code(code).and_then(|key| self.macros.library.position_by_key(key));
+                if let (Some(index), Some((x, y))) = (index, self.cursor_world_position()) {
+                    self.play_macro(index, x, y);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn handle_mouse_wheel(&mut self, delta: winit::event::MouseScrollDelta) {
+        if !self.photo_mode {
+            return;
+        }
+        let lines = match delta {
+            winit::event::MouseScrollDelta::LineDelta(_, y) => y as f64,
+            winit::event::MouseScrollDelta::PixelDelta(position) => position.y / 40.0,
+        };
+        let anchor = self
+            .mouse_position
+            .unwrap_or((WIDTH as f64 / 2.0, HEIGHT as f64 / 2.0));
+        self.camera.zoom_at(anchor, 1.1_f64.powf(lines));
+    }
+
+    /// フォトモードの切り替え。終了時はカメラを初期位置に戻す
+    fn set_photo_mode(&mut self, enabled: bool) {
+        self.photo_mode = enabled;
+        if !enabled {
+            self.camera = Camera::default();
+            self.pan_keys = [false; 4];
+            self.camera_path.stop();
+        }
     }
 
     pub fn handle_mouse_input(
@@ -258,37 +1591,36 @@ impl App {
         match button {
             winit::event::MouseButton::Left => {
                 self.left_mouse_pressed = state == winit::event::ElementState::Pressed;
-                if self.left_mouse_pressed {
-                    if let Some((x, y)) = self.mouse_position {
-                        self.add_dot_if_not_exists(x as i32, y as i32);
+                self.last_stamp = None; // 新しいストローク
+                if self.left_mouse_pressed && self.tool == Tool::Brush && !self.replay.is_playing() {
+                    if let Some((x, y)) = self.cursor_world_position() {
+                        self.stamp_brush(x.floor(), y.floor());
                     }
                 }
+                if !self.left_mouse_pressed {
+                    self.finish_region();
+                } else if matches!(self.tool, Tool::Drain | Tool::Conveyor) {
+                    self.region_start = self.cursor_world_position();
+                } else if self.tool == Tool::Portal {
+                    self.place_portal_end();
+                }
+            }
+            winit::event::MouseButton::Right if state == winit::event::ElementState::Released => {
+                self.right_mouse_pressed = false;
+                self.finish_erase_stroke();
             }
             winit::event::MouseButton::Right => {
-                if state == winit::event::ElementState::Pressed {
-                    if let Some((x, y)) = self.mouse_position {
-                        let mut clicked_dot_id = None;
-                        // クリック位置のドットを探す
-                        for dot in self.dots.iter().rev() {
-                            let dx = dot.x - x;
-                            let dy = dot.y - y;
-                            if (dx * dx + dy * dy) < (DOT_RADIUS * DOT_RADIUS) {
-                                clicked_dot_id = Some(dot.id);
-                                break;
-                            }
-                        }
-
-                        // selected_dot_id を更新
-                        self.selected_dot_id = clicked_dot_id;
-
-                        // is_selected フラグを更新
-                        for dot in self.dots.iter_mut() {
-                            dot.is_selected = Some(dot.id) == clicked_dot_id;
-                        }
-
-                        if let Some(ref window) = self.window {
-                            window.request_redraw();
-                        }
+                self.right_mouse_pressed = true;
+                self.erase_drag_origin = self.mouse_position;
+                self.erase_spared_id = None;
+                if self.renderer.is_some() {
+                    // クリック位置のドットは GPU ピッキングの結果から得る
+                    let clicked_dot_id = self.hovered_dot().map(|dot| dot.id);
+                    self.select_dot(clicked_dot_id);
+                    self.erase_spared_id = clicked_dot_id;
+
+                    if let Some(ref window) = self.window {
+                        window.request_redraw();
                     }
                 }
             }
@@ -296,16 +1628,170 @@ impl App {
         }
     }
 
+    /// Drain / Conveyor ツールのドラッグを終えて、その矩形に排水口かコンベアを置く
+    fn finish_region(&mut self) {
+        let (Some(start), Some(end)) = (self.region_start.take(), self.cursor_world_position()) else {
+            return;
+        };
+        match self.tool {
+            Tool::Drain => {
+                let Some(drain) = Drain::from_corners(start, end, self.drain_filter.clone()) else {
+                    return;
+                };
+                tracing::info!(target: crate::logging::APP, filter = %drain.filter.label(), "placed drain");
+                self.drains.push(drain);
+            }
+            Tool::Conveyor => {
+                let Some(conveyor) = Conveyor::from_corners(start, end, self.conveyor_settings) else {
+                    return;
+                };
+                tracing::info!(target: crate::logging::APP, speed = conveyor.settings.speed, "placed conveyor");
+                self.conveyors.push(conveyor);
+            }
+            _ => return,
+        }
+        self.is_updating = true; // 止まっているドットにも作用させる
+    }
+
+    /// Portal ツールのクリック。入口がなければ入口を、あれば出口を置いてポータルにする
+    fn place_portal_end(&mut self) {
+        let Some(position) = self.cursor_world_position() else {
+            return;
+        };
+        let Some(entrance) = self.portal_entrance.take() else {
+            self.portal_entrance = Some(position);
+            return;
+        };
+        tracing::info!(target: crate::logging::APP, rotation = self.portal_settings.rotation, "placed portal");
+        self.portals.push(Portal { a: entrance, b: position, settings: self.portal_settings });
+        self.is_updating = true;
+    }
+
+    /// 描画するポータルの縁 (入口は橙、出口は青、出口待ちの入口は白)
+    fn portal_rims(&self) -> Vec<PortalRim> {
+        let rim = |center, radius, color| PortalRim { center, radius, color };
+        self.portals
+            .iter()
+            .flat_map(|portal| {
+                [
+                    rim(portal.a, portal.settings.radius, (255, 150, 60)),
+                    rim(portal.b, portal.settings.radius, (80, 170, 255)),
+                ]
+            })
+            .chain(self.portal_entrance.map(|entrance| rim(entrance, self.portal_settings.radius, (230, 230, 230))))
+            .collect()
+    }
+
+    /// GPU ピッキングで得たカーソル下のドット
+    fn hovered_dot(&self) -> Option<&Dot> {
+        let picked = self.renderer.as_ref()?.hovered_dot_id()?;
+        self.dots.iter().find(|dot| dot.id as u32 == picked)
+    }
+
+    /// 選択中のドットを変える (None で選択解除)
+    fn select_dot(&mut self, id: Option<u64>) {
+        self.selected_dot_id = id;
+        for dot in self.dots.iter_mut() {
+            dot.is_selected = Some(dot.id) == id;
+        }
+    }
+
+    /// ドットのメモを書き換える (空ならメモを消す)
+    fn set_note(&mut self, id: u64, text: &str) {
+        let Some(dot) = self.dots.iter_mut().find(|dot| dot.id == id) else {
+            return;
+        };
+        let text = text.trim();
+        dot.note = (!text.is_empty()).then(|| text.to_string());
+        tracing::info!(target: crate::logging::APP, id, note = text, "dot note updated");
+    }
+
+    /// 前回からの経過時間を固定の PHYSICS_DT ずつ進める (フレームレートに関係なく同じ結果になる)
+    /// 余った時間は render_alpha として描画時の位置の補間に使う
     pub fn update_physics(&mut self) {
         if !self.is_updating {
+            self.physics_accumulator = 0.0;
+            self.render_alpha = 1.0;
             return;
         }
 
         let now = std::time::Instant::now();
+        let frame_dt = now.duration_since(self.last_time).as_secs_f64();
+        self.last_time = now;
+
+        // 長く止まった後 (ウィンドウのドラッグなど) に大量のステップを実行しないようにする
+        self.physics_accumulator += frame_dt.min(MAX_PHYSICS_STEPS as f64 * PHYSICS_DT);
+        let mut steps = 0;
+        while self.physics_accumulator >= PHYSICS_DT && steps < MAX_PHYSICS_STEPS {
+            self.physics_accumulator -= PHYSICS_DT;
+            steps += 1;
+            self.advance_step();
+            if !self.is_updating {
+                self.physics_accumulator = 0.0;
+                break;
+            }
+        }
+        self.render_alpha = if self.is_updating { self.physics_accumulator / PHYSICS_DT } else { 1.0 };
+    }
 
-        let dt = now.duration_since(self.last_time).as_secs_f64();
+    /// 固定ステップを1つ進める (乱数を使う処理はすべてここで行い、フレームレートに左右されないようにする)
+    fn advance_step(&mut self) {
+        self.apply_due_replay_inputs();
+        self.step_physics(PHYSICS_DT);
+        self.weather.update(&mut self.dots, PHYSICS_DT, &mut self.next_dot_id, &mut self.rng);
+        self.physics_steps += 1;
+        self.update_decay();
+        self.apply_held_tool(PHYSICS_DT);
+        self.shatter_brittle_dots();
+    }
 
-        self.last_time = now;
+    /// 掘る・かき混ぜるツールを dt 秒分だけ使う
+    /// 掘るとドットが削除されるので、空間グリッドを使う処理 (step_physics) の後に呼ぶ
+    fn apply_held_tool(&mut self, dt: f64) {
+        let Some((tool, x, y)) = self.held_tool else {
+            return;
+        };
+        // ステップの後半 (燃焼・ポータル・排水口・ブレンド) や天気でドットが増減している
+        self.physics.rebuild_grid(&self.dots);
+        match tool {
+            Tool::Brush | Tool::Drain | Tool::Portal | Tool::Conveyor => {}
+            Tool::Dig => {
+                crate::tools::dig(&self.physics, &mut self.dots, x, y, dt, &mut self.rng);
+                self.is_updating = true; // 瓦礫を落とす
+            }
+            Tool::Stir => {
+                crate::tools::stir(&self.physics, &mut self.dots, x, y, dt);
+                self.is_updating = true;
+            }
+        }
+    }
+
+    /// 強い衝撃を受けたもろい固体を破片に砕く (ドットの並びが変わるのでツールの後に行う)
+    fn shatter_brittle_dots(&mut self) {
+        let shattered = shatter::shatter(&mut self.dots, &mut self.next_dot_id, &mut self.rng);
+        if shattered > 0 {
+            self.is_updating = true;
+            self.journal.record_explosion(&self.dots, self.start_time.elapsed().as_secs_f64(), shattered);
+        }
+    }
+
+    /// 物理を dt 秒だけ進める
+    fn step_physics(&mut self, dt: f64) {
+        // 決定論モードでは、ブレンドをワーカーの処理が終わる時刻に左右されないようにステップ内で解決する
+        self.physics.set_synchronous_blends(self.rng.seed().is_some());
+
+        for dot in self.dots.iter_mut() {
+            dot.prev_x = dot.x;
+            dot.prev_y = dot.y;
+        }
+
+        // 凍結グループのドットは物理更新の後で元の位置に戻す
+        let frozen: HashMap<u64, (f64, f64)> = self
+            .dots
+            .iter()
+            .filter(|dot| self.groups.is_frozen(dot.group))
+            .map(|dot| (dot.id, (dot.x, dot.y)))
+            .collect();
 
         if let Some(ref renderer) = self.renderer {
             let device = renderer.get_device();
@@ -332,19 +1818,138 @@ impl App {
 
         // GPUが利用可能でも、CPUでの衝突判定と位置更新を行う
         // 1. 状態に基づいて力を適用
-        engine::update_state(&mut self.dots, self.gravity, dt);
+        engine::update_state(&mut self.dots, self.gravity, dt, self.convection_enabled, &mut self.rng);
+        self.thermostat.apply(&mut self.dots, dt);
+        conveyor::apply(&self.conveyors, &mut self.dots, dt);
 
         // 2. 衝突判定と応答
         self.physics.update_collision(&mut self.dots, dt);
+        if self.electricity_enabled {
+            electricity::apply(&self.physics, &mut self.dots, self.physics.collision_pairs(), dt);
+        }
+        electricity::fade_sparks(&mut self.dots);
+        radioactivity::emit(&self.physics, &mut self.dots, dt, &mut self.rng);
+        if self.color_diffusion_enabled {
+            color_diffusion::diffuse_liquid_colors(&mut self.dots, self.physics.collision_pairs(), dt);
+        }
+        if self.combustion_enabled {
+            combustion::burn(&mut self.dots, self.physics.collision_pairs(), dt, &mut self.next_dot_id, &mut self.rng);
+        }
 
         // 3. 位置更新と壁との衝突
-        let all_stopped = engine::update_position(&mut self.dots, dt);
+        let all_stopped = engine::update_position(&mut self.dots, dt, &mut self.rng);
+
+        if !frozen.is_empty() {
+            for dot in self.dots.iter_mut() {
+                if let Some(&(x, y)) = frozen.get(&dot.id) {
+                    dot.x = x;
+                    dot.y = y;
+                    dot.vx = 0.0;
+                    dot.vy = 0.0;
+                }
+            }
+        }
+
+        portal::teleport(&self.portals, &mut self.dots);
+
+        // 排水口は位置を確定した後に消す (衝突候補ペアの添字が変わるため)
+        if drain::drain(&self.drains, &mut self.dots) > 0 && !self.dots.iter().any(|dot| dot.is_selected) {
+            self.selected_dot_id = None;
+        }
+
+        let collisions = self.physics.take_collisions();
+        if !collisions.is_empty() {
+            let mutation = crate::material::mutation::settings();
+            let results = collisions.iter().flat_map(|(a, b)| blend_collision(a, b, mutation)).collect();
+            let (changes, vanished) = self.group_blend_results(results);
+            apply_blend_results(&mut self.dots, &changes, &vanished);
+        }
 
-        if all_stopped && !self.dots.is_empty() {
+        // 崩壊や発光の終了を待っているドット、温度を保つ壁、降っている雨やコンベアがあれば、止まっていても時間を進める
+        let environment_active = self.thermostat.is_active() || self.weather.is_active() || !self.conveyors.is_empty();
+        if all_stopped && !self.dots.is_empty() && !self.dots.iter().any(Dot::has_pending_timer) && !environment_active {
             self.is_updating = false;
         }
     }
 
+    /// 崩壊までの時間が尽きた不安定・放射性の物質を派生物質に変化させ、熱と気体を放出する
+    fn update_decay(&mut self) {
+        let decayed: Vec<usize> = self
+            .dots
+            .iter()
+            .enumerate()
+            .filter(|(_, dot)| dot.decay_in.is_some_and(|left| left <= 0.0))
+            .map(|(i, _)| i)
+            .collect();
+        if decayed.is_empty() {
+            return;
+        }
+        // 排水口・天気・燃焼でドットが増減しているので、崩壊熱を伝える前にグリッドを作り直す
+        self.physics.rebuild_grid(&self.dots);
+
+        for i in decayed {
+            let decay = self.dots[i].material_dna.decay();
+            tracing::debug!(
+                target: crate::logging::PHYSICS,
+                dot = self.dots[i].id,
+                heat = decay.heat,
+                gas = decay.gas.is_some(),
+                "unstable material decayed"
+            );
+            let (x, y, group) = (self.dots[i].x, self.dots[i].y, self.dots[i].group);
+
+            let dot = &mut self.dots[i];
+            dot.material = from_dna(&decay.product);
+            dot.name = crate::naming::generate_name(&decay.product);
+            dot.decay_in = decay_delay(&decay.product, dot.id);
+            dot.material_dna = decay.product;
+
+            // 崩壊熱を周囲に伝える
+            let neighbors: Vec<usize> = self
+                .physics
+                .query_circle(&self.dots, x, y, DOT_RADIUS * 6.0)
+                .collect();
+            for j in neighbors {
+                let heat = if j == i { decay.heat } else { decay.heat * 0.5 };
+                let temperature = &mut self.dots[j].material.temperature;
+                *temperature = (*temperature + heat).clamp(-1.0, 1.0);
+            }
+
+            // 気体を真上に放出する
+            if let Some(gas_dna) = decay.gas {
+                let material = from_dna(&gas_dna);
+                let mut gas = self.new_dot(x, y - DOT_RADIUS * 2.0, material, gas_dna);
+                gas.vy = -30.0;
+                gas.group = group;
+                gas.hidden = !self.groups.is_visible(group);
+                self.dots.push(gas);
+            }
+
+            self.is_updating = true;
+        }
+    }
+
+    /// ブレンド結果をドットごとにまとめ (同じドットへの変更は後のものを優先する)、反応を記録する
+    fn group_blend_results(&mut self, results: Vec<BlendResult>) -> (HashMap<u64, MaterialDNA>, HashSet<u64>) {
+        let mut changes: HashMap<u64, MaterialDNA> = HashMap::new();
+        let mut vanished: HashSet<u64> = HashSet::new();
+        for result in results {
+            match result {
+                BlendResult::Change { id, new_dna } => {
+                    changes.insert(id, new_dna);
+                }
+                BlendResult::Vanish { id } => {
+                    vanished.insert(id);
+                }
+                BlendResult::Reaction { parents, child, reaction_type } => {
+                    self.journal.record_discovery(&child);
+                    self.reaction_graph.record([&parents[0], &parents[1]], &child, reaction_type);
+                }
+            }
+        }
+        (changes, vanished)
+    }
+
     pub fn handle_redraw_requested(&mut self) {
         let now = std::time::Instant::now();
 
@@ -365,49 +1970,67 @@ impl App {
             self.frame_times.pop_front();
         }
 
-        if self.left_mouse_pressed {
-            if let Some((x, y)) = self.mouse_position {
-                if now.duration_since(self.last_dot_add_time) >= self.dot_add_interval {
-                    self.add_dot_if_not_exists(x as i32, y as i32);
-                }
-            }
+        // --- ゲームパッド入力 ---
+        let pad = self.gamepad.poll();
+        if pad.stick != (0.0, 0.0) {
+            let (x, y) = self.mouse_position.unwrap_or((WIDTH as f64 / 2.0, HEIGHT as f64 / 2.0));
+            let x = (x + pad.stick.0 as f64 * CURSOR_SPEED * delta_time).clamp(0.0, WIDTH as f64);
+            let y = (y + pad.stick.1 as f64 * CURSOR_SPEED * delta_time).clamp(0.0, HEIGHT as f64);
+            self.mouse_position = Some((x, y));
+        }
+        if pad.randomize_pressed {
+            self.randomize_brush_material();
+        }
+        if pad.clear_pressed {
+            self.clear_dots();
         }
 
-        self.update_physics();
-
-        // ブレンド結果を適用
-        let mut to_be_removed: Vec<usize> = Vec::new();
-        let mut changes: Vec<(usize, MaterialDNA)> = Vec::new();
+        // --- フォトモードのカメラ移動 ---
+        if self.camera_path.is_playing() {
+            self.camera_path.advance(&mut self.camera, delta_time);
+        } else if self.photo_mode {
+            let [up, left, down, right] = self.pan_keys.map(|held| if held { 1.0 } else { 0.0 });
+            self.camera.update((right - left, down - up), delta_time);
+        }
 
-        for result in self.result_rx.try_iter() {
-            match result {
-                BlendResult::Change { index, new_dna } => {
-                    changes.push((index, new_dna));
-                }
-                BlendResult::Vanish { index } => {
-                    to_be_removed.push(index);
+        // リプレイの再生中は記録された入力だけでドットを置く
+        self.apply_due_replay_inputs();
+        let tool_held = (self.left_mouse_pressed || pad.place_held) && !self.replay.is_playing();
+        if tool_held && self.tool == Tool::Brush {
+            if let Some((x, y)) = self.cursor_world_position() {
+                if now.duration_since(self.last_dot_add_time) >= self.dot_add_interval {
+                    self.stamp_brush(x.floor(), y.floor());
                 }
             }
         }
 
-        // 変更を適用
-        for (index, new_dna) in changes {
-            if let Some(dot) = self.dots.get_mut(index) {
-                dot.material_dna = new_dna;
-                dot.material = from_dna(&dot.material_dna);
-                dot.name = crate::naming::generate_name(&dot.material_dna);
-            }
+        // 掘る・かき混ぜるツールは固定ステップごとに適用する (止まっていれば動かし始める)
+        // 再生中はリプレイの入力が使う位置を決める
+        if !self.replay.is_playing() {
+            let held_tool = match self.cursor_world_position() {
+                Some((x, y)) if tool_held && matches!(self.tool, Tool::Dig | Tool::Stir) => Some((self.tool, x, y)),
+                _ => None,
+            };
+            self.set_held_tool(held_tool);
         }
+        if self.held_tool.is_some() {
+            self.is_updating = true;
+        }
+        self.record_world_state();
 
-        // 重複を削除し、降順にソートしてインデックスのズレを防ぐ
-        to_be_removed.sort_unstable();
-        to_be_removed.dedup();
-        to_be_removed.reverse();
-
-        for index in to_be_removed {
-            if index < self.dots.len() {
-                self.dots.remove(index);
-            }
+        self.update_physics();
+        self.tick_background_tabs(delta_time);
+
+        // ワーカーから届いたブレンド結果を適用する
+        // (ドットの ID はタブごとに別の範囲なので、裏のタブにもそのまま適用する。決定論モードのタブの
+        // ブレンドはステップ内で解決するので、届くのは切り替える前の古い結果だけ)
+        let results: Vec<BlendResult> = self.result_rx.try_iter().collect();
+        let (changes, vanished) = self.group_blend_results(results);
+        if !self.physics.synchronous_blends() {
+            apply_blend_results(&mut self.dots, &changes, &vanished);
+        }
+        for state in self.tabs.background_mut().filter(|state| !state.physics.synchronous_blends()) {
+            apply_blend_results(&mut state.dots, &changes, &vanished);
         }
 
         if now.duration_since(self.last_fps_update).as_secs_f32() > 0.5 {
@@ -420,45 +2043,516 @@ impl App {
             self.last_fps_update = now;
         }
 
+        if let Some(puzzle) = &mut self.puzzle {
+            let was_completed = puzzle.completed_at.is_some();
+            puzzle.evaluate(&self.dots);
+            if !was_completed && puzzle.completed_at.is_some() {
+                tracing::info!(target: crate::logging::APP, target_name = %puzzle.target_name, "puzzle completed");
+            }
+        }
+
+        self.update_snapshot_diff();
+
+        let temperature = self.renderer.as_ref().map(|renderer| renderer.settings.temperature).unwrap_or_default();
+        self.journal.observe(&self.dots, self.start_time.elapsed().as_secs_f64(), delta_time, &temperature);
+
+        if let Some(crash_reporter) = &self.crash_reporter {
+            let settings = format!(
+                "gravity={} convection={} photo_mode={} render={:?}",
+                self.gravity,
+                self.convection_enabled,
+                self.photo_mode,
+                self.renderer.as_ref().map(|renderer| renderer.settings),
+            );
+            crash_reporter.update(self.brush_seed, self.dots.len(), settings);
+        }
+
+        let brush_preview = self.brush_preview();
         let window = self.window.as_ref().unwrap();
 
-        let (hovered_material, hovered_dot_dna, hovered_dot_name, _hovered_dot_velocity) =
+        let (hovered_material, hovered_dot_dna, hovered_dot_name, _hovered_dot_velocity, selected_dot_group) =
             if let Some(selected_id) = self.selected_dot_id {
                 self.dots
                     .iter()
                     .find(|d| d.id == selected_id)
-                    .map_or((None, None, None, None), |dot| {
+                    .map_or((None, None, None, None, None), |dot| {
                         (
                             Some(dot.material.clone()),
                             Some(dot.material_dna.clone()),
                             Some(dot.name.clone()),
                             Some((dot.vx, dot.vy)),
+                            dot.group,
                         )
                     })
             } else {
-                (None, None, None, None)
+                (None, None, None, None, None)
             };
 
+        let notes = self
+            .dots
+            .iter()
+            .filter(|dot| !dot.hidden)
+            .filter_map(|dot| {
+                dot.note.as_ref().map(|text| crate::renderer::gui::NoteSummary {
+                    id: dot.id,
+                    name: dot.name.clone(),
+                    text: text.clone(),
+                    position: (dot.x, dot.y),
+                })
+            })
+            .collect();
+        let selected_dot_note = self
+            .selected_dot_id
+            .and_then(|id| self.dots.iter().find(|dot| dot.id == id))
+            .and_then(|dot| dot.note.clone());
+
+        let groups = self
+            .groups
+            .iter()
+            .zip(self.groups.stats(&self.dots))
+            .map(|(group, stats)| crate::renderer::gui::GroupSummary {
+                id: group.id,
+                name: group.name.clone(),
+                visible: group.visible,
+                frozen: group.frozen,
+                stats,
+            })
+            .collect();
+
+        let portal_rims = self.portal_rims();
         let ui_data = crate::renderer::gui::UiData {
             fps: self.fps,
             dot_count: self.dots.len(),
+            sleeping_dots: self.physics.sleeping_count(),
+            physics_seed: self.rng.seed(),
             selected_material: hovered_material,
             selected_dot_dna: hovered_dot_dna,
             selected_dot_name: hovered_dot_name,
+            selected_dot_id: self.selected_dot_id,
+            selected_dot_note,
+            hovered_note: self.hovered_dot().and_then(|dot| dot.note.clone()),
+            notes,
+            gamepad_name: self.gamepad.connected_name(),
+            gamepad_dead_zone: self.gamepad.dead_zone,
+            gamepad_bindings: self.gamepad.bindings,
+            cursor_position: self.mouse_position,
+            photo_mode: self.photo_mode,
+            shader_error: self.renderer.as_ref().and_then(|renderer| renderer.shader_error()),
+            groups,
+            brush_group: self.groups.brush_group,
+            selected_dot_group,
+            convection_enabled: self.convection_enabled,
+            combustion_enabled: self.combustion_enabled,
+            electricity_enabled: self.electricity_enabled,
+            weather: self.weather.settings,
+            weather_material: self.weather.drop_dna(),
+            weather_dots: self.weather.active_count(),
+            drains: &self.drains,
+            drain_filter: &self.drain_filter,
+            conveyors: &self.conveyors,
+            conveyor_settings: self.conveyor_settings,
+            region_preview: self.region_start.zip(self.cursor_world_position()),
+            portals: &self.portals,
+            portal_settings: self.portal_settings,
+            portal_pending: self.portal_entrance.is_some(),
+            thermostat: self.thermostat,
+            color_diffusion_enabled: self.color_diffusion_enabled,
+            flow_field: match &self.renderer {
+                Some(renderer) if renderer.settings.show_flow => {
+                    convection::flow_field(&self.dots, convection::FLOW_CELL_SIZE)
+                }
+                _ => Vec::new(),
+            },
+            camera: self.camera,
+            camera_path: &self.camera_path,
+            reaction_graph: &self.reaction_graph,
+            puzzle: self.puzzle.as_ref(),
+            presets: &self.presets,
+            commands: &self.commands,
+            logs: &self.log_buffer,
+            previous_crash_report: self.previous_crash_report.as_deref(),
+            reaction_export_status: self.reaction_export_status.clone(),
+            journal: &self.journal,
+            journal_status: self.journal_status.clone(),
+            scene_status: self.scene_status.clone(),
+            macros: &self.macros,
+            macro_status: self.macro_status.clone(),
+            material_library: &self.material_library,
+            material_library_status: self.material_library_status.clone(),
+            replay: &self.replay,
+            replay_status: self.replay_status.clone(),
+            tab_names: self.tabs.names(),
+            active_tab: self.tabs.active,
+            background_tabs_running: self.tabs.background_running,
+            tool: self.tool,
+            brush: self.brush,
+            brush_dna: to_dna(&self.brush_material, self.brush_seed),
+            mutation: crate::material::mutation::settings(),
+            brush_preview_count: brush_preview.as_ref().map(|preview| preview.positions.len()),
+            interactions: self.physics.interactions,
+            memory: crate::renderer::gui::MemoryUsage {
+                dots: self.dots.capacity() * std::mem::size_of::<Dot>()
+                    + self.dots.iter().map(|dot| dot.name.capacity()).sum::<usize>(),
+                physics: self.physics.memory_usage(),
+                renderer: self.renderer.as_ref().map(|renderer| renderer.memory_usage()).unwrap_or_default(),
+            },
         };
 
         if let Some(renderer) = &mut self.renderer {
             let time = self.start_time.elapsed().as_secs_f32();
-            let (randomize_clicked, clear_clicked) =
-                renderer.render(window, &self.dots, &ui_data, time); // 戻り値を受け取る
+            renderer.set_pick_position(self.mouse_position);
+            renderer.set_brush_preview(brush_preview.as_ref());
+            renderer.set_portal_rims(&portal_rims);
+            let actions = renderer.render(window, &self.dots, self.render_alpha, &self.camera, &ui_data, time);
 
-            if randomize_clicked {
+            if actions.randomize {
                 self.randomize_brush_material();
             }
-            if clear_clicked {
+            if actions.clear {
                 // CLSボタンがクリックされたら
                 self.clear_dots(); // ドットをクリア
             }
+            if actions.open_crash_report {
+                if let Some(path) = &self.previous_crash_report {
+                    if let Err(error) = crate::crash::open_report(path) {
+                        tracing::warn!(target: crate::logging::APP, "failed to open crash report: {}", error);
+                    }
+                }
+            }
+            if actions.dismiss_crash_report {
+                crate::crash::dismiss_previous_report();
+                self.previous_crash_report = None;
+            }
+            if let Some(command) = actions.command {
+                self.run_command(command);
+            }
+            if let Some(index) = actions.load_preset {
+                self.load_preset(index);
+            }
+            if actions.new_puzzle {
+                self.puzzle = Some(Puzzle::random());
+            }
+            if actions.quit_puzzle {
+                self.puzzle = None;
+            }
+            if let Some(dna) = actions.set_brush {
+                self.set_brush_material(&dna);
+            }
+            if let Some(mutation) = actions.set_mutation {
+                crate::material::mutation::set_settings(mutation);
+            }
+            match actions.dna_edit {
+                Some(DnaEdit::SetBrush(dna)) => self.set_brush_material(&dna),
+                Some(DnaEdit::WriteToSelected(id, dna)) => self.set_dot_material(id, dna),
+                None => {}
+            }
+            if let Some(format) = actions.export_reactions {
+                self.export_reaction_graph(format);
+            }
+            if let Some(thermostat) = actions.set_thermostat {
+                self.thermostat = thermostat;
+                self.is_updating |= thermostat.is_active(); // 止まっているドットも温め始める
+            }
+            if let Some(filter) = actions.set_drain_filter {
+                self.drain_filter = filter;
+            }
+            if let Some(dead_zone) = actions.set_gamepad_dead_zone {
+                self.gamepad.set_dead_zone(dead_zone);
+                self.save_gamepad_settings();
+            }
+            if let Some(bindings) = actions.set_gamepad_bindings {
+                tracing::info!(target: crate::logging::APP, ?bindings, "gamepad bindings changed");
+                self.gamepad.bindings = bindings;
+                self.save_gamepad_settings();
+            }
+            if let Some(settings) = actions.set_portal_settings {
+                self.portal_settings = settings;
+            }
+            if let Some(index) = actions.remove_portal {
+                if index < self.portals.len() {
+                    self.portals.remove(index);
+                }
+            }
+            if let Some(settings) = actions.set_conveyor_settings {
+                self.conveyor_settings = settings;
+            }
+            if let Some(index) = actions.remove_conveyor {
+                if index < self.conveyors.len() {
+                    self.conveyors.remove(index);
+                }
+            }
+            if let Some(index) = actions.remove_drain {
+                if index < self.drains.len() {
+                    self.drains.remove(index);
+                }
+            }
+            if let Some(settings) = actions.set_weather {
+                self.set_weather(settings);
+            }
+            if actions.weather_use_brush {
+                self.weather.material = to_dna(&self.brush_material, self.brush_seed);
+            }
+            if actions.toggle_electricity {
+                self.electricity_enabled = !self.electricity_enabled;
+            }
+            if actions.toggle_combustion {
+                self.combustion_enabled = !self.combustion_enabled;
+            }
+            if actions.toggle_convection {
+                self.convection_enabled = !self.convection_enabled;
+            }
+            if actions.toggle_color_diffusion {
+                self.color_diffusion_enabled = !self.color_diffusion_enabled;
+            }
+            if let Some(tool) = actions.set_tool {
+                self.tool = tool;
+            }
+            if let Some(brush) = actions.brush {
+                self.set_brush(brush);
+            }
+            if let Some(action) = actions.macro_action {
+                self.apply_macro_action(action);
+            }
+            if let Some(action) = actions.library_action {
+                self.apply_library_action(action);
+            }
+            if let Some(action) = actions.tab {
+                self.apply_tab_action(action);
+            }
+            if let Some(action) = actions.group_action {
+                self.apply_group_action(action);
+            }
+            if let Some(action) = actions.camera_path {
+                self.apply_camera_path_action(action);
+            }
+            if let Some((id, text)) = actions.set_note {
+                self.set_note(id, &text);
+            }
+            if let Some(id) = actions.select_dot {
+                self.select_dot(Some(id));
+            }
+            if let Some(matrix) = actions.set_interactions {
+                self.set_interactions(matrix);
+            }
+        }
+    }
+}
+
+fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Brush", "Randomize material", Command::RandomizeBrush);
+    registry.register("Dots", "Clear all", Command::ClearDots);
+    registry.register("View", "Toggle photo mode", Command::TogglePhotoMode);
+    registry.register("Physics", "Toggle convection", Command::ToggleConvection);
+    registry.register("Physics", "Toggle combustion", Command::ToggleCombustion);
+    registry.register("Physics", "Toggle electricity", Command::ToggleElectricity);
+    registry.register("Physics", "Toggle rain / snow", Command::ToggleWeather);
+    registry.register("Physics", "Clear drains", Command::ClearDrains);
+    registry.register("Physics", "Clear conveyors", Command::ClearConveyors);
+    registry.register("Physics", "Clear portals", Command::ClearPortals);
+    registry.register("View", "Toggle liquid color mixing", Command::ToggleColorDiffusion);
+    registry.register("Puzzle", "New puzzle", Command::NewPuzzle);
+    registry.register("Puzzle", "Quit puzzle", Command::QuitPuzzle);
+}
+
+/// ブレンド結果を dots に反映する
+/// 変更はチャンクごとに並列で適用し (消えるドットは変更しない)、消えたドットはまとめて詰める (並び順は保つ)
+fn apply_blend_results(dots: &mut Vec<Dot>, changes: &HashMap<u64, MaterialDNA>, vanished: &HashSet<u64>) {
+    if !changes.is_empty() {
+        dots.par_chunks_mut(BLEND_CHUNK_SIZE).for_each(|chunk| {
+            for dot in chunk {
+                if vanished.contains(&dot.id) {
+                    continue;
+                }
+                if let Some(new_dna) = changes.get(&dot.id) {
+                    dot.material_dna = new_dna.clone();
+                    dot.material = from_dna(&dot.material_dna);
+                    dot.name = crate::naming::generate_name(&dot.material_dna);
+                    dot.decay_in = decay_delay(&dot.material_dna, dot.id);
+                }
+            }
+        });
+    }
+
+    if !vanished.is_empty() {
+        dots.retain(|dot| !vanished.contains(&dot.id));
+    }
+}
+
+/// 崩壊するまでの時間。放射性の物質は半減期に従う指数分布、不安定な物質は 2 ~ 8秒 (どちらでもなければ None)
+/// 乱数ではなく DNA とドットの ID から決めるので、並列に適用しても再現できる
+fn decay_delay(dna: &MaterialDNA, id: u64) -> Option<f64> {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(dna.seed ^ id.rotate_left(32));
+    match dna.half_life() {
+        // 1 - u は (0, 1] なので対数が発散しない
+        Some(half_life) => Some(-half_life / std::f64::consts::LN_2 * (1.0 - rng.gen::<f64>()).ln()),
+        None => dna.unstable.then(|| rng.gen_range(2.0..8.0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// seed を指定した App に、2種類の物質を重ねて落としたもの
+    fn seeded_app(seed: u64) -> App {
+        let (collision_tx, _collision_rx) = mpsc::channel();
+        let (_result_tx, result_rx) = mpsc::channel();
+        let mut app = App::new(collision_tx, result_rx, false, 0);
+        app.rng = GameRng::seeded(seed);
+        for (k, material_seed) in [11, 22, 33].into_iter().enumerate() {
+            app.brush_seed = material_seed;
+            app.brush_material = crate::material::from_seed(material_seed);
+            for i in 0..12 {
+                app.stamp_brush(200.0 + i as f64 * 9.0, 100.0 + k as f64 * 12.0);
+            }
+        }
+        app
+    }
+
+    /// ドットの状態 (ID・位置・速度・物質) の比較用
+    fn fingerprint(app: &App) -> Vec<(u64, u64, u64, u64, u64, u64)> {
+        app.dots
+            .iter()
+            .map(|dot| (dot.id, dot.x.to_bits(), dot.y.to_bits(), dot.vx.to_bits(), dot.vy.to_bits(), dot.material_dna.seed))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_the_same_world() {
+        let mut runs = [seeded_app(7), seeded_app(7)];
+        for app in runs.iter_mut() {
+            for _ in 0..240 {
+                app.advance_step();
+            }
         }
+        assert!(!runs[0].dots.is_empty());
+        // ブレンドがステップ内で解決されている (ワーカーがいなくても反応が起きる)
+        assert!(runs[0].dots.iter().any(|dot| ![11, 22, 33].contains(&dot.material_dna.seed)));
+        assert_eq!(fingerprint(&runs[0]), fingerprint(&runs[1]));
+    }
+
+    #[test]
+    fn right_click_erases_only_after_dragging_past_the_threshold() {
+        use winit::dpi::PhysicalPosition;
+        use winit::event::{ElementState, MouseButton};
+
+        let mut app = seeded_app(5);
+        app.brush.radius = 12.0;
+        let count = app.dots.len();
+        app.handle_cursor_moved(PhysicalPosition::new(200.0, 100.0));
+        app.handle_mouse_input(ElementState::Pressed, MouseButton::Right);
+        // 押したときに選んだドット (ヘッドレスではピッキングできないので直接指定する)
+        let spared = app.dots.iter().min_by(|a, b| (a.x - 218.0).abs().total_cmp(&(b.x - 218.0).abs())).unwrap().id;
+        app.erase_spared_id = Some(spared);
+
+        app.handle_cursor_moved(PhysicalPosition::new(203.0, 100.0));
+        assert_eq!(app.dots.len(), count);
+
+        app.handle_cursor_moved(PhysicalPosition::new(218.0, 100.0));
+        assert!(app.dots.len() < count);
+        assert!(app.dots.iter().any(|dot| dot.id == spared));
+
+        app.handle_mouse_input(ElementState::Released, MouseButton::Right);
+        app.undo_erase();
+        assert_eq!(app.dots.len(), count);
+    }
+
+    #[test]
+    fn brush_sees_dots_added_and_removed_after_the_last_step() {
+        let mut app = seeded_app(1);
+        advance(&mut app, 1);
+        app.brush.radius = 10.0;
+        let (x, y) = (60.0, 300.0);
+        assert_eq!(app.placeable_positions(vec![(x, y)]), vec![(x, y)]);
+
+        // ステップの後に足したドットの場所には置かない
+        let material = app.brush_material.clone();
+        let dot = app.new_dot(x, y, material.clone(), to_dna(&material, app.brush_seed));
+        let id = dot.id;
+        app.dots.push(dot);
+        assert!(app.placeable_positions(vec![(x, y)]).is_empty());
+
+        // 前のドットを消してインデックスがずれても見つかる
+        app.dots.remove(0);
+        assert!(app.placeable_positions(vec![(x, y)]).is_empty());
+
+        // 消したドットの場所には置ける
+        app.dots.retain(|dot| dot.id != id);
+        assert_eq!(app.placeable_positions(vec![(x, y)]), vec![(x, y)]);
+    }
+
+    #[test]
+    fn pending_snapshot_stays_with_its_tab() {
+        let mut app = seeded_app(2);
+        app.pending_snapshot = Some((Snapshot::capture(&app.dots, 0.0), crate::snapshot::DIFF_FRAMES));
+
+        // 新しいタブでは前のタブのスナップショットと比べない
+        app.apply_tab_action(TabAction::New);
+        assert!(app.pending_snapshot.is_none());
+
+        app.apply_tab_action(TabAction::Switch(0));
+        let (_, frames_left) = app.pending_snapshot.as_ref().unwrap();
+        assert_eq!(*frames_left, crate::snapshot::DIFF_FRAMES);
+    }
+
+    fn advance(app: &mut App, steps: usize) {
+        for _ in 0..steps {
+            app.advance_step();
+        }
+    }
+
+    #[test]
+    fn recorded_inputs_play_back_to_the_same_world() {
+        let mut recording = seeded_app(3);
+        recording.replay = ReplayMode::Idle;
+        recording.apply_replay_control(ReplayControl::StartRecording);
+        let seed = recording.rng.seed().unwrap();
+
+        recording.set_brush_material(&to_dna(&crate::material::from_seed(44), 44));
+        for i in 0..10 {
+            recording.stamp_brush(150.0 + i as f64 * 10.0, 80.0);
+        }
+        advance(&mut recording, 60);
+
+        // 消しゴムと取り消し
+        recording.erase_at(180.0, 200.0, 20.0, None);
+        recording.erase_at(220.0, 200.0, 20.0, None);
+        recording.finish_erase_stroke();
+        recording.erase_at(260.0, 200.0, 20.0, None);
+        recording.undo_erase();
+        advance(&mut recording, 30);
+
+        // 掘る・かき混ぜるツール
+        recording.set_held_tool(Some((Tool::Stir, 200.0, 250.0)));
+        advance(&mut recording, 20);
+        recording.set_held_tool(Some((Tool::Dig, 210.0, 300.0)));
+        advance(&mut recording, 20);
+        recording.set_held_tool(None);
+
+        // 領域と物理の設定 (フレームの最初に記録される)
+        recording.drains.push(Drain::from_corners((0.0, 380.0), (120.0, 600.0), DrainFilter::Any).unwrap());
+        recording.conveyors.push(Conveyor::from_corners((130.0, 300.0), (330.0, 320.0), ConveyorSettings::default()).unwrap());
+        recording.convection_enabled = !recording.convection_enabled;
+        recording.record_world_state();
+        advance(&mut recording, 120);
+
+        let ReplayMode::Recording(recorder) = std::mem::take(&mut recording.replay) else {
+            panic!("not recording");
+        };
+        let replay = recorder.finish();
+        let steps = recording.physics_steps;
+        let replay = serde_json::from_str(&serde_json::to_string(&replay).unwrap()).unwrap();
+
+        let mut playback = seeded_app(99); // 記録と違う状態から始めても、再生でやり直せる
+        playback.restart_deterministic(seed);
+        playback.replay = ReplayMode::Playing(ReplayPlayer::new(replay));
+        advance(&mut playback, steps as usize);
+
+        assert!(!recording.dots.is_empty());
+        assert_eq!(playback.drains, recording.drains);
+        assert_eq!(playback.conveyors, recording.conveyors);
+        assert_eq!(playback.convection_enabled, recording.convection_enabled);
+        assert_eq!(fingerprint(&playback), fingerprint(&recording));
     }
 }
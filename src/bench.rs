@@ -0,0 +1,151 @@
+//! ヘッドレスのベンチマークシナリオ (`--bench-suite`)
+//!
+//! presets/bench/*.json のシナリオをウィンドウなしで固定ステップ数だけ進め、
+//! ステップ時間と結果を JSON のレポートにまとめる。物理の変更前後で
+//! レポートを比べられるよう、dt と乱数の seed は固定にしている。
+
+use crate::app::{Dot, GRAVITY, PHYSICS_DT};
+use crate::material::to_dna;
+use crate::physics::{engine, GameRng, Physics};
+use crate::presets::Preset;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::mpsc;
+use std::time::Instant;
+
+const SCENARIOS: [&str; 3] = [
+    include_str!("../presets/bench/dense_liquid_pour.json"),
+    include_str!("../presets/bench/gas_explosion_chain.json"),
+    include_str!("../presets/bench/settled_solids.json"),
+];
+
+const DT: f64 = PHYSICS_DT; // アプリと同じ固定ステップ
+const SEED: u64 = 0x7E44A; // 物理の乱数の seed
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub version: String,
+    pub steps: u32,
+    pub dt: f64,
+    pub scenarios: Vec<ScenarioResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    pub name: String,
+    pub initial_dots: usize,
+    pub final_dots: usize,
+    pub total_ms: f64,
+    pub mean_step_ms: f64,
+    pub p95_step_ms: f64,
+    pub max_step_ms: f64,
+    pub collision_events: usize, // ワーカーに送られるはずだった衝突イベントの数
+    pub mean_speed: f64,         // 最終ステップでのドットの平均速度
+}
+
+/// すべてのシナリオを steps ステップずつ実行する
+pub fn run(steps: u32) -> BenchReport {
+    let scenarios = SCENARIOS
+        .iter()
+        .map(|source| {
+            let preset: Preset = serde_json::from_str(source).expect("bench scenario must be valid JSON");
+            run_scenario(&preset, steps)
+        })
+        .collect();
+    BenchReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        steps,
+        dt: DT,
+        scenarios,
+    }
+}
+
+fn run_scenario(preset: &Preset, steps: u32) -> ScenarioResult {
+    let mut dots = spawn_dots(preset);
+    let initial_dots = dots.len();
+    // ワーカースレッドは起動しないので、衝突イベントは数えるだけにする
+    let (collision_tx, collision_rx) = mpsc::channel();
+    let mut physics = Physics::new(collision_tx);
+    let mut rng = GameRng::seeded(SEED);
+    let mut collision_events = 0;
+    let mut step_ms = Vec::with_capacity(steps as usize);
+
+    for _ in 0..steps {
+        let start = Instant::now();
+        engine::update_state(&mut dots, GRAVITY, DT, true, &mut rng);
+        physics.update_collision(&mut dots, DT);
+        engine::update_position(&mut dots, DT, &mut rng);
+        step_ms.push(start.elapsed().as_secs_f64() * 1000.0);
+        collision_events += collision_rx.try_iter().count();
+    }
+
+    let total_ms: f64 = step_ms.iter().sum();
+    let mean_speed = if dots.is_empty() {
+        0.0
+    } else {
+        dots.iter().map(|dot| (dot.vx * dot.vx + dot.vy * dot.vy).sqrt()).sum::<f64>() / dots.len() as f64
+    };
+    step_ms.sort_by(f64::total_cmp);
+    let percentile = |p: f64| {
+        let index = ((step_ms.len() as f64 * p) as usize).min(step_ms.len().saturating_sub(1));
+        step_ms.get(index).copied().unwrap_or(0.0)
+    };
+
+    let result = ScenarioResult {
+        name: preset.name.clone(),
+        initial_dots,
+        final_dots: dots.len(),
+        total_ms,
+        mean_step_ms: total_ms / steps.max(1) as f64,
+        p95_step_ms: percentile(0.95),
+        max_step_ms: step_ms.last().copied().unwrap_or(0.0),
+        collision_events,
+        mean_speed,
+    };
+    tracing::info!(
+        target: crate::logging::APP,
+        scenario = %result.name,
+        dots = result.initial_dots,
+        mean_step_ms = result.mean_step_ms,
+        "bench scenario finished"
+    );
+    result
+}
+
+fn spawn_dots(preset: &Preset) -> Vec<Dot> {
+    let mut dots = Vec::new();
+    for fill in &preset.fills {
+        let material = fill.material();
+        let material_dna = to_dna(&material, fill.seed);
+        for (x, y) in fill.positions() {
+            dots.push(Dot::new(dots.len() as u64, x, y, material.clone(), material_dna.clone()));
+        }
+    }
+    dots
+}
+
+/// 基準のレポートとの比較結果 (同名のシナリオごとに平均ステップ時間の増減)
+pub fn compare(baseline: &BenchReport, current: &BenchReport) -> String {
+    let mut summary = String::new();
+    let _ = writeln!(summary, "bench: {} -> {}", baseline.version, current.version);
+    for result in &current.scenarios {
+        match baseline.scenarios.iter().find(|base| base.name == result.name) {
+            Some(base) => {
+                let change = if base.mean_step_ms > 0.0 {
+                    (result.mean_step_ms - base.mean_step_ms) / base.mean_step_ms * 100.0
+                } else {
+                    0.0
+                };
+                let _ = writeln!(
+                    summary,
+                    "{:<24} {:>9.3} ms -> {:>9.3} ms ({:+.1}%)  dots {} -> {}",
+                    result.name, base.mean_step_ms, result.mean_step_ms, change, base.final_dots, result.final_dots
+                );
+            }
+            None => {
+                let _ = writeln!(summary, "{:<24} {:>9.3} ms (new)", result.name, result.mean_step_ms);
+            }
+        }
+    }
+    summary
+}
@@ -0,0 +1,140 @@
+//! ドットを置くブラシの形と大きさ
+//!
+//! 半径 0 では従来どおり1回に1つだけ置く。半径を指定すると円や正方形の範囲を格子状に埋めるか、
+//! スプレーとして範囲内にばらまく。密度は格子の詰まり具合 (スプレーでは1回にまく量) を決める。
+//! 置いたドットには温度の上乗せと初速を与えられるので、熱いガスを噴き出したり冷たい雨を降らせたりできる。
+
+use crate::commands::{Command, CommandRegistry};
+use crate::physics::{GameRng, DOT_RADIUS};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_RADIUS: f64 = 40.0;
+pub const MAX_LAUNCH_SPEED: f64 = 400.0;
+const SPRAY_FILL: f64 = 0.15; // スプレー1回で範囲を埋める量の割合 (密度 1.0 のとき)
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BrushShape {
+    #[default]
+    Circle,
+    Square,
+    Spray,
+}
+
+impl BrushShape {
+    pub const ALL: [BrushShape; 3] = [BrushShape::Circle, BrushShape::Square, BrushShape::Spray];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BrushShape::Circle => "Circle",
+            BrushShape::Square => "Square",
+            BrushShape::Spray => "Spray",
+        }
+    }
+}
+
+/// 置いたドットに与える初速
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Launch {
+    #[default]
+    Still,
+    Direction { angle: f64, speed: f64 }, // angle は度 (0 で右、90 で下)
+    Drag { speed: f64 },                  // ドラッグしている向き (止まっているときは初速なし)
+}
+
+impl Launch {
+    pub fn label(self) -> &'static str {
+        match self {
+            Launch::Still => "None",
+            Launch::Direction { .. } => "Direction",
+            Launch::Drag { .. } => "Drag direction",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Brush {
+    pub shape: BrushShape,
+    pub radius: f64,  // 0.0 なら1つだけ置く
+    pub density: f64, // 0.05 ~ 1.0 (1.0 で隣り合うドットが接する)
+    #[serde(default)]
+    pub temperature_offset: f32, // 置いたドットの温度に足す量 (-1.0 ~ 1.0)
+    #[serde(default)]
+    pub launch: Launch,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            shape: BrushShape::default(),
+            radius: 0.0,
+            density: 1.0,
+            temperature_offset: 0.0,
+            launch: Launch::default(),
+        }
+    }
+}
+
+impl Brush {
+    /// 置いたドットの初速。drag は直前にブラシを当てた位置からの移動量
+    pub fn launch_velocity(&self, drag: Option<(f64, f64)>) -> (f64, f64) {
+        match self.launch {
+            Launch::Still => (0.0, 0.0),
+            Launch::Direction { angle, speed } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                (cos * speed, sin * speed)
+            }
+            Launch::Drag { speed } => match drag {
+                Some((dx, dy)) if dx != 0.0 || dy != 0.0 => {
+                    let length = dx.hypot(dy);
+                    (dx / length * speed, dy / length * speed)
+                }
+                _ => (0.0, 0.0),
+            },
+        }
+    }
+
+    /// (x, y) を中心に1回ブラシを当てたときにドットを置く位置
+    pub fn positions(&self, x: f64, y: f64, rng: &mut GameRng) -> Vec<(f64, f64)> {
+        if self.radius < DOT_RADIUS {
+            return vec![(x, y)];
+        }
+        let density = self.density.clamp(0.05, 1.0);
+        // 密度 1.0 で直径おきの格子になり、密度が下がると面積あたりの数が比例して減る
+        let spacing = DOT_RADIUS * 2.0 / density.sqrt();
+        match self.shape {
+            BrushShape::Circle | BrushShape::Square => {
+                let steps = (self.radius / spacing).floor() as i32;
+                let mut positions = Vec::new();
+                for iy in -steps..=steps {
+                    for ix in -steps..=steps {
+                        let (dx, dy) = (ix as f64 * spacing, iy as f64 * spacing);
+                        if self.shape == BrushShape::Circle && dx * dx + dy * dy > self.radius * self.radius {
+                            continue;
+                        }
+                        positions.push((x + dx, y + dy));
+                    }
+                }
+                positions
+            }
+            BrushShape::Spray => {
+                let area = std::f64::consts::PI * self.radius * self.radius;
+                let count = ((area / (spacing * spacing)) * SPRAY_FILL).ceil().max(1.0) as usize;
+                (0..count)
+                    .map(|_| {
+                        // 円内に一様に散らす
+                        let r = self.radius * rng.gen::<f64>().sqrt();
+                        let angle = rng.gen::<f64>() * std::f64::consts::TAU;
+                        (x + r * angle.cos(), y + r * angle.sin())
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    for shape in BrushShape::ALL {
+        registry.register("Brush", format!("Shape: {}", shape.label()), Command::SetBrushShape(shape));
+    }
+}
@@ -0,0 +1,119 @@
+//! コマンドパレット (Ctrl+P) から実行できる操作の登録簿
+//!
+//! 各モジュールは `register_commands` で自分の操作を登録する。
+//! GUI はあいまい検索で絞り込んだコマンドを返し、App (または GUI 自身) が実行する。
+
+use crate::brush::BrushShape;
+use crate::macros::MacroAction;
+use crate::material::library::LibraryAction;
+use crate::naming::NamingStyle;
+use crate::reaction_graph::ExportFormat;
+use crate::replay::ReplayControl;
+use crate::tabs::TabAction;
+use crate::renderer::camera_path::CameraPathAction;
+use crate::tools::Tool;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    RandomizeBrush,
+    ClearDots,
+    TogglePhotoMode,
+    ToggleConvection,
+    ToggleColorDiffusion,
+    ToggleCombustion,
+    ToggleElectricity,
+    ToggleWeather,
+    ClearDrains,
+    ClearConveyors,
+    ClearPortals,
+    NewPuzzle,
+    QuitPuzzle,
+    LoadPreset(usize),
+    ExportReactions(ExportFormat),
+    ExportSeedBank,
+    ExportJournal,
+    SaveScene,
+    LoadScene,
+    ImportSeedBank,
+    SnapshotDiff,
+    GenerateLandscape,
+    SelectTool(Tool),
+    UndoErase,
+    SetBrushShape(BrushShape),
+    CameraPath(CameraPathAction),
+    Macro(MacroAction),
+    Replay(ReplayControl),
+    Tab(TabAction),
+    MaterialLibrary(LibraryAction),
+    SetNamingStyle(NamingStyle),
+    // 以下は GUI 側の状態なので Gui が処理する
+    ToggleGallery,
+    ToggleReactionGraph,
+    ToggleMaterialCompare,
+    ToggleDnaEditor,
+    ToggleFlowField,
+    ToggleSkipComposite,
+    ToggleLog,
+    ToggleCameraPath,
+    ToggleNotes,
+    ToggleJournal,
+    ToggleMacros,
+    ToggleMaterialLibrary,
+}
+
+pub struct CommandEntry {
+    pub category: &'static str,
+    pub label: String,
+    pub command: Command,
+}
+
+#[derive(Default)]
+pub struct CommandRegistry {
+    entries: Vec<CommandEntry>,
+}
+
+impl CommandRegistry {
+    pub fn register(&mut self, category: &'static str, label: impl Into<String>, command: Command) {
+        self.entries.push(CommandEntry {
+            category,
+            label: label.into(),
+            command,
+        });
+    }
+
+    /// "カテゴリ: ラベル" に対してあいまい検索し、よく一致する順に返す (空なら登録順)
+    pub fn search(&self, query: &str) -> Vec<&CommandEntry> {
+        let mut matches: Vec<(i32, &CommandEntry)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                fuzzy_score(query, &format!("{}: {}", entry.category, entry.label)).map(|score| (score, entry))
+            })
+            .collect();
+        matches.sort_by_key(|(score, _)| -score); // 安定ソートなので同点は登録順
+        matches.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
+
+/// query の文字が text に順番どおり含まれていればスコアを返す (大文字小文字は無視)
+/// 連続して一致した文字と単語の先頭での一致を高く評価する
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = text[position..].iter().position(|&c| c == query_char)? + position;
+        score += 1;
+        if previous_match.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
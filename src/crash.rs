@@ -0,0 +1,144 @@
+//! クラッシュレポート
+//!
+//! パニック時にバックトレース、シード、ドット数、直近のイベントとログ、設定を
+//! crash_reports/ に書き出す。次回起動時に前回のレポートがあればダイアログで知らせる。
+
+use crate::logging::LogBuffer;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const REPORT_DIR: &str = "crash_reports";
+const LAST_CRASH_FILE: &str = "LAST_CRASH"; // 未確認のレポートのパスを記録する
+const MAX_EVENTS: usize = 100;
+
+/// パニック時に書き出すアプリの状態 (毎フレーム更新する)
+#[derive(Default)]
+struct CrashContext {
+    brush_seed: u64,
+    dot_count: usize,
+    settings: String,
+    events: VecDeque<String>,
+}
+
+#[derive(Clone)]
+pub struct CrashReporter {
+    context: Arc<Mutex<CrashContext>>,
+}
+
+impl CrashReporter {
+    /// パニックフックを登録する (既定のフックも引き続き呼ばれる)
+    pub fn install(logs: LogBuffer) -> Self {
+        let context = Arc::new(Mutex::new(CrashContext::default()));
+        let hook_context = context.clone();
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // フック内で再びパニックしないよう、ロックできなければ状態なしで書く
+            let report = match hook_context.try_lock() {
+                Ok(context) => build_report(info, Some(&context), &logs),
+                Err(_) => build_report(info, None, &logs),
+            };
+            if let Ok(path) = write_report(&report) {
+                eprintln!("crash report written to {}", path.display());
+            }
+            default_hook(info);
+        }));
+        Self { context }
+    }
+
+    /// レポートに含めるアプリの状態を更新する
+    pub fn update(&self, brush_seed: u64, dot_count: usize, settings: String) {
+        if let Ok(mut context) = self.context.lock() {
+            context.brush_seed = brush_seed;
+            context.dot_count = dot_count;
+            context.settings = settings;
+        }
+    }
+
+    /// 直近のイベントとして記録する (古いものから捨てる)
+    pub fn record_event(&self, event: String) {
+        if let Ok(mut context) = self.context.lock() {
+            if context.events.len() == MAX_EVENTS {
+                context.events.pop_front();
+            }
+            context.events.push_back(event);
+        }
+    }
+}
+
+fn build_report(info: &std::panic::PanicHookInfo<'_>, context: Option<&CrashContext>, logs: &LogBuffer) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "terraspiel {} crash report", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "panic: {}", info);
+    let _ = writeln!(report);
+
+    match context {
+        Some(context) => {
+            let _ = writeln!(report, "brush seed: {}", context.brush_seed);
+            let _ = writeln!(report, "dot count: {}", context.dot_count);
+            let _ = writeln!(report, "settings: {}", context.settings);
+            let _ = writeln!(report);
+            let _ = writeln!(report, "--- last {} events ---", context.events.len());
+            for event in &context.events {
+                let _ = writeln!(report, "{}", event);
+            }
+        }
+        None => {
+            let _ = writeln!(report, "(application state unavailable)");
+        }
+    }
+
+    let _ = writeln!(report);
+    let _ = writeln!(report, "--- recent log ---");
+    let entries = logs.entries(tracing::Level::TRACE);
+    for entry in entries.iter().skip(entries.len().saturating_sub(MAX_EVENTS)) {
+        let _ = writeln!(report, "{:>8.2} {:<5} {}: {}", entry.elapsed, entry.level.as_str(), entry.target, entry.message);
+    }
+
+    let _ = writeln!(report);
+    let _ = writeln!(report, "--- backtrace ---");
+    let _ = writeln!(report, "{}", std::backtrace::Backtrace::force_capture());
+    report
+}
+
+fn write_report(report: &str) -> std::io::Result<PathBuf> {
+    let dir = Path::new(REPORT_DIR);
+    std::fs::create_dir_all(dir)?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let path = dir.join(format!("crash-{}.txt", timestamp));
+    std::fs::write(&path, report)?;
+    std::fs::write(dir.join(LAST_CRASH_FILE), path.to_string_lossy().as_bytes())?;
+    Ok(path)
+}
+
+/// 前回のセッションで書かれた未確認のレポート
+pub fn previous_report() -> Option<PathBuf> {
+    let path = std::fs::read_to_string(Path::new(REPORT_DIR).join(LAST_CRASH_FILE)).ok()?;
+    let path = PathBuf::from(path.trim());
+    path.exists().then_some(path)
+}
+
+/// レポートを確認済みにする (次回起動時にダイアログを出さない)
+pub fn dismiss_previous_report() {
+    let _ = std::fs::remove_file(Path::new(REPORT_DIR).join(LAST_CRASH_FILE));
+}
+
+/// OS の既定のアプリでレポートを開く
+pub fn open_report(path: &Path) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = std::process::Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = std::process::Command::new("xdg-open");
+
+    command.arg(path).spawn().map(|_| ())
+}
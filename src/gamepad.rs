@@ -0,0 +1,350 @@
+//! ゲームパッド入力 (`gamepad` フィーチャー有効時のみ gilrs を使用)
+//!
+//! 左スティックで仮想カーソルを動かし、ボタンでブラシ操作を行う。
+//! ボタンの割り当てとデッドゾーンは Info ウィンドウの Gamepad 欄で変えられ、
+//! ユーザーごとの設定ディレクトリに保存される。
+//! フィーチャー無効時は常に空の入力を返す。
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const SETTINGS_FILE: &str = "gamepad.json";
+
+/// デッドゾーンのデフォルト値 (スティックの傾き 0.0 ~ 1.0)
+pub const DEFAULT_DEAD_ZONE: f32 = 0.15;
+/// スティック最大入力時のカーソル速度 (px/秒)
+pub const CURSOR_SPEED: f64 = 300.0;
+
+/// デッドゾーンの上限 (これ以上にするとスティックが効かなくなる)
+pub const MAX_DEAD_ZONE: f32 = 0.95;
+
+/// 割り当てられるボタン
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    Select,
+    Start,
+}
+
+impl PadButton {
+    pub const ALL: [PadButton; 8] = [
+        PadButton::South,
+        PadButton::East,
+        PadButton::West,
+        PadButton::North,
+        PadButton::LeftShoulder,
+        PadButton::RightShoulder,
+        PadButton::Select,
+        PadButton::Start,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PadButton::South => "South (A)",
+            PadButton::East => "East (B)",
+            PadButton::West => "West (X)",
+            PadButton::North => "North (Y)",
+            PadButton::LeftShoulder => "LB",
+            PadButton::RightShoulder => "RB",
+            PadButton::Select => "Select",
+            PadButton::Start => "Start",
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn to_gilrs(self) -> gilrs::Button {
+        match self {
+            PadButton::South => gilrs::Button::South,
+            PadButton::East => gilrs::Button::East,
+            PadButton::West => gilrs::Button::West,
+            PadButton::North => gilrs::Button::North,
+            PadButton::LeftShoulder => gilrs::Button::LeftTrigger,
+            PadButton::RightShoulder => gilrs::Button::RightTrigger,
+            PadButton::Select => gilrs::Button::Select,
+            PadButton::Start => gilrs::Button::Start,
+        }
+    }
+}
+
+/// ボタンで行う操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadAction {
+    Place,
+    Randomize,
+    Clear,
+}
+
+impl PadAction {
+    pub const ALL: [PadAction; 3] = [PadAction::Place, PadAction::Randomize, PadAction::Clear];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PadAction::Place => "Place dots",
+            PadAction::Randomize => "Randomize brush material",
+            PadAction::Clear => "Clear all dots",
+        }
+    }
+}
+
+/// 操作ごとのボタン割り当て
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadBindings {
+    pub place: PadButton,
+    pub randomize: PadButton,
+    pub clear: PadButton,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        Self {
+            place: PadButton::South,
+            randomize: PadButton::West,
+            clear: PadButton::North,
+        }
+    }
+}
+
+impl GamepadBindings {
+    pub fn button(&self, action: PadAction) -> PadButton {
+        match action {
+            PadAction::Place => self.place,
+            PadAction::Randomize => self.randomize,
+            PadAction::Clear => self.clear,
+        }
+    }
+
+    /// action に button を割り当てる (他の操作が同じボタンを使っていれば、その操作と入れ替える)
+    pub fn assign(&mut self, action: PadAction, button: PadButton) {
+        let previous = self.button(action);
+        for other in PadAction::ALL {
+            if other != action && self.button(other) == button {
+                *self.slot(other) = previous;
+            }
+        }
+        *self.slot(action) = button;
+    }
+
+    fn slot(&mut self, action: PadAction) -> &mut PadButton {
+        match action {
+            PadAction::Place => &mut self.place,
+            PadAction::Randomize => &mut self.randomize,
+            PadAction::Clear => &mut self.clear,
+        }
+    }
+}
+
+/// 保存するゲームパッドの設定 (欠けている項目はデフォルト)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadSettings {
+    pub dead_zone: f32,
+    pub bindings: GamepadBindings,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone: DEFAULT_DEAD_ZONE,
+            bindings: GamepadBindings::default(),
+        }
+    }
+}
+
+impl GamepadSettings {
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(std::io::Error::other)
+    }
+}
+
+/// ユーザーごとのゲームパッドの設定の保存先
+pub fn settings_path() -> std::path::PathBuf {
+    crate::macros::config_path(SETTINGS_FILE)
+}
+
+/// 保存されたゲームパッドの設定を読み込む (なければデフォルト。読めなければ警告してデフォルト)
+pub fn load_settings() -> GamepadSettings {
+    let path = settings_path();
+    match GamepadSettings::load(&path) {
+        Ok(settings) => settings,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => GamepadSettings::default(),
+        Err(error) => {
+            tracing::warn!(target: crate::logging::APP, path = %path.display(), "gamepad settings load failed: {}", error);
+            GamepadSettings::default()
+        }
+    }
+}
+
+/// 1フレーム分のゲームパッド入力
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GamepadState {
+    pub stick: (f32, f32),       // デッドゾーン適用後のスティック入力 (-1.0 ~ 1.0)
+    pub place_held: bool,        // ドット配置ボタンが押されているか
+    pub randomize_pressed: bool, // このフレームでRNDが押されたか
+    pub clear_pressed: bool,     // このフレームでCLSが押されたか
+}
+
+pub struct Gamepad {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
+    #[cfg(feature = "gamepad")]
+    active_id: Option<gilrs::GamepadId>,
+    pub dead_zone: f32,
+    pub bindings: GamepadBindings,
+}
+
+impl Gamepad {
+    pub fn new(settings: GamepadSettings) -> Self {
+        Self {
+            #[cfg(feature = "gamepad")]
+            gilrs: gilrs::Gilrs::new().ok(),
+            #[cfg(feature = "gamepad")]
+            active_id: None,
+            dead_zone: settings.dead_zone.clamp(0.0, MAX_DEAD_ZONE),
+            bindings: settings.bindings,
+        }
+    }
+
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone.clamp(0.0, MAX_DEAD_ZONE);
+    }
+
+    pub fn settings(&self) -> GamepadSettings {
+        GamepadSettings {
+            dead_zone: self.dead_zone,
+            bindings: self.bindings,
+        }
+    }
+
+    /// 接続中のゲームパッド名 (未接続なら None)
+    #[cfg(feature = "gamepad")]
+    pub fn connected_name(&self) -> Option<String> {
+        let gilrs = self.gilrs.as_ref()?;
+        let id = self.active_id?;
+        gilrs.connected_gamepad(id).map(|pad| pad.name().to_string())
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn connected_name(&self) -> Option<String> {
+        None
+    }
+
+    /// イベントを処理して現在の入力を返す
+    #[cfg(feature = "gamepad")]
+    pub fn poll(&mut self) -> GamepadState {
+        use gilrs::{Axis, EventType};
+
+        let mut state = GamepadState::default();
+        let Some(gilrs) = self.gilrs.as_mut() else {
+            return state;
+        };
+
+        while let Some(event) = gilrs.next_event() {
+            // 最後に操作されたゲームパッドを操作対象とする
+            self.active_id = Some(event.id);
+            match event.event {
+                EventType::ButtonPressed(button, _) if button == self.bindings.randomize.to_gilrs() => {
+                    state.randomize_pressed = true
+                }
+                EventType::ButtonPressed(button, _) if button == self.bindings.clear.to_gilrs() => state.clear_pressed = true,
+                EventType::Disconnected => self.active_id = None,
+                _ => {}
+            }
+        }
+
+        if let Some(pad) = self.active_id.and_then(|id| gilrs.connected_gamepad(id)) {
+            state.stick = apply_dead_zone(
+                pad.value(Axis::LeftStickX),
+                // gilrs は上方向が正なので画面座標に合わせて反転
+                -pad.value(Axis::LeftStickY),
+                self.dead_zone,
+            );
+            state.place_held = pad.is_pressed(self.bindings.place.to_gilrs());
+        }
+
+        state
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn poll(&mut self) -> GamepadState {
+        GamepadState::default()
+    }
+}
+
+/// 円形デッドゾーンを適用し、残りの範囲を 0.0 ~ 1.0 に再マッピングする
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+fn apply_dead_zone(x: f32, y: f32, dead_zone: f32) -> (f32, f32) {
+    let magnitude = (x * x + y * y).sqrt();
+    if magnitude <= dead_zone || magnitude < 1e-6 {
+        return (0.0, 0.0);
+    }
+    let scaled = ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0);
+    (x / magnitude * scaled, y / magnitude * scaled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_zone_ignores_small_tilts_and_rescales_the_rest() {
+        assert_eq!(apply_dead_zone(0.1, -0.05, 0.15), (0.0, 0.0));
+        assert_eq!(apply_dead_zone(0.0, 0.0, 0.0), (0.0, 0.0));
+
+        // 端まで倒せば 1.0、向きは変えない
+        let (x, y) = apply_dead_zone(0.6, 0.8, 0.15);
+        assert!((x - 0.6).abs() < 1e-6 && (y - 0.8).abs() < 1e-6);
+        let (x, y) = apply_dead_zone(-1.0, 0.0, 0.5);
+        assert!((x + 1.0).abs() < 1e-6 && y == 0.0);
+
+        // デッドゾーンの外側は 0.0 から始まる
+        let (x, _) = apply_dead_zone(0.575, 0.0, 0.15);
+        assert!((x - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn assigning_a_used_button_swaps_it_with_the_other_action() {
+        let mut bindings = GamepadBindings::default();
+        bindings.assign(PadAction::Place, PadButton::North);
+        assert_eq!(bindings.place, PadButton::North);
+        assert_eq!(bindings.clear, PadButton::South);
+        assert_eq!(bindings.randomize, PadButton::West);
+
+        bindings.assign(PadAction::Randomize, PadButton::Start);
+        assert_eq!(bindings.randomize, PadButton::Start);
+        assert_eq!((bindings.place, bindings.clear), (PadButton::North, PadButton::South));
+    }
+
+    #[test]
+    fn settings_round_trip_and_fill_missing_fields() {
+        let mut bindings = GamepadBindings::default();
+        bindings.assign(PadAction::Clear, PadButton::Select);
+        let settings = GamepadSettings { dead_zone: 0.3, bindings };
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(serde_json::from_str::<GamepadSettings>(&json).unwrap(), settings);
+
+        let partial: GamepadSettings = serde_json::from_str(r#"{"bindings": {"place": "East"}}"#).unwrap();
+        assert_eq!(partial.dead_zone, DEFAULT_DEAD_ZONE);
+        assert_eq!(partial.bindings.place, PadButton::East);
+        assert_eq!(partial.bindings.clear, PadButton::North);
+
+        // 範囲外のデッドゾーンは読み込み時に丸める
+        let gamepad = Gamepad::new(GamepadSettings { dead_zone: 2.0, ..settings });
+        assert_eq!(gamepad.settings().dead_zone, MAX_DEAD_ZONE);
+        assert_eq!(gamepad.settings().bindings, bindings);
+    }
+}
@@ -0,0 +1,114 @@
+//! ドットのグループ (タグ) 管理
+//!
+//! ユーザーが作成したグループにドットを割り当て、グループ単位で
+//! 統計の表示、表示/非表示、凍結 (移動しない)、一括削除を行う。
+
+use crate::app::Dot;
+use crate::material::State;
+use serde::{Deserialize, Serialize};
+
+pub type GroupId = u32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DotGroup {
+    pub id: GroupId,
+    pub name: String,
+    pub visible: bool,
+    pub frozen: bool,
+}
+
+/// グループごとの統計
+#[derive(Debug, Clone, Default)]
+pub struct GroupStats {
+    pub count: usize,
+    pub mean_temperature: f32,
+    pub solid: usize,
+    pub liquid: usize,
+    pub gas: usize,
+}
+
+/// GUI から要求されるグループ操作
+#[derive(Debug, Clone)]
+pub enum GroupAction {
+    Create(String),
+    Delete(GroupId), // グループとそのドットをすべて削除
+    SetVisible(GroupId, bool),
+    SetFrozen(GroupId, bool),
+    AssignSelected(Option<GroupId>), // 選択中のドットの所属を変更
+    SetBrushGroup(Option<GroupId>),  // 新しく置くドットの所属
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DotGroups {
+    groups: Vec<DotGroup>,
+    next_id: GroupId,
+    pub brush_group: Option<GroupId>,
+}
+
+impl DotGroups {
+    pub fn create(&mut self, name: &str) -> GroupId {
+        let id = self.next_id;
+        self.next_id += 1;
+        let name = match name.trim() {
+            "" => format!("Group {}", id + 1),
+            trimmed => trimmed.to_string(),
+        };
+        self.groups.push(DotGroup {
+            id,
+            name,
+            visible: true,
+            frozen: false,
+        });
+        id
+    }
+
+    pub fn remove(&mut self, id: GroupId) {
+        self.groups.retain(|group| group.id != id);
+        if self.brush_group == Some(id) {
+            self.brush_group = None;
+        }
+    }
+
+    pub fn get_mut(&mut self, id: GroupId) -> Option<&mut DotGroup> {
+        self.groups.iter_mut().find(|group| group.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DotGroup> {
+        self.groups.iter()
+    }
+
+    pub fn is_visible(&self, id: Option<GroupId>) -> bool {
+        id.and_then(|id| self.groups.iter().find(|group| group.id == id))
+            .is_none_or(|group| group.visible)
+    }
+
+    pub fn is_frozen(&self, id: Option<GroupId>) -> bool {
+        id.and_then(|id| self.groups.iter().find(|group| group.id == id))
+            .is_some_and(|group| group.frozen)
+    }
+
+    /// グループごとの統計を groups と同じ順で返す
+    pub fn stats(&self, dots: &[Dot]) -> Vec<GroupStats> {
+        let mut stats = vec![GroupStats::default(); self.groups.len()];
+        for dot in dots {
+            let Some(index) = dot
+                .group
+                .and_then(|id| self.groups.iter().position(|group| group.id == id))
+            else {
+                continue;
+            };
+            let entry = &mut stats[index];
+            entry.count += 1;
+            entry.mean_temperature += dot.material.temperature;
+            match dot.material.state {
+                State::Solid => entry.solid += 1,
+                State::Liquid => entry.liquid += 1,
+                State::Gas => entry.gas += 1,
+            }
+        }
+        for entry in stats.iter_mut().filter(|entry| entry.count > 0) {
+            entry.mean_temperature /= entry.count as f32;
+        }
+        stats
+    }
+}
@@ -0,0 +1,218 @@
+//! 実験ジャーナル (節目の自動記録)
+//!
+//! 初めての気体、最大の爆発 (一度に砕けたドットの数)、最高温度の更新、反応で生まれた
+//! 新しい物質を、起動からの経過時間とともに記録する。物質の発見以外はその時点の画面を
+//! PNG で journal/ に保存し、Markdown に書き出すと画像つきの実験ノートになる。
+//! 画面はドットの色だけで描き直したもので、グローなどの描画効果は含まない。
+
+use crate::app::{Dot, HEIGHT, WIDTH};
+use crate::commands::{Command, CommandRegistry};
+use crate::material::{MaterialDNA, State};
+use crate::physics::DOT_RADIUS;
+use crate::renderer::temperature::TemperatureScale;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// 書き出し先 (カレントディレクトリ)
+pub const JOURNAL_DIR: &str = "journal";
+const MARKDOWN_FILE: &str = "journal.md";
+
+const OBSERVE_INTERVAL: f64 = 0.5; // 気体と温度を調べる間隔 (秒)
+const MIN_EXPLOSION: usize = 3; // これ以上のドットが一度に砕けたら爆発として扱う
+const HOTTEST_STEP: f32 = 0.1; // 最高温度がこれ以上更新されたら記録する
+const DISCOVERY_INTERVAL: f64 = 10.0; // 発見した物質はこの間隔でまとめて記録する
+const MAX_LISTED_DISCOVERIES: usize = 8; // 1件の記録に名前を載せる物質の数
+
+pub struct JournalEntry {
+    pub elapsed: f64, // 起動からの秒数
+    pub title: String,
+    pub detail: String,
+    pub screenshot: Option<String>, // JOURNAL_DIR 内のファイル名
+}
+
+pub struct Journal {
+    pub entries: Vec<JournalEntry>,
+    session: u64, // スクリーンショットのファイル名に付ける起動時刻 (UNIX 秒)
+    since_observe: f64,
+    seen_gas: bool,
+    biggest_explosion: usize,
+    hottest: Option<f32>, // None なら最初の観測を基準にする (記録しない)
+    known_materials: HashSet<u64>,
+    pending_discoveries: Vec<String>,
+    since_discovery: f64,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        let session = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Self {
+            entries: Vec::new(),
+            session,
+            since_observe: 0.0,
+            seen_gas: false,
+            biggest_explosion: 0,
+            hottest: None,
+            known_materials: HashSet::new(),
+            pending_discoveries: Vec::new(),
+            since_discovery: 0.0,
+        }
+    }
+}
+
+impl Journal {
+    /// 毎フレーム呼ぶ。一定間隔で気体と最高温度を調べ、発見した物質をまとめて記録する
+    pub fn observe(&mut self, dots: &[Dot], elapsed: f64, dt: f64, temperature: &TemperatureScale) {
+        self.since_discovery += dt;
+        if self.since_discovery >= DISCOVERY_INTERVAL && !self.pending_discoveries.is_empty() {
+            self.since_discovery = 0.0;
+            self.flush_discoveries(elapsed);
+        }
+
+        self.since_observe += dt;
+        if self.since_observe < OBSERVE_INTERVAL {
+            return;
+        }
+        self.since_observe = 0.0;
+
+        let visible = || dots.iter().filter(|dot| !dot.hidden);
+        if !self.seen_gas {
+            if let Some(gas) = visible().find(|dot| dot.material.state == State::Gas) {
+                self.seen_gas = true;
+                let detail = format!("{} appeared.", gas.name);
+                self.record(dots, elapsed, "First gas".to_string(), detail, true);
+            }
+        }
+
+        let Some(hottest) = visible().max_by(|a, b| a.material.temperature.total_cmp(&b.material.temperature)) else {
+            return;
+        };
+        let value = hottest.material.temperature;
+        match self.hottest {
+            None => self.hottest = Some(value),
+            Some(record) if value >= record + HOTTEST_STEP => {
+                self.hottest = Some(value);
+                let detail = format!("{} reached {}.", hottest.name, temperature.format(value));
+                self.record(dots, elapsed, "Hottest temperature".to_string(), detail, true);
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// 一度に砕けたドットの数が過去最大なら爆発として記録する
+    pub fn record_explosion(&mut self, dots: &[Dot], elapsed: f64, shattered: usize) {
+        if shattered < MIN_EXPLOSION || shattered <= self.biggest_explosion {
+            return;
+        }
+        self.biggest_explosion = shattered;
+        let detail = format!("{} dots shattered at once.", shattered);
+        self.record(dots, elapsed, "Biggest explosion".to_string(), detail, true);
+    }
+
+    /// 反応で生まれた物質を、初めてなら発見として記録待ちにする
+    pub fn record_discovery(&mut self, dna: &MaterialDNA) {
+        if self.known_materials.insert(dna.seed) {
+            self.pending_discoveries.push(crate::naming::generate_name(dna));
+        }
+    }
+
+    fn flush_discoveries(&mut self, elapsed: f64) {
+        let names = std::mem::take(&mut self.pending_discoveries);
+        let mut detail = names.iter().take(MAX_LISTED_DISCOVERIES).cloned().collect::<Vec<_>>().join(", ");
+        if names.len() > MAX_LISTED_DISCOVERIES {
+            let _ = write!(detail, " and {} more", names.len() - MAX_LISTED_DISCOVERIES);
+        }
+        let title = match names.len() {
+            1 => "New material".to_string(),
+            count => format!("{} new materials", count),
+        };
+        self.record(&[], elapsed, title, detail, false);
+    }
+
+    fn record(&mut self, dots: &[Dot], elapsed: f64, title: String, detail: String, screenshot: bool) {
+        tracing::info!(target: crate::logging::APP, elapsed, "journal: {}: {}", title, detail);
+        let screenshot = screenshot.then(|| {
+            let file = format!("{}_{:03}.png", self.session, self.entries.len());
+            save_screenshot(dots, Path::new(JOURNAL_DIR).join(&file));
+            file
+        });
+        self.entries.push(JournalEntry {
+            elapsed,
+            title,
+            detail,
+            screenshot,
+        });
+    }
+
+    /// Markdown に変換する (画像は JOURNAL_DIR からの相対パス)
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Experiment journal\n");
+        for entry in &self.entries {
+            let _ = writeln!(out, "\n## {} {}\n\n{}", format_elapsed(entry.elapsed), entry.title, entry.detail);
+            if let Some(file) = &entry.screenshot {
+                let _ = writeln!(out, "\n![{}]({})", entry.title, file);
+            }
+        }
+        out
+    }
+
+    /// JOURNAL_DIR に Markdown を書き出し、そのパスを返す
+    pub fn export_markdown(&self) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(JOURNAL_DIR)?;
+        let path = Path::new(JOURNAL_DIR).join(MARKDOWN_FILE);
+        std::fs::write(&path, self.to_markdown())?;
+        Ok(path)
+    }
+}
+
+/// 経過時間を mm:ss (1時間以上なら h:mm:ss) で表示する
+pub fn format_elapsed(elapsed: f64) -> String {
+    let seconds = elapsed.max(0.0) as u64;
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// ドットを物質の色の円としてワールド全体の画像に描き、別スレッドで PNG に保存する
+fn save_screenshot(dots: &[Dot], path: PathBuf) {
+    let (width, height) = (WIDTH as usize, HEIGHT as usize);
+    let mut pixels = vec![0u8; width * height * 3];
+    for dot in dots.iter().filter(|dot| !dot.hidden) {
+        let (r, g, b) = dot.material.get_color_rgb();
+        let radius = DOT_RADIUS * dot.scale;
+        let x_range = ((dot.x - radius).floor().max(0.0) as usize)..=((dot.x + radius).ceil().min(width as f64 - 1.0) as usize);
+        let y_range = ((dot.y - radius).floor().max(0.0) as usize)..=((dot.y + radius).ceil().min(height as f64 - 1.0) as usize);
+        for y in y_range {
+            for x in x_range.clone() {
+                let (dx, dy) = (x as f64 + 0.5 - dot.x, y as f64 + 0.5 - dot.y);
+                if dx * dx + dy * dy <= radius * radius {
+                    let i = (y * width + x) * 3;
+                    pixels[i..i + 3].copy_from_slice(&[r, g, b]);
+                }
+            }
+        }
+    }
+
+    std::thread::spawn(move || {
+        let result = std::fs::create_dir_all(JOURNAL_DIR)
+            .and_then(|_| std::fs::File::create(&path))
+            .and_then(|file| {
+                let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), WIDTH, HEIGHT);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+                writer.write_image_data(&pixels).map_err(std::io::Error::other)
+            });
+        if let Err(error) = result {
+            tracing::warn!(target: crate::logging::APP, path = %path.display(), "journal screenshot failed: {}", error);
+        }
+    });
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Journal", "Export to Markdown", Command::ExportJournal);
+}
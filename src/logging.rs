@@ -0,0 +1,111 @@
+//! サブシステムごとのログ出力
+//!
+//! `tracing` のターゲットをサブシステム名 (physics / worker / renderer / app) にして出力する。
+//! 詳細度は環境変数 RUST_LOG で指定できる (例: `RUST_LOG=physics=debug,renderer=trace`)。
+//! 標準エラー出力に加えて直近のログをリングバッファに保持し、アプリ内の Log ウィンドウで表示する。
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+pub const PHYSICS: &str = "physics";
+pub const WORKER: &str = "worker";
+pub const RENDERER: &str = "renderer";
+pub const APP: &str = "app";
+
+/// RUST_LOG が無い場合の既定値 (依存クレートは警告以上のみ)
+const DEFAULT_FILTER: &str = "warn,physics=info,worker=info,renderer=info,app=info";
+const BUFFER_CAPACITY: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    pub elapsed: f64, // 起動からの秒数
+}
+
+/// アプリ内表示用のログのリングバッファ
+#[derive(Clone)]
+pub struct LogBuffer {
+    entries: Arc<Mutex<VecDeque<LogEntry>>>,
+    start: std::time::Instant,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(BUFFER_CAPACITY))),
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+impl LogBuffer {
+    fn push(&self, entry: LogEntry) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        if entries.len() == BUFFER_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// level 以上 (より重要) のログを古い順に返す
+    pub fn entries(&self, level: Level) -> Vec<LogEntry> {
+        self.entries
+            .lock()
+            .map(|entries| entries.iter().filter(|entry| entry.level <= level).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+struct BufferLayer {
+    buffer: LogBuffer,
+}
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+        self.buffer.push(LogEntry {
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: visitor.0,
+            elapsed: self.buffer.start.elapsed().as_secs_f64(),
+        });
+    }
+}
+
+/// message フィールドとその他のフィールドを1行にまとめる
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        if field.name() == "message" {
+            self.0.push_str(&format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!("{}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// ロガーを初期化し、アプリ内表示用のバッファを返す
+pub fn init() -> LogBuffer {
+    let buffer = LogBuffer::default();
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(BufferLayer { buffer: buffer.clone() })
+        .init();
+    buffer
+}
@@ -0,0 +1,287 @@
+//! ツール操作のマクロ (記録と再生)
+//!
+//! 記録中はブラシで置いた位置 (最初に置いた位置からの相対座標)、ブラシの物質の切り替え、
+//! ブラシの形と大きさの変更を順に記録する。再生するとカーソル位置を原点に、指定した倍率で
+//! 一度に置き直す (ブラシの半径も同じ倍率にする)。再生のあとはブラシを再生前の状態に戻す。
+//! マクロはユーザーごとの設定ディレクトリに保存され、F5 ~ F12 に割り当てられる。
+
+use crate::brush::{Brush, MAX_RADIUS};
+use crate::commands::{Command, CommandRegistry};
+use crate::material::MaterialDNA;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const MACRO_FILE: &str = "macros.json";
+const FORMAT_VERSION: u32 = 1;
+pub const MIN_SCALE: f64 = 0.25;
+pub const MAX_SCALE: f64 = 4.0;
+
+/// Macros パネルとコマンドパレットからの操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroAction {
+    StartRecording,
+    StopRecording,
+    Play(usize), // 画面の中央で再生する (ホットキーならカーソル位置)
+    Rename(usize, String),
+    SetHotkey(usize, Option<MacroKey>),
+    Delete(usize),
+    SetScale(f64),
+}
+
+/// マクロを割り当てられるキー
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MacroKey {
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+}
+
+impl MacroKey {
+    pub const ALL: [MacroKey; 8] = [
+        MacroKey::F5,
+        MacroKey::F6,
+        MacroKey::F7,
+        MacroKey::F8,
+        MacroKey::F9,
+        MacroKey::F10,
+        MacroKey::F11,
+        MacroKey::F12,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MacroKey::F5 => "F5",
+            MacroKey::F6 => "F6",
+            MacroKey::F7 => "F7",
+            MacroKey::F8 => "F8",
+            MacroKey::F9 => "F9",
+            MacroKey::F10 => "F10",
+            MacroKey::F11 => "F11",
+            MacroKey::F12 => "F12",
+        }
+    }
+
+    pub fn from_key_code(code: winit::keyboard::KeyCode) -> Option<Self> {
+        use winit::keyboard::KeyCode;
+        Some(match code {
+            KeyCode::F5 => MacroKey::F5,
+            KeyCode::F6 => MacroKey::F6,
+            KeyCode::F7 => MacroKey::F7,
+            KeyCode::F8 => MacroKey::F8,
+            KeyCode::F9 => MacroKey::F9,
+            KeyCode::F10 => MacroKey::F10,
+            KeyCode::F11 => MacroKey::F11,
+            KeyCode::F12 => MacroKey::F12,
+            _ => return None,
+        })
+    }
+}
+
+/// 記録される操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MacroStep {
+    Stamp { x: f64, y: f64 }, // 最初に置いた位置からの相対座標 (ワールド座標)
+    SetMaterial(MaterialDNA),
+    SetBrush(Brush),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    pub hotkey: Option<MacroKey>,
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    pub fn stamp_count(&self) -> usize {
+        self.steps.iter().filter(|step| matches!(step, MacroStep::Stamp { .. })).count()
+    }
+
+    /// (x, y) を原点に scale 倍した操作の列 (ブラシの半径も scale 倍する)
+    pub fn placed(&self, x: f64, y: f64, scale: f64) -> impl Iterator<Item = MacroStep> + '_ {
+        self.steps.iter().map(move |step| match step {
+            MacroStep::Stamp { x: dx, y: dy } => MacroStep::Stamp {
+                x: x + dx * scale,
+                y: y + dy * scale,
+            },
+            MacroStep::SetBrush(brush) => MacroStep::SetBrush(Brush {
+                radius: (brush.radius * scale).min(MAX_RADIUS),
+                ..*brush
+            }),
+            step => step.clone(),
+        })
+    }
+}
+
+/// 記録中のマクロ
+#[derive(Debug)]
+pub struct MacroRecorder {
+    origin: Option<(f64, f64)>, // 最初に置いた位置
+    steps: Vec<MacroStep>,
+}
+
+impl MacroRecorder {
+    /// 記録開始時のブラシから記録する (再生したときに同じ物質と形で始まるように)
+    pub fn new(material: MaterialDNA, brush: Brush) -> Self {
+        Self {
+            origin: None,
+            steps: vec![MacroStep::SetMaterial(material), MacroStep::SetBrush(brush)],
+        }
+    }
+
+    pub fn stamp(&mut self, x: f64, y: f64) {
+        let (origin_x, origin_y) = *self.origin.get_or_insert((x, y));
+        self.steps.push(MacroStep::Stamp { x: x - origin_x, y: y - origin_y });
+    }
+
+    pub fn push(&mut self, step: MacroStep) {
+        self.steps.push(step);
+    }
+
+    pub fn stamp_count(&self) -> usize {
+        self.steps.iter().filter(|step| matches!(step, MacroStep::Stamp { .. })).count()
+    }
+
+    /// 記録を終えてマクロにする (何も置いていなければ None)
+    pub fn finish(self, name: String) -> Option<Macro> {
+        self.origin?;
+        Some(Macro {
+            name,
+            hotkey: None,
+            steps: self.steps,
+        })
+    }
+}
+
+/// マクロの一覧と記録・再生の状態
+pub struct Macros {
+    pub library: MacroLibrary,
+    pub recording: Option<MacroRecorder>,
+    pub scale: f64, // 再生するときの倍率
+}
+
+impl Default for Macros {
+    fn default() -> Self {
+        Self {
+            library: MacroLibrary::default(),
+            recording: None,
+            scale: 1.0,
+        }
+    }
+}
+
+impl Macros {
+    /// 記録中なら操作を記録する
+    pub fn record(&mut self, step: MacroStep) {
+        if let Some(recorder) = &mut self.recording {
+            recorder.push(step);
+        }
+    }
+
+    /// 記録中ならブラシを置いた位置を記録する
+    pub fn record_stamp(&mut self, x: f64, y: f64) {
+        if let Some(recorder) = &mut self.recording {
+            recorder.stamp(x, y);
+        }
+    }
+
+    /// 記録を終えて一覧に加える (何も置いていなければ捨てる)
+    pub fn stop_recording(&mut self) -> Option<&Macro> {
+        let recorder = self.recording.take()?;
+        let name = format!("Macro {}", self.library.macros.len() + 1);
+        let recorded = recorder.finish(name)?;
+        self.library.macros.push(recorded);
+        self.library.macros.last()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MacroLibrary {
+    pub version: u32,
+    pub macros: Vec<Macro>,
+}
+
+impl Default for MacroLibrary {
+    fn default() -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            macros: Vec::new(),
+        }
+    }
+}
+
+impl MacroLibrary {
+    /// キーが割り当てられたマクロの番号
+    pub fn position_by_key(&self, key: MacroKey) -> Option<usize> {
+        self.macros.iter().position(|m| m.hotkey == Some(key))
+    }
+
+    /// index のマクロに key を割り当てる (他のマクロに割り当てられていれば外す)
+    pub fn assign_key(&mut self, index: usize, key: Option<MacroKey>) {
+        if key.is_some() {
+            for m in self.macros.iter_mut().filter(|m| m.hotkey == key) {
+                m.hotkey = None;
+            }
+        }
+        if let Some(m) = self.macros.get_mut(index) {
+            m.hotkey = key;
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let library: MacroLibrary = serde_json::from_str(&json).map_err(std::io::Error::other)?;
+        if library.version > FORMAT_VERSION {
+            return Err(std::io::Error::other(format!("unsupported macro library version {}", library.version)));
+        }
+        Ok(library)
+    }
+}
+
+/// ユーザーごとのマクロの保存先
+pub fn library_path() -> PathBuf {
+    config_path(MACRO_FILE)
+}
+
+/// ユーザーごとの設定ファイルの保存先 (Windows は %APPDATA%、それ以外は $XDG_CONFIG_HOME か ~/.config)
+pub fn config_path(file: &str) -> PathBuf {
+    let config_dir = std::env::var_os("APPDATA")
+        .or_else(|| std::env::var_os("XDG_CONFIG_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    match config_dir {
+        Some(dir) => dir.join("terraspiel").join(file),
+        None => PathBuf::from(file),
+    }
+}
+
+/// 保存されたマクロを読み込む (なければ空。読めなければ警告して空)
+pub fn load_library() -> MacroLibrary {
+    let path = library_path();
+    match MacroLibrary::load(&path) {
+        Ok(library) => library,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => MacroLibrary::default(),
+        Err(error) => {
+            tracing::warn!(target: crate::logging::APP, path = %path.display(), "macro library load failed: {}", error);
+            MacroLibrary::default()
+        }
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Macro", "Start recording", Command::Macro(MacroAction::StartRecording));
+    registry.register("Macro", "Stop recording", Command::Macro(MacroAction::StopRecording));
+}
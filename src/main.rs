@@ -1,12 +1,31 @@
 mod app;
+mod bench;
+mod brush;
+mod commands;
+mod crash;
+mod gamepad;
+mod groups;
+mod journal;
+mod logging;
+mod macros;
 mod material;
 mod naming;
 mod physics;
+mod presets;
+mod puzzle;
+mod reaction_graph;
+mod replay;
 mod renderer;
+mod scene;
+mod seed_bank;
+mod snapshot;
+mod tabs;
+mod tools;
+mod weather;
+mod worldgen;
 
 use app::{App, BlendResult};
 use clap::Parser;
-use material::{decide_reaction_type, from_dna, ReactionType};
 use rayon::prelude::*;
 use std::sync::mpsc;
 use std::thread;
@@ -20,10 +39,52 @@ struct Args {
     /// Enable test mode with specified max dot count
     #[arg(long, value_name = "MAX_DOTS")]
     test_mode: Option<u32>,
+
+    /// Gamepad stick dead zone (0.0 - 0.95, overrides the saved setting)
+    #[arg(long, value_name = "DEAD_ZONE")]
+    gamepad_dead_zone: Option<f32>,
+
+    /// Run the physics deterministically from this seed
+    #[arg(long, value_name = "SEED")]
+    seed: Option<u64>,
+
+    /// Play back a recorded input replay on startup
+    #[arg(long, value_name = "PATH")]
+    replay: Option<std::path::PathBuf>,
+
+    /// Run the headless benchmark scenarios and exit
+    #[arg(long)]
+    bench_suite: bool,
+
+    /// Physics steps per benchmark scenario
+    #[arg(long, value_name = "STEPS", default_value_t = 300)]
+    bench_steps: u32,
+
+    /// Where to write the benchmark report (JSON)
+    #[arg(long, value_name = "PATH", default_value = "bench_report.json")]
+    bench_output: std::path::PathBuf,
+
+    /// Compare the benchmark results against an earlier report
+    #[arg(long, value_name = "PATH")]
+    bench_baseline: Option<std::path::PathBuf>,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
+    let log_buffer = logging::init();
+    let crash_reporter = crash::CrashReporter::install(log_buffer.clone());
+
+    if args.bench_suite {
+        let report = bench::run(args.bench_steps);
+        std::fs::write(&args.bench_output, serde_json::to_string_pretty(&report)?)?;
+        println!("bench report written to {}", args.bench_output.display());
+        if let Some(baseline) = &args.bench_baseline {
+            let baseline: bench::BenchReport = serde_json::from_str(&std::fs::read_to_string(baseline)?)?;
+            print!("{}", bench::compare(&baseline, &report));
+        }
+        return Ok(());
+    }
+
     let is_test_mode_enabled = args.test_mode.is_some();
     let max_test_dots = args.test_mode.unwrap_or(0);
 
@@ -35,81 +96,56 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (result_tx, result_rx) = mpsc::channel::<BlendResult>();
 
     let mut app = App::new(collision_tx, result_rx, is_test_mode_enabled, max_test_dots);
+    let mut gamepad_settings = gamepad::load_settings();
+    if let Some(dead_zone) = args.gamepad_dead_zone {
+        gamepad_settings.dead_zone = dead_zone;
+    }
+    app.gamepad = gamepad::Gamepad::new(gamepad_settings);
+    if let Some(seed) = args.seed {
+        app.rng = physics::GameRng::seeded(seed);
+        tracing::info!(target: logging::APP, seed, "deterministic physics");
+    }
+    if let Some(path) = &args.replay {
+        app.play_replay(path);
+    }
+    app.log_buffer = log_buffer;
+    app.crash_reporter = Some(crash_reporter);
 
     // --- ワーカースレッドを起動 ---
     thread::spawn(move || {
         // 衝突イベントをバッチ処理するためのベクトル
         let mut collision_batch = Vec::with_capacity(1024);
-
-        loop {
-            // 最初のイベントをブロックして待つ
-            match collision_rx.recv() {
-                Ok(first_event) => {
-                    collision_batch.push(first_event);
-                    // キューに残っているイベントをすべて取得
-                    collision_batch.extend(collision_rx.try_iter());
-
-                    // バッチを並列処理
-                    let results: Vec<BlendResult> = collision_batch
-                        .par_iter()
-                        .flat_map(|((index_a, dna_a), (index_b, dna_b))| {
-                            if dna_a.seed == dna_b.seed {
-                                return Vec::new(); // 同じseedを持つドットはブレンドしない
-                            }
-
-                            let params_a = from_dna(dna_a);
-                            let params_b = from_dna(dna_b);
-
-                            let reaction_type = decide_reaction_type(params_a.state, params_b.state);
-                            let new_dna = dna_a.blend(dna_b, 0.5);
-
-                            let mut results = Vec::new();
-
-                            match reaction_type {
-                                ReactionType::Reaction => {
-                                    results.push(BlendResult::Change { index: *index_a, new_dna: new_dna.clone() });
-                                    results.push(BlendResult::Change { index: *index_b, new_dna });
-                                }
-                                ReactionType::CatalyticLowChanges => {
-                                    let energy_a = params_a.state.get_energy_level();
-                                    let energy_b = params_b.state.get_energy_level();
-                                    if energy_a < energy_b {
-                                        results.push(BlendResult::Change { index: *index_a, new_dna });
-                                    } else {
-                                        results.push(BlendResult::Change { index: *index_b, new_dna });
-                                    }
-                                }
-                                ReactionType::CatalyticHighChangesAndLowVanishes => {
-                                    let energy_a = params_a.state.get_energy_level();
-                                    let energy_b = params_b.state.get_energy_level();
-                                    if energy_a > energy_b {
-                                        results.push(BlendResult::Change { index: *index_a, new_dna });
-                                        results.push(BlendResult::Vanish { index: *index_b });
-                                    } else {
-                                        results.push(BlendResult::Change { index: *index_b, new_dna });
-                                        results.push(BlendResult::Vanish { index: *index_a });
-                                    }
-                                }
-                            }
-                            results
-                        })
-                        .collect();
-
-                    // 結果をメインスレッドに送信
-                    for result in results {
-                        if result_tx.send(result).is_err() {
-                            // メインスレッドが終了した場合
-                            break;
-                        }
-                    }
-
-                    collision_batch.clear();
-                }
-                Err(_) => {
-                    // 送信側がすべて破棄されたらループを抜ける
+        let mut results: Vec<BlendResult> = Vec::new(); // バッチの結果 (バッチをまたいで再利用する)
+
+        // 最初のイベントをブロックして待つ (送信側がすべて破棄されたらループを抜ける)
+        while let Ok(first_event) = collision_rx.recv() {
+            collision_batch.push(first_event);
+            // キューに残っているイベントをすべて取得
+            collision_batch.extend(collision_rx.try_iter());
+
+            // バッチを並列処理
+            let mutation = material::mutation::settings();
+            let batch_results = collision_batch
+                .par_iter()
+                .flat_map(|(a, b)| app::blend_collision(a, b, mutation));
+            results.par_extend(batch_results);
+
+            tracing::trace!(
+                target: logging::WORKER,
+                collisions = collision_batch.len(),
+                results = results.len(),
+                "processed blend batch"
+            );
+
+            // 結果をメインスレッドに送信
+            for result in results.drain(..) {
+                if result_tx.send(result).is_err() {
+                    // メインスレッドが終了した場合
                     break;
                 }
             }
+
+            collision_batch.clear();
         }
     });
 
@@ -126,6 +162,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         None => return,
                     };
 
+                    // クラッシュレポート用に直近のイベントを記録する (毎フレームの再描画要求は除く)
+                    if !matches!(event, WindowEvent::RedrawRequested) {
+                        if let Some(crash_reporter) = &app.crash_reporter {
+                            crash_reporter.record_event(format!("{:?}", event));
+                        }
+                    }
+
                     let consumed_by_egui = app.handle_window_event(&window, &event);
                     if consumed_by_egui {
                         return;
@@ -145,6 +188,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             WindowEvent::MouseInput { state, button, .. } => {
                                 app.handle_mouse_input(state, button);
                             }
+                            WindowEvent::MouseWheel { delta, .. } => {
+                                app.handle_mouse_wheel(delta);
+                            }
+                            WindowEvent::KeyboardInput { event, .. } => {
+                                app.handle_keyboard_input(&event);
+                            }
                             WindowEvent::RedrawRequested => {
                                 app.handle_redraw_requested();
                             }
@@ -1,3 +1,6 @@
+pub mod library;
+pub mod mutation;
+
 use palette::{FromColor, Hsl, RgbHue, Srgb};
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
@@ -23,7 +26,7 @@ impl State {
 }
 
 /// ブレンド反応の種類
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ReactionType {
     /// 相互変化: 両方の物質が変化する
     Reaction,
@@ -65,8 +68,14 @@ pub struct BaseMaterialParams {
     pub heat_conductivity: f32, // 熱伝導率 (0.0 ~ 1.0)
     pub heat_capacity_high: f32,     // 熱容量(高) (0.0 ~ 1.0)
     pub heat_capacity_low: f32,      // 熱容量(低) (-1.0 ~ 0.0)
+    pub melting_point: f32,     // 融点 (相対温度 -1.0 ~ 1.0)
+    pub boiling_point: f32,     // 沸点 (相対温度 融点 ~ 1.0)
+    pub flammability: f32,      // 可燃性 (0.0 ~ 1.0)
 
     // 電磁特性
+    pub conductivity: f32, // 電気伝導率 (0.0 ~ 1.0)
+    pub magnetism: f32,    // 磁性 (0.0 ~ 1.0)
+    pub charge: f32,       // 電荷 (-1.0 ~ 1.0、0.0 で中性)
 
     // 光・見た目系
     pub color_hue: f32,        // 色相 (0.0 ~ 1.0)
@@ -76,6 +85,12 @@ pub struct BaseMaterialParams {
     pub entropy_bias: f32,     // エントロピーバイアス (0.0 ~ 1.0)
     pub volatility: f32,       // 揮発性 (0.0 ~ 1.0)
     pub cohesion: f32,         // 凝集力 (0.0 ~ 1.0)
+
+    // 温度依存
+    pub thermal_expansion: f32, // 熱膨張係数 (0.0 ~ 1.0)
+
+    // 放射性
+    pub radioactivity: f32, // 放射能 (0.0 で安定、高いほど半減期が短く、よく熱を出す)
 }
 
 impl Default for BaseMaterialParams {
@@ -90,6 +105,12 @@ impl Default for BaseMaterialParams {
             heat_conductivity: 0.4,
             heat_capacity_high: 0.6,
             heat_capacity_low: -0.1,
+            melting_point: 0.2,
+            boiling_point: 0.6,
+            flammability: 0.0,
+            conductivity: 0.1,
+            magnetism: 0.0,
+            charge: 0.0,
             color_hue: 0.5,
             color_saturation: 0.8,
             color_luminance: 0.6,
@@ -97,6 +118,8 @@ impl Default for BaseMaterialParams {
             entropy_bias: 0.1,
             volatility: 0.3,
             cohesion: 0.2,
+            thermal_expansion: 0.3,
+            radioactivity: 0.0,
         }
     }
 }
@@ -115,6 +138,28 @@ impl BaseMaterialParams {
 
         (rgb.red, rgb.green, rgb.blue)
     }
+
+    /// 現在の温度に応じて粘度・硬度・密度を遺伝子の値から再計算する
+    /// 融点 (melting_point) に近づくほど粘度は下がり (温めた蜂蜜のように流れやすくなる)、
+    /// 硬度は融点の手前で急に下がる。密度は熱膨張係数に応じて温度とともに下がる
+    /// (thermal_expansion_enabled が false なら密度は遺伝子の値のまま = 対流が起きない)
+    pub fn apply_temperature_curves(&mut self, dna: &MaterialDNA, thermal_expansion_enabled: bool) {
+        // 下限 (-1.0) から融点までのどこにいるか (0.0 ~ 1.0)
+        let melting_point = self.melting_point;
+        let heat = ((self.temperature + 1.0) / (melting_point + 1.0).max(f32::EPSILON)).clamp(0.0, 1.0);
+
+        self.viscosity = dna.genes[2] * (1.0 - 0.8 * heat * heat);
+
+        // 融点の7割を超えたあたりから柔らかくなる (smoothstep)
+        let softening = ((heat - 0.7) / 0.3).clamp(0.0, 1.0);
+        let softening = softening * softening * (3.0 - 2.0 * softening);
+        self.hardness = dna.genes[3] * (1.0 - 0.9 * softening);
+
+        // 熱膨張: 温度 -1.0 で遺伝子どおり、1.0 で最大 (係数 × 40%) まで軽くなる
+        let warmth = (self.temperature.clamp(-1.0, 1.0) + 1.0) / 2.0;
+        let expansion = if thermal_expansion_enabled { self.thermal_expansion } else { 0.0 };
+        self.density = dna.genes[1] * (1.0 - 0.4 * expansion * warmth);
+    }
 }
 
 pub fn from_seed(seed: u64) -> BaseMaterialParams {
@@ -126,7 +171,7 @@ pub fn from_seed(seed: u64) -> BaseMaterialParams {
         _ => State::Gas,
     };
 
-    BaseMaterialParams {
+    let mut material = BaseMaterialParams {
         state,
         density: rng.gen(),
         viscosity: rng.gen(),
@@ -136,6 +181,12 @@ pub fn from_seed(seed: u64) -> BaseMaterialParams {
         heat_conductivity: rng.gen(),
         heat_capacity_high: rng.gen(),
         heat_capacity_low: rng.gen::<f32>() - 1.0,
+        melting_point: 0.0,
+        boiling_point: 0.0,
+        flammability: 0.0,
+        conductivity: 0.0,
+        magnetism: 0.0,
+        charge: 0.0,
         color_hue: rng.gen(),
         color_saturation: rng.gen(),
         color_luminance: rng.gen::<f32>() * 0.8 + 0.2, // 0.2-1.0の範囲にマッピング
@@ -143,18 +194,175 @@ pub fn from_seed(seed: u64) -> BaseMaterialParams {
         entropy_bias: rng.gen(),
         volatility: rng.gen(),
         cohesion: rng.gen(),
-    }
+        thermal_expansion: rng.gen(),
+        radioactivity: 0.0,
+    };
+
+    // 後から追加した特性は最後に引く (既存の seed から生成される物質を変えないため)
+    material.melting_point = rng.gen::<f32>() * 2.0 - 1.0;
+    material.boiling_point = material.melting_point + rng.gen::<f32>() * (1.0 - material.melting_point);
+    material.flammability = rng.gen();
+    material.conductivity = rng.gen();
+    material.magnetism = rng.gen();
+    // 電荷を持つ物質は一部だけにする (多すぎると画面中が引き合って散らかる)
+    let charged = rng.gen::<f32>() < CHARGED_CHANCE;
+    let charge = rng.gen::<f32>() * 2.0 - 1.0;
+    material.charge = if charged { charge } else { 0.0 };
+    let radioactive = rng.gen::<f32>() < RADIOACTIVE_CHANCE;
+    let radioactivity = rng.gen::<f32>();
+    material.radioactivity = if radioactive { radioactivity } else { 0.0 };
+    material
 }
 
+/// seed から生成した物質が電荷を持つ確率
+const CHARGED_CHANCE: f32 = 0.2;
+/// seed から生成した物質が放射性になる確率
+const RADIOACTIVE_CHANCE: f32 = 0.1;
+
+/// 遺伝子の数
+pub const GENE_COUNT: usize = 24;
+/// 放射能の遺伝子の位置
+pub const RADIOACTIVITY_GENE: usize = 23;
+/// 放射能がこれ未満の物質は放射性とみなさない
+const MIN_RADIOACTIVITY: f32 = 0.05;
+/// 現在の遺伝子の並びのバージョン
+/// 1: 16個 (cohesion まで)
+/// 2: 熱膨張係数を追加した17個
+/// 3: 融点, 沸点, 可燃性, 電気伝導率, 磁性 を追加した22個
+/// 4: 電荷を追加した23個
+/// 5: 放射能を追加した24個
+pub const DNA_VERSION: u32 = 5;
+
 /// 物質のすべてを決定する数値列 (plan.md参照)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 保存された古い並びの DNA は読み込み時に現在の並びへ移行する
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SerializedDNA")]
 pub struct MaterialDNA {
     pub seed: u64,
-    /// 各特性を0〜1正規化した値。順序はBaseMaterialParamsのフィールドに対応。
-    pub genes: [f32; 16],
+    pub dna_version: u32,
+    /// 各特性を0〜1正規化した値。順序は from_dna / to_dna を参照。
+    pub genes: [f32; GENE_COUNT],
+    /// 不安定な物質か (一定時間後に崩壊して別の物質に変化する)
+    pub unstable: bool,
+}
+
+/// 保存された DNA
+/// バージョンがないのは並びにバージョンを付ける前の保存で、遺伝子の数からバージョンを判断する
+#[derive(Deserialize)]
+struct SerializedDNA {
+    seed: u64,
+    dna_version: Option<u32>,
+    genes: Vec<f32>,
+    #[serde(default)]
+    unstable: bool,
+}
+
+/// バージョンごとの遺伝子の数 (GENE_COUNTS[v - 1] がバージョン v)
+const GENE_COUNTS: [usize; DNA_VERSION as usize] = [16, 17, 22, 23, GENE_COUNT];
+
+/// バージョン v の並びを v + 1 の並びに変える処理 (MIGRATIONS[v - 1])
+/// 並びを変えるときは DNA_VERSION を上げ、GENE_COUNTS とここに1つずつ追加する
+const MIGRATIONS: [fn(&mut Vec<f32>); DNA_VERSION as usize - 1] =
+    [migrate_v1_to_v2, migrate_v2_to_v3, migrate_v3_to_v4, migrate_v4_to_v5];
+
+/// v1 -> v2: 保存されていた物質はすべて熱膨張しないものとする
+fn migrate_v1_to_v2(genes: &mut Vec<f32>) {
+    genes.push(0.0);
+}
+
+/// v2 -> v3: 融点は従来どおり熱容量(高)、沸点はそれと上限の中間、他は持たないものとする
+/// (融点・沸点の遺伝子は -1.0 ~ 1.0 を 0.0 ~ 1.0 に詰めた値なので、熱容量(高)の生の値から変換する)
+fn migrate_v2_to_v3(genes: &mut Vec<f32>) {
+    let melting_point = genes[7];
+    let boiling_point = (melting_point + 1.0) / 2.0;
+    genes.extend([(melting_point + 1.0) / 2.0, (boiling_point + 1.0) / 2.0, 0.0, 0.0, 0.0]);
+}
+
+/// v3 -> v4: 保存されていた物質はすべて中性とする
+fn migrate_v3_to_v4(genes: &mut Vec<f32>) {
+    genes.push(0.5);
+}
+
+/// v4 -> v5: 保存されていた物質はすべて放射性でないものとする
+fn migrate_v4_to_v5(genes: &mut Vec<f32>) {
+    genes.push(0.0);
+}
+
+/// バージョンのない保存の並び (熱膨張係数を足してからバージョンを付けるまでの間は17個で保存していた)
+fn unversioned_dna_version(genes: &[f32]) -> u32 {
+    if genes.len() == GENE_COUNTS[1] {
+        2
+    } else {
+        1
+    }
+}
+
+/// 保存されたバージョンの遺伝子を、移行を順に適用して現在の並びにする
+pub fn migrate_genes(version: u32, mut genes: Vec<f32>) -> Result<[f32; GENE_COUNT], String> {
+    if version == 0 || version > DNA_VERSION {
+        return Err(format!("unsupported DNA version {}", version));
+    }
+    let first = version as usize - 1;
+    if genes.len() != GENE_COUNTS[first] {
+        return Err(format!(
+            "DNA version {} must have {} genes, found {}",
+            version,
+            GENE_COUNTS[first],
+            genes.len()
+        ));
+    }
+    for (step, migrate) in MIGRATIONS.iter().enumerate().skip(first) {
+        migrate(&mut genes);
+        debug_assert_eq!(genes.len(), GENE_COUNTS[step + 1], "migration from DNA version {}", step + 1);
+    }
+    genes.try_into().map_err(|genes: Vec<f32>| format!("migrated DNA has {} genes", genes.len()))
+}
+
+impl TryFrom<SerializedDNA> for MaterialDNA {
+    type Error = String;
+
+    fn try_from(dna: SerializedDNA) -> Result<Self, Self::Error> {
+        Ok(Self {
+            seed: dna.seed,
+            dna_version: DNA_VERSION,
+            genes: migrate_genes(dna.dna_version.unwrap_or_else(|| unversioned_dna_version(&dna.genes)), dna.genes)?,
+            unstable: dna.unstable,
+        })
+    }
+}
+
+/// 不安定な物質が崩壊した結果
+pub struct Decay {
+    pub product: MaterialDNA,     // 崩壊後の物質 (さらに不安定な場合もある)
+    pub heat: f32,                // 周囲に放出する熱
+    pub gas: Option<MaterialDNA>, // 放出する気体 (揮発性が高い場合のみ)
 }
 
 impl MaterialDNA {
+    /// 放射性の物質の半減期 (秒)。放射能が高いほど短い (30 ~ 2秒)。放射性でなければ None
+    pub fn half_life(&self) -> Option<f64> {
+        let radioactivity = self.genes[RADIOACTIVITY_GENE];
+        (radioactivity >= MIN_RADIOACTIVITY).then_some(30.0 - 28.0 * radioactivity as f64)
+    }
+
+    /// 色と温度以外の遺伝子がほぼ同じか (同じ seed や、色だけが違うブレンドなど同系統の物質)
+    pub fn same_family(&self, other: &Self) -> bool {
+        const FAMILY_TOLERANCE: f32 = 0.1; // 遺伝子の差の平均の上限
+        const IGNORED: [usize; 4] = [5, 9, 10, 11]; // 温度, 色相, 彩度, 明度
+
+        if self.seed == other.seed {
+            return true;
+        }
+        let (sum, count) = self
+            .genes
+            .iter()
+            .zip(other.genes.iter())
+            .enumerate()
+            .filter(|(index, _)| !IGNORED.contains(index))
+            .fold((0.0, 0), |(sum, count), (_, (a, b))| (sum + (a - b).abs(), count + 1));
+        sum / count as f32 <= FAMILY_TOLERANCE
+    }
+
     /// 2つのDNAを線形補間でブレンドする
     /// ratio: 0.0でa、1.0でbになる
     pub fn blend(&self, other: &Self, ratio: f32) -> Self {
@@ -163,12 +371,12 @@ impl MaterialDNA {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
-        let mut new_genes = [0.0; 16];
+        let mut new_genes = [0.0; GENE_COUNT];
 
         // --- 他の特性は線形補間 ---
-        for i in 0..16 {
-            if i < 9 || i > 11 {
-                new_genes[i] = self.genes[i] * (1.0 - ratio) + other.genes[i] * ratio;
+        for (i, gene) in new_genes.iter_mut().enumerate() {
+            if !(9..=11).contains(&i) {
+                *gene = self.genes[i] * (1.0 - ratio) + other.genes[i] * ratio;
             }
         }
 
@@ -233,9 +441,62 @@ impl MaterialDNA {
             new_seed = 1;
         }
 
+        // エントロピーバイアスと揮発性が高いほど不安定な物質になりやすい (最大50%)
+        let unstable = rng.gen::<f32>() < new_genes[13] * new_genes[14] * 0.5;
+
         Self {
             seed: new_seed,
+            dna_version: DNA_VERSION,
             genes: new_genes,
+            unstable,
+        }
+    }
+
+    /// 不安定な物質を崩壊させ、派生した物質を作る
+    /// 状態は1段階エネルギーの高い方へ進み、エントロピーバイアスが下がるため連鎖はいずれ止まる
+    pub fn decay(&self) -> Decay {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut rng = StdRng::seed_from_u64(self.seed ^ 0x9E37_79B9_7F4A_7C15);
+        let mut genes = self.genes;
+
+        genes[0] = (genes[0] + 0.33).min(0.99); // Solid -> Liquid -> Gas
+        genes[1] *= 0.8; // 軽くなる
+        genes[5] = (genes[5] + 0.2).min(1.0); // 崩壊熱で温まる
+        genes[9] = (genes[9] + rng.gen_range(-0.1..0.1) + 1.0) % 1.0; // 色相が少しずれる
+        genes[13] *= 0.7;
+        genes[RADIOACTIVITY_GENE] *= 0.5; // 崩壊のたびに弱まり、いずれ安定する
+
+        let mut hasher = DefaultHasher::new();
+        for &gene in &genes {
+            gene.to_bits().hash(&mut hasher);
+        }
+        let seed = hasher.finish().max(1);
+
+        let product = Self {
+            seed,
+            dna_version: DNA_VERSION,
+            genes,
+            unstable: rng.gen::<f32>() < genes[13] * genes[14],
+        };
+
+        // 揮発性が高い物質は崩壊時に気体を放出する
+        let gas = (genes[14] > 0.5).then(|| {
+            let mut gas_genes = genes;
+            gas_genes[0] = 0.825;
+            Self {
+                seed: seed.wrapping_add(1).max(1),
+                dna_version: DNA_VERSION,
+                genes: gas_genes,
+                unstable: false,
+            }
+        });
+
+        Decay {
+            product,
+            heat: 0.2 + self.genes[14] * 0.3,
+            gas,
         }
     }
 }
@@ -266,6 +527,14 @@ pub fn from_dna(dna: &MaterialDNA) -> BaseMaterialParams {
         entropy_bias: dna.genes[13],
         volatility: dna.genes[14],
         cohesion: dna.genes[15],
+        thermal_expansion: dna.genes[16],
+        melting_point: dna.genes[17] * 2.0 - 1.0,
+        boiling_point: dna.genes[18] * 2.0 - 1.0,
+        flammability: dna.genes[19],
+        conductivity: dna.genes[20],
+        magnetism: dna.genes[21],
+        charge: dna.genes[22] * 2.0 - 1.0,
+        radioactivity: dna.genes[RADIOACTIVITY_GENE],
     }
 }
 
@@ -279,6 +548,7 @@ pub fn to_dna(params: &BaseMaterialParams, seed: u64) -> MaterialDNA {
 
     MaterialDNA {
         seed,
+        dna_version: DNA_VERSION,
         genes: [
             state_gene,
             params.density,
@@ -296,6 +566,105 @@ pub fn to_dna(params: &BaseMaterialParams, seed: u64) -> MaterialDNA {
             params.entropy_bias,
             params.volatility,
             params.cohesion,
+            params.thermal_expansion,
+            (params.melting_point + 1.0) / 2.0,
+            (params.boiling_point + 1.0) / 2.0,
+            params.flammability,
+            params.conductivity,
+            params.magnetism,
+            (params.charge + 1.0) / 2.0,
+            params.radioactivity,
         ],
+        unstable: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dna_from_genes(genes: [f32; GENE_COUNT]) -> MaterialDNA {
+        MaterialDNA { seed: 1, dna_version: DNA_VERSION, genes, unstable: false }
+    }
+
+    #[test]
+    fn v1_migrates_without_thermal_expansion() {
+        let genes = migrate_genes(1, vec![0.5; 16]).unwrap();
+        assert_eq!(genes.len(), 24);
+        assert_eq!(genes[..16], [0.5; 16]);
+        assert_eq!(genes[16], 0.0); // 熱膨張係数
+        assert_eq!(genes[19..22], [0.0, 0.0, 0.0]); // 可燃性, 電気伝導率, 磁性
+    }
+
+    #[test]
+    fn v2_melting_and_boiling_points_decode_from_heat_capacity() {
+        let mut v2 = vec![0.0; 17];
+        v2[7] = 0.4; // heat_capacity_high
+        let material = from_dna(&dna_from_genes(migrate_genes(2, v2).unwrap()));
+        assert!((material.melting_point - 0.4).abs() < 1e-6);
+        assert!((material.boiling_point - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn v2_migrates_to_current() {
+        let genes = migrate_genes(2, vec![0.5; 17]).unwrap();
+        assert_eq!(genes.len(), 24);
+        assert_eq!(genes[..17], [0.5; 17]);
+        assert_eq!(genes[19..22], [0.0, 0.0, 0.0]); // 可燃性, 電気伝導率, 磁性
+    }
+
+    #[test]
+    fn v3_migrates_to_current() {
+        let genes = migrate_genes(3, vec![0.25; 22]).unwrap();
+        assert_eq!(genes.len(), 24);
+        assert_eq!(genes[..22], [0.25; 22]);
+    }
+
+    #[test]
+    fn v4_migrates_to_neutral_and_stable() {
+        let genes = migrate_genes(4, vec![0.75; 23]).unwrap();
+        assert_eq!(genes[..22], [0.75; 22]);
+        assert_eq!(genes[22], 0.75); // 保存されていた電荷はそのまま
+        assert_eq!(genes[RADIOACTIVITY_GENE], 0.0);
+
+        let genes = migrate_genes(3, vec![0.75; 22]).unwrap();
+        assert_eq!(genes[22], 0.5); // 電荷は中性
+        assert_eq!(genes[RADIOACTIVITY_GENE], 0.0);
+    }
+
+    #[test]
+    fn current_version_passes_through() {
+        let original: Vec<f32> = (0..GENE_COUNT).map(|i| i as f32 / GENE_COUNT as f32).collect();
+        let genes = migrate_genes(DNA_VERSION, original.clone()).unwrap();
+        assert_eq!(genes.to_vec(), original);
+    }
+
+    #[test]
+    fn rejects_unknown_versions_and_wrong_gene_counts() {
+        assert!(migrate_genes(0, vec![0.0; 16]).is_err());
+        assert!(migrate_genes(DNA_VERSION + 1, vec![0.0; GENE_COUNT]).is_err());
+        assert!(migrate_genes(1, vec![0.0; 17]).is_err());
+        assert!(migrate_genes(3, vec![0.0; 17]).is_err());
+        assert!(migrate_genes(DNA_VERSION, vec![0.0; 22]).is_err());
+    }
+
+    #[test]
+    fn legacy_json_without_version_deserializes() {
+        // 元の16個の並びと、熱膨張係数を足した17個の並び
+        for count in [16, 17] {
+            let genes: Vec<String> = (0..count).map(|i| format!("{}", i as f32 / 20.0)).collect();
+            let json = format!(r#"{{"seed": 42, "genes": [{}]}}"#, genes.join(", "));
+            let dna: MaterialDNA = serde_json::from_str(&json).unwrap();
+            assert_eq!(dna.seed, 42);
+            assert_eq!(dna.dna_version, DNA_VERSION);
+            assert!(!dna.unstable);
+            assert_eq!(dna.genes[5], 0.25);
+            assert_eq!(dna.genes[16], if count == 16 { 0.0 } else { 0.8 });
+            assert_eq!(dna.genes[RADIOACTIVITY_GENE], 0.0);
+
+            // 保存し直すと現在のバージョンとして読み戻せる
+            let saved = serde_json::to_string(&dna).unwrap();
+            assert_eq!(serde_json::from_str::<MaterialDNA>(&saved).unwrap(), dna);
+        }
     }
 }
@@ -0,0 +1,303 @@
+//! 名前を付けた物質のプリセット (ライブラリ)
+//!
+//! 物質の特性 (BaseMaterialParams) と DNA を組にして、1つの物質を1つの JSON ファイルとして
+//! materials/ に保存する。起動時にディレクトリのファイルをすべて読み込むので、
+//! 他の人から受け取ったファイルを置くだけで同じ物質をブラシで使える。
+
+use super::{to_dna, BaseMaterialParams, MaterialDNA, State};
+use crate::commands::{Command, CommandRegistry};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 保存先 (カレントディレクトリ)
+pub const LIBRARY_DIR: &str = "materials";
+const FORMAT_VERSION: u32 = 1;
+const TRASH_LIMIT: usize = 20; // 削除を取り消せる数
+const LUMINOUS_THRESHOLD: f32 = 0.8; // これより明るい物質は光って見える (dot.wgsl と同じ)
+
+/// Materials パネルとコマンドパレットからの操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum LibraryAction {
+    SaveBrush(String),    // ブラシの物質をこの名前で保存する (空なら物質の名前)
+    SaveSelected(String), // 選択中のドットの物質をこの名前で保存する
+    Use(usize),           // ブラシの物質にする
+    Duplicate(usize),
+    Rename(usize, String),
+    Delete(usize), // ブラシの物質になっているものは消せない
+    UndoDelete,
+    Reload,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialPreset {
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub created: u64, // 保存した時刻 (UNIX 秒。古いファイルでは 0)
+    pub params: BaseMaterialParams, // 温度などが DNA から作った値と違っていてもそのまま使う
+    pub dna: MaterialDNA,
+}
+
+impl MaterialPreset {
+    pub fn new(name: String, params: BaseMaterialParams, dna: MaterialDNA) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            name,
+            created: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            params,
+            dna,
+        }
+    }
+
+    /// ブラシの物質 (to_dna したもの) と同じ物質か
+    pub fn is_material(&self, brush: &MaterialDNA) -> bool {
+        self.dna.seed == brush.seed && to_dna(&self.params, brush.seed).genes == brush.genes
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let preset: MaterialPreset = serde_json::from_str(&json).map_err(std::io::Error::other)?;
+        if preset.version > FORMAT_VERSION {
+            return Err(std::io::Error::other(format!("unsupported material preset version {}", preset.version)));
+        }
+        Ok(preset)
+    }
+}
+
+/// Materials パネルの並べ方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Created, // 新しい順
+    Hue,
+    Density,     // 重い順
+    Temperature, // 温度の区分 (熱い順)
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 4] = [SortKey::Created, SortKey::Hue, SortKey::Density, SortKey::Temperature];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Created => "Newest",
+            SortKey::Hue => "Hue",
+            SortKey::Density => "Density",
+            SortKey::Temperature => "Temperature",
+        }
+    }
+}
+
+/// 相対温度の大まかな区分
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TemperatureClass {
+    Cold, // -1.0 ~ -0.5
+    Cool, // -0.5 ~ 0.0
+    Warm, // 0.0 ~ 0.5
+    Hot,  // 0.5 ~ 1.0
+}
+
+impl TemperatureClass {
+    pub fn of(temperature: f32) -> Self {
+        match temperature {
+            t if t < -0.5 => TemperatureClass::Cold,
+            t if t < 0.0 => TemperatureClass::Cool,
+            t if t < 0.5 => TemperatureClass::Warm,
+            _ => TemperatureClass::Hot,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TemperatureClass::Cold => "Cold",
+            TemperatureClass::Cool => "Cool",
+            TemperatureClass::Warm => "Warm",
+            TemperatureClass::Hot => "Hot",
+        }
+    }
+}
+
+/// Materials パネルの絞り込み条件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PresetFilter {
+    pub state: Option<State>,
+    pub luminous_only: bool,
+    pub search: String, // 名前に含まれる文字列 (大文字小文字は区別しない)
+}
+
+impl PresetFilter {
+    pub fn matches(&self, preset: &MaterialPreset) -> bool {
+        self.state.is_none_or(|state| preset.params.state == state)
+            && (!self.luminous_only || preset.params.luminescence > LUMINOUS_THRESHOLD)
+            && preset.name.to_lowercase().contains(&self.search.trim().to_lowercase())
+    }
+
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MaterialLibrary {
+    pub presets: Vec<MaterialPreset>, // 名前順
+    pub trash: Vec<MaterialPreset>,   // 削除したプリセット (新しいものが最後。取り消すと保存し直す)
+}
+
+impl MaterialLibrary {
+    /// dir の *.json をすべて読み込む (ディレクトリがなければ空。読めないファイルは警告して飛ばす)
+    pub fn load(dir: &Path) -> Self {
+        let mut presets = Vec::new();
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                if error.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!(target: crate::logging::APP, dir = %dir.display(), "material library load failed: {}", error);
+                }
+                return Self::default();
+            }
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            match MaterialPreset::load(&path) {
+                Ok(preset) => presets.push(preset),
+                Err(error) => {
+                    tracing::warn!(target: crate::logging::APP, path = %path.display(), "material preset load failed: {}", error);
+                }
+            }
+        }
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { presets, trash: Vec::new() }
+    }
+
+    /// dir に保存して一覧に加える (同じ名前のプリセットは置き換える)。保存したパスを返す
+    pub fn save(&mut self, dir: &Path, preset: MaterialPreset) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(file_name(&preset.name));
+        preset.save(&path)?;
+        self.presets.retain(|existing| existing.name != preset.name);
+        let index = self.presets.partition_point(|existing| existing.name < preset.name);
+        self.presets.insert(index, preset);
+        Ok(path)
+    }
+
+    /// 条件に合うプリセットの番号を sort の順に並べる (同じ順位なら名前順)
+    pub fn view(&self, sort: SortKey, filter: &PresetFilter) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.presets.len()).filter(|&i| filter.matches(&self.presets[i])).collect();
+        // presets は名前順なので、安定ソートで同じ順位のものは名前順のまま残る
+        indices.sort_by(|&a, &b| {
+            let (a, b) = (&self.presets[a], &self.presets[b]);
+            match sort {
+                SortKey::Created => b.created.cmp(&a.created),
+                SortKey::Hue => a.params.color_hue.total_cmp(&b.params.color_hue),
+                SortKey::Density => b.params.density.total_cmp(&a.params.density),
+                SortKey::Temperature => {
+                    TemperatureClass::of(b.params.temperature).cmp(&TemperatureClass::of(a.params.temperature))
+                }
+            }
+        });
+        indices
+    }
+
+    /// index のプリセットを同じ内容・別の名前で保存する
+    pub fn duplicate(&mut self, dir: &Path, index: usize) -> std::io::Result<PathBuf> {
+        let preset = self.get(index)?;
+        let name = self.unused_name(&format!("{} copy", preset.name));
+        let copy = MaterialPreset::new(name, preset.params.clone(), preset.dna.clone());
+        self.save(dir, copy)
+    }
+
+    /// index のプリセットの名前を変える (ファイル名も変わる)
+    pub fn rename(&mut self, dir: &Path, index: usize, name: &str) -> std::io::Result<PathBuf> {
+        let preset = self.get(index)?;
+        let name = name.trim();
+        if name == preset.name {
+            return Ok(dir.join(file_name(name)));
+        }
+        if name.is_empty() || self.presets.iter().any(|other| file_name(&other.name) == file_name(name)) {
+            return Err(std::io::Error::other(format!("name \"{}\" is empty or already used", name)));
+        }
+        let mut renamed = preset.clone();
+        renamed.name = name.to_string();
+        let old_path = dir.join(file_name(&preset.name));
+        let path = self.save(dir, renamed)?;
+        remove_file(&old_path)?;
+        self.presets.retain(|existing| existing.name != preset.name);
+        Ok(path)
+    }
+
+    /// index のプリセットのファイルを消してごみ箱に移す
+    pub fn remove(&mut self, dir: &Path, index: usize) -> std::io::Result<&MaterialPreset> {
+        let name = self.get(index)?.name.clone();
+        remove_file(&dir.join(file_name(&name)))?;
+        if self.trash.len() >= TRASH_LIMIT {
+            self.trash.remove(0);
+        }
+        self.trash.push(self.presets.remove(index));
+        Ok(&self.trash[self.trash.len() - 1])
+    }
+
+    /// 最後に削除したプリセットを保存し直す (ごみ箱が空なら None)
+    /// その間に同じ名前で保存されていれば、上書きせずに別の名前にする
+    pub fn restore(&mut self, dir: &Path) -> Option<std::io::Result<String>> {
+        let mut preset = self.trash.pop()?;
+        let name = self.unused_name(&preset.name);
+        let original = std::mem::replace(&mut preset.name, name.clone());
+        Some(match self.save(dir, preset.clone()) {
+            Ok(_) => Ok(name),
+            Err(error) => {
+                preset.name = original;
+                self.trash.push(preset);
+                Err(error)
+            }
+        })
+    }
+
+    fn get(&self, index: usize) -> std::io::Result<MaterialPreset> {
+        self.presets
+            .get(index)
+            .cloned()
+            .ok_or_else(|| std::io::Error::other(format!("no material preset at {}", index)))
+    }
+
+    /// base か、使われていなければ base 2, base 3, ...
+    fn unused_name(&self, base: &str) -> String {
+        let used = |name: &str| self.presets.iter().any(|preset| file_name(&preset.name) == file_name(name));
+        (1..)
+            .map(|n| if n == 1 { base.to_string() } else { format!("{} {}", base, n) })
+            .find(|name| !used(name))
+            .unwrap_or_default()
+    }
+}
+
+/// ファイルを消す (手で名前を変えたなどで見つからなければ何もしない)
+fn remove_file(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+/// プリセットの名前から付けるファイル名 (英数字と - _ 以外は _ にする)
+fn file_name(name: &str) -> String {
+    let stem: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}.json", if stem.is_empty() { "material" } else { &stem })
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Materials", "Save brush material", Command::MaterialLibrary(LibraryAction::SaveBrush(String::new())));
+    registry.register("Materials", "Undo material delete", Command::MaterialLibrary(LibraryAction::UndoDelete));
+    registry.register("Materials", format!("Reload {}/", LIBRARY_DIR), Command::MaterialLibrary(LibraryAction::Reload));
+}
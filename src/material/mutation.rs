@@ -0,0 +1,78 @@
+//! ブレンドで生まれた物質の突然変異
+//!
+//! ブレンドは親の遺伝子の補間なので、長く動かしていると物質はだんだん似たものに収束する。
+//! 突然変異を有効にすると、ブレンドの結果の遺伝子をエントロピーバイアスが高いほど高い確率で
+//! ずらし、親のどちらにもなかった物質が現れるようにする。
+//! ずらし方はブレンド結果の seed から決めるので、同じ親からは同じ物質ができる。
+
+use super::{MaterialDNA, DNA_VERSION};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+pub const MAX_RATE: f32 = 1.0;
+pub const MAX_MAGNITUDE: f32 = 0.5;
+const ENTROPY_GENE: usize = 13;
+const HUE_GENE: usize = 9; // 色相は端でつながっているので、ずらしたら 0 ~ 1 に巻き戻す
+
+/// 突然変異の設定 (ワーカースレッドからも参照するのでグローバルに持つ)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Mutation {
+    pub rate: f32,      // エントロピーバイアスが 1.0 の物質で、遺伝子1つが変異する確率 (0 なら無効)
+    pub magnitude: f32, // 1回の変異で遺伝子がずれる最大量
+}
+
+static RATE: AtomicU32 = AtomicU32::new(0x3DCC_CCCD); // 0.1
+static MAGNITUDE: AtomicU32 = AtomicU32::new(0x3E19_999A); // 0.15
+
+pub fn settings() -> Mutation {
+    Mutation {
+        rate: f32::from_bits(RATE.load(Ordering::Relaxed)),
+        magnitude: f32::from_bits(MAGNITUDE.load(Ordering::Relaxed)),
+    }
+}
+
+pub fn set_settings(mutation: Mutation) {
+    RATE.store(mutation.rate.clamp(0.0, MAX_RATE).to_bits(), Ordering::Relaxed);
+    MAGNITUDE.store(mutation.magnitude.clamp(0.0, MAX_MAGNITUDE).to_bits(), Ordering::Relaxed);
+}
+
+impl MaterialDNA {
+    /// 突然変異させた DNA (変異した遺伝子があれば seed も付け直す)
+    pub fn mutated(&self, mutation: Mutation) -> Self {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let chance = mutation.rate * self.genes[ENTROPY_GENE];
+        if chance <= 0.0 || mutation.magnitude <= 0.0 {
+            return self.clone();
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed ^ 0xA24B_AED4_963E_E407);
+        let mut genes = self.genes;
+        let mut changed = false;
+        for (index, gene) in genes.iter_mut().enumerate() {
+            if rng.gen::<f32>() >= chance {
+                continue;
+            }
+            let shifted = *gene + rng.gen_range(-mutation.magnitude..=mutation.magnitude);
+            *gene = if index == HUE_GENE { shifted.rem_euclid(1.0) } else { shifted.clamp(0.0, 1.0) };
+            changed = true;
+        }
+        if !changed {
+            return self.clone();
+        }
+
+        let mut hasher = DefaultHasher::new();
+        for &gene in &genes {
+            gene.to_bits().hash(&mut hasher);
+        }
+        Self {
+            seed: hasher.finish().max(1),
+            dna_version: DNA_VERSION,
+            genes,
+            unstable: self.unstable,
+        }
+    }
+}
@@ -1,9 +1,46 @@
-use crate::material::{MaterialDNA, State};
+use crate::commands::{Command, CommandRegistry};
+use crate::material::{BaseMaterialParams, MaterialDNA, State};
 use rand::distributions::{Distribution, WeightedIndex};
 use rand::seq::SliceRandom;
 use rand::Rng;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// --- Naming Styles ---
+
+/// Phonology used for generated names. The same seed always gets the same name within a style.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NamingStyle {
+    #[default]
+    Fantasy,
+    Japanese,
+    Latin, // alchemical Latin
+}
+
+impl NamingStyle {
+    pub const ALL: [NamingStyle; 3] = [NamingStyle::Fantasy, NamingStyle::Japanese, NamingStyle::Latin];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NamingStyle::Fantasy => "Fantasy",
+            NamingStyle::Japanese => "Japanese",
+            NamingStyle::Latin => "Latin (alchemical)",
+        }
+    }
+}
+
+// Names are generated from many places (new dots, decay, the reaction graph), so the selected style is global
+static STYLE: AtomicU8 = AtomicU8::new(0);
+
+pub fn style() -> NamingStyle {
+    NamingStyle::ALL[STYLE.load(Ordering::Relaxed) as usize]
+}
+
+pub fn set_style(style: NamingStyle) {
+    let index = NamingStyle::ALL.iter().position(|&s| s == style).unwrap_or(0);
+    STYLE.store(index as u8, Ordering::Relaxed);
+}
 
 // --- Markov Chain Model ---
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -26,12 +63,21 @@ const GAS_PREFIX: &[&str] = &["ae", "pha", "is", "sy", "lu"];
 const GAS_ROOT: &[&str] = &["el", "ar", "ia", "es", "the", "ion"];
 const GAS_SUFFIX: &[&str] = &["-is", "-os", "-ion", "-eth"];
 
-fn build_markov_transitions(state: State, _rng: &mut StdRng) -> Transitions {
+// Gene-based phonemes (rare unless the gene is high)
+const HARSH_CLUSTERS: &[&str] = &["skr", "thr", "zk", "grax", "kth"]; // hardness
+const BRIGHT_VOWELS: &[&str] = &["ei", "ia", "yu", "lum", "ee"]; // luminescence
+const GENE_PHONEME_BASE_WEIGHT: f32 = 0.2; // gene 0.0 still allows them occasionally
+const GENE_PHONEME_WEIGHT: f32 = 4.0; // gene 1.0 makes them 4.2x as likely as a neutral phoneme
+
+fn build_markov_transitions(material: &BaseMaterialParams, _rng: &mut StdRng) -> Transitions {
     let mut transitions = Transitions::new();
     let all_phonemes: Vec<Phoneme> = SOLID_PREFIX.iter().chain(SOLID_ROOT.iter()).chain(SOLID_SUFFIX.iter())
         .chain(LIQUID_PREFIX.iter()).chain(LIQUID_ROOT.iter()).chain(LIQUID_SUFFIX.iter())
         .chain(GAS_PREFIX.iter()).chain(GAS_ROOT.iter()).chain(GAS_SUFFIX.iter())
+        .chain(HARSH_CLUSTERS.iter()).chain(BRIGHT_VOWELS.iter())
         .map(|s| Phoneme(s.to_string().replace("-", ""))).collect();
+    let harsh_weight = GENE_PHONEME_BASE_WEIGHT + material.hardness * GENE_PHONEME_WEIGHT;
+    let bright_weight = GENE_PHONEME_BASE_WEIGHT + material.luminescence * GENE_PHONEME_WEIGHT;
 
     for p1 in &all_phonemes {
         let mut dests = Vec::new();
@@ -39,7 +85,7 @@ fn build_markov_transitions(state: State, _rng: &mut StdRng) -> Transitions {
             if p1 != p2 {
                 let mut weight = 1.0;
                 // State-specific transition biases
-                match state {
+                match material.state {
                     State::Solid => {
                         if (p1.0.contains('r') && p2.0.contains('d')) || (p1.0.contains('d') && p2.0.contains('r')) {
                             weight = 5.0; // Increase probability for r <-> d
@@ -56,6 +102,12 @@ fn build_markov_transitions(state: State, _rng: &mut StdRng) -> Transitions {
                         }
                     }
                 }
+                // Gene-specific biases: hard materials sound harsh, luminescent ones bright
+                if HARSH_CLUSTERS.contains(&p2.0.as_str()) {
+                    weight *= harsh_weight;
+                } else if BRIGHT_VOWELS.contains(&p2.0.as_str()) {
+                    weight *= bright_weight;
+                }
                 dests.push((p2.clone(), weight));
             }
         }
@@ -65,34 +117,51 @@ fn build_markov_transitions(state: State, _rng: &mut StdRng) -> Transitions {
 }
 
 pub fn generate_name(dna: &MaterialDNA) -> String {
+    generate_name_with_style(dna, style())
+}
+
+pub fn generate_name_with_style(dna: &MaterialDNA, style: NamingStyle) -> String {
     let mut rng = StdRng::seed_from_u64(dna.seed);
     let material = crate::material::from_dna(dna);
 
-    let transitions = build_markov_transitions(material.state, &mut rng);
-
-    // --- Choose Template ---
-    let template_len = match material.state {
-        State::Solid => rng.gen_range(2..=3),
-        State::Liquid => 3,
-        State::Gas => rng.gen_range(1..=2),
+    let name = match style {
+        NamingStyle::Fantasy => fantasy_name(&material, &mut rng),
+        NamingStyle::Japanese => japanese_name(&material, &mut rng),
+        NamingStyle::Latin => latin_name(&material, &mut rng),
     };
 
-    // --- Generate Name ---
-    let mut all_phonemes: Vec<Phoneme> = transitions.keys().cloned().collect();
-    // Sort the phonemes to ensure deterministic name generation
-    all_phonemes.sort_by(|a, b| a.0.cmp(&b.0));
+    // Capitalize the first letter
+    let mut chars = name.chars();
+    match chars.next() {
+        None => String::new(),
+        Some(f) => f.to_uppercase().collect::<String>() + chars.as_str(),
+    }
+}
+
+/// Walks the Markov chain for `len` phonemes.
+/// Starts from the `start` node (not part of the name) if given, otherwise from a uniformly random phoneme.
+fn walk(transitions: &Transitions, start: Option<&Phoneme>, len: usize, rng: &mut StdRng) -> Vec<Phoneme> {
     let mut name_phonemes = Vec::new();
-    let mut current_phoneme = all_phonemes.choose(&mut rng)
-        .expect("Failed to choose initial phoneme. 'all_phonemes' might be empty.")
-        .clone();
-    name_phonemes.push(current_phoneme.clone());
+    let mut current_phoneme = match start {
+        Some(start) => start.clone(),
+        None => {
+            let mut all_phonemes: Vec<Phoneme> = transitions.keys().cloned().collect();
+            // Sort the phonemes to ensure deterministic name generation
+            all_phonemes.sort_by(|a, b| a.0.cmp(&b.0));
+            let phoneme = all_phonemes.choose(rng)
+                .expect("Failed to choose initial phoneme. 'all_phonemes' might be empty.")
+                .clone();
+            name_phonemes.push(phoneme.clone());
+            phoneme
+        }
+    };
 
-    for _ in 1..template_len {
+    while name_phonemes.len() < len {
         if let Some(dests) = transitions.get(&current_phoneme) {
             if !dests.is_empty() {
                 let dist = WeightedIndex::new(dests.iter().map(|(_, w)| *w).collect::<Vec<_>>())
                     .expect("Failed to create WeightedIndex. 'dests' might be empty or weights sum to zero.");
-                current_phoneme = dests[dist.sample(&mut rng)].0.clone();
+                current_phoneme = dests[dist.sample(rng)].0.clone();
                 name_phonemes.push(current_phoneme.clone());
             } else {
                 break;
@@ -102,12 +171,151 @@ pub fn generate_name(dna: &MaterialDNA) -> String {
         }
     }
 
-    let name = name_phonemes.iter().map(|p| p.0.as_str()).collect::<String>();
+    name_phonemes
+}
+
+fn join(phonemes: &[Phoneme], separator: &str) -> String {
+    phonemes.iter().map(|p| p.0.as_str()).collect::<Vec<_>>().join(separator)
+}
 
-    // Capitalize the first letter
-    let mut chars = name.chars();
-    match chars.next() {
-        None => String::new(),
-        Some(f) => f.to_uppercase().collect::<String>() + chars.as_str(),
+fn fantasy_name(material: &BaseMaterialParams, rng: &mut StdRng) -> String {
+    let transitions = build_markov_transitions(material, rng);
+
+    // --- Choose Template ---
+    let template_len: usize = match material.state {
+        State::Solid => rng.gen_range(2..=3),
+        State::Liquid => 3,
+        State::Gas => rng.gen_range(1..=2),
+    };
+    // Dense materials get longer names, light ones shorter
+    let template_len = if material.density > 0.66 {
+        template_len + 1
+    } else if material.density < 0.33 {
+        template_len.saturating_sub(1).max(1)
+    } else {
+        template_len
+    };
+
+    join(&walk(&transitions, None, template_len, rng), "")
+}
+
+// --- Japanese Phonology ---
+
+// CV syllables grouped by the impression they give
+const JP_SOLID_SYLLABLES: &[&str] = &["ka", "ko", "ga", "go", "to", "do", "te", "ku", "gu", "ta"];
+const JP_LIQUID_SYLLABLES: &[&str] = &["mi", "na", "ru", "yu", "se", "ne", "wa", "ri", "mo", "no"];
+const JP_GAS_SYLLABLES: &[&str] = &["fu", "su", "shi", "ho", "ki", "sa", "hi", "so", "yo", "tsu"];
+const JP_SUFFIX_SOLID: &[&str] = &["seki", "gane", "ishi"];
+const JP_SUFFIX_LIQUID: &[&str] = &["sui", "mizu", "eki"];
+const JP_SUFFIX_GAS: &[&str] = &["ki", "kaze", "kemuri"];
+
+// Start node of the Japanese and Latin chains (never appears in a name)
+const START: &str = "";
+
+fn build_japanese_transitions(material: &BaseMaterialParams) -> Transitions {
+    let mut transitions = Transitions::new();
+    let all_syllables: Vec<Phoneme> = JP_SOLID_SYLLABLES.iter().chain(JP_LIQUID_SYLLABLES.iter()).chain(JP_GAS_SYLLABLES.iter())
+        .chain(std::iter::once(&"n"))
+        .map(|s| Phoneme(s.to_string())).collect();
+    let favored: &[&str] = match material.state {
+        State::Solid => JP_SOLID_SYLLABLES,
+        State::Liquid => JP_LIQUID_SYLLABLES,
+        State::Gas => JP_GAS_SYLLABLES,
+    };
+
+    let start = Phoneme(START.to_string());
+    for p1 in all_syllables.iter().chain(std::iter::once(&start)) {
+        let mut dests = Vec::new();
+        for p2 in &all_syllables {
+            // The moraic "n" never starts a name and never doubles
+            if p2.0 == "n" && (p1.0 == "n" || *p1 == start) {
+                continue;
+            }
+            let mut weight = if favored.contains(&p2.0.as_str()) { 5.0 } else { 1.0 };
+            if p2.0 == "n" {
+                weight = 1.0 + material.hardness * 2.0; // hard materials end syllables heavily
+            }
+            // Repeated syllables (e.g. "koko") are common in Japanese
+            if p1 == p2 {
+                weight *= 1.5;
+            }
+            dests.push((p2.clone(), weight));
+        }
+        transitions.insert(p1.clone(), dests);
     }
-}
\ No newline at end of file
+    transitions
+}
+
+fn japanese_name(material: &BaseMaterialParams, rng: &mut StdRng) -> String {
+    let transitions = build_japanese_transitions(material);
+    let len = if material.density > 0.66 { 3 } else { 2 };
+    let name = join(&walk(&transitions, Some(&Phoneme(START.to_string())), len, rng), "");
+    let suffix = match material.state {
+        State::Solid => JP_SUFFIX_SOLID,
+        State::Liquid => JP_SUFFIX_LIQUID,
+        State::Gas => JP_SUFFIX_GAS,
+    };
+    name + "-" + suffix.choose(rng).expect("suffix set must not be empty")
+}
+
+// --- Latin Alchemical Phonology ---
+
+const LATIN_ROOTS_SOLID: &[&str] = &["ferr", "plumb", "petr", "lapid", "cupr", "stann"];
+const LATIN_ROOTS_LIQUID: &[&str] = &["aqu", "merc", "oleum", "sal", "vitr", "lact"];
+const LATIN_ROOTS_GAS: &[&str] = &["spirit", "vapor", "aer", "fum", "sulph", "nitr"];
+const LATIN_ROOTS_BRIGHT: &[&str] = &["aur", "argent", "lum", "ign"]; // luminescence
+const LATIN_SUFFIX_SOLID: &[&str] = &["um", "ium", "ites"];
+const LATIN_SUFFIX_LIQUID: &[&str] = &["a", "is", "ina"];
+const LATIN_SUFFIX_GAS: &[&str] = &["us", "or", "alis"];
+
+fn build_latin_transitions(material: &BaseMaterialParams) -> Transitions {
+    let mut transitions = Transitions::new();
+    let all_roots: Vec<Phoneme> = LATIN_ROOTS_SOLID.iter().chain(LATIN_ROOTS_LIQUID.iter()).chain(LATIN_ROOTS_GAS.iter())
+        .chain(LATIN_ROOTS_BRIGHT.iter())
+        .map(|s| Phoneme(s.to_string())).collect();
+    let favored: &[&str] = match material.state {
+        State::Solid => LATIN_ROOTS_SOLID,
+        State::Liquid => LATIN_ROOTS_LIQUID,
+        State::Gas => LATIN_ROOTS_GAS,
+    };
+    let bright_weight = GENE_PHONEME_BASE_WEIGHT + material.luminescence * GENE_PHONEME_WEIGHT;
+
+    let start = Phoneme(START.to_string());
+    for p1 in all_roots.iter().chain(std::iter::once(&start)) {
+        let mut dests = Vec::new();
+        for p2 in &all_roots {
+            if p1 == p2 {
+                continue;
+            }
+            let weight = if favored.contains(&p2.0.as_str()) {
+                5.0
+            } else if LATIN_ROOTS_BRIGHT.contains(&p2.0.as_str()) {
+                bright_weight
+            } else {
+                1.0
+            };
+            dests.push((p2.clone(), weight));
+        }
+        transitions.insert(p1.clone(), dests);
+    }
+    transitions
+}
+
+fn latin_name(material: &BaseMaterialParams, rng: &mut StdRng) -> String {
+    let transitions = build_latin_transitions(material);
+    let len = if material.density > 0.66 { 2 } else { 1 };
+    // Compound roots are joined with the Latin linking vowel
+    let name = join(&walk(&transitions, Some(&Phoneme(START.to_string())), len, rng), "o");
+    let suffix = match material.state {
+        State::Solid => LATIN_SUFFIX_SOLID,
+        State::Liquid => LATIN_SUFFIX_LIQUID,
+        State::Gas => LATIN_SUFFIX_GAS,
+    };
+    name + suffix.choose(rng).expect("suffix set must not be empty")
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    for style in NamingStyle::ALL {
+        registry.register("Naming", format!("Style: {}", style.label()), Command::SetNamingStyle(style));
+    }
+}
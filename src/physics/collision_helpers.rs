@@ -45,62 +45,6 @@ pub fn handle_detailed_collision(dot1: &mut Dot, dot2: &mut Dot, nx: f64, ny: f6
 
     let avg_viscosity = (dot1.material.viscosity + dot2.material.viscosity) / 2.0;
 
-    // --- 熱交換 ---
-    // 熱交換の頻度を制限
-    let now = std::time::Instant::now();
-    let elapsed1 = now.duration_since(dot1.last_heat_exchange_time).as_secs_f64();
-    let elapsed2 = now.duration_since(dot2.last_heat_exchange_time).as_secs_f64();
-    
-    if elapsed1 >= HEAT_EXCHANGE_INTERVAL && elapsed2 >= HEAT_EXCHANGE_INTERVAL {
-        let temp_diff = dot1.material.temperature - dot2.material.temperature;
-        let avg_heat_conductivity =
-            (dot1.material.heat_conductivity + dot2.material.heat_conductivity) / 2.0;
-        let heat_transfer = (temp_diff * avg_heat_conductivity * HEAT_TRANSFER_COEFFICIENT).clamp(-1.0, 1.0); // NaN ガード
-
-        // エネルギー保存：dot1 が失う熱 = dot2 が得る熱
-        dot1.material.temperature = (dot1.material.temperature - heat_transfer).clamp(-1.0, 1.0);
-        dot2.material.temperature = (dot2.material.temperature + heat_transfer).clamp(-1.0, 1.0);
-
-        // 熱交換時刻を更新
-        dot1.last_heat_exchange_time = now;
-        dot2.last_heat_exchange_time = now;
-    }
-
-    // --- 凝集力 ---
-    if dot1.material.state == dot2.material.state {
-        let avg_cohesion = (dot1.material.cohesion + dot2.material.cohesion) / 2.0;
-        if avg_cohesion > 0.01 { // 計算負荷を減らすために閾値を設ける
-            let ideal_dist = DOT_RADIUS * 1.5; // この距離に近づけようとする
-            let dist_sq = (dot1.x - dot2.x).powi(2) + (dot1.y - dot2.y).powi(2);
-            let dist = dist_sq.sqrt();
-
-            // 凝集力が働く範囲 (e.g., DOT_RADIUS * 4)
-            let effective_range = DOT_RADIUS * 4.0;
-
-            if dist < effective_range && dist > 1e-6 {
-                // 理想的な距離との差に基づいて力を計算
-                let force_magnitude = (ideal_dist - dist) * (avg_cohesion as f64) * 0.01; // 係数は要調整
-
-                let nx = (dot2.x - dot1.x) / dist;
-                let ny = (dot2.y - dot1.y) / dist;
-
-                let force_x = nx * force_magnitude;
-                let force_y = ny * force_magnitude;
-
-                // 質量に応じて力を適用
-                let m1 = dot1.material.density as f64;
-                let m2 = dot2.material.density as f64;
-                let total_mass = m1 + m2;
-                if total_mass > 1e-6 {
-                    dot1.vx += force_x * (m2 / total_mass);
-                    dot1.vy += force_y * (m2 / total_mass);
-                    dot2.vx -= force_x * (m1 / total_mass);
-                    dot2.vy -= force_y * (m1 / total_mass);
-                }
-            }
-        }
-    }
-
     // Liquids spread based on viscosity
     if dot1.material.state == State::Liquid && dot2.material.state == State::Liquid {
         let viscosity_threshold = 0.5; // Liquids require lower viscosity to spread
@@ -120,6 +64,60 @@ pub fn handle_detailed_collision(dot1: &mut Dot, dot2: &mut Dot, nx: f64, ny: f6
     }
 }
 
+// 2つのドットの間の熱交換 (頻度を制限する)
+pub fn exchange_heat(dot1: &mut Dot, dot2: &mut Dot) {
+    // 熱交換の頻度を制限
+    if dot1.since_heat_exchange >= HEAT_EXCHANGE_INTERVAL && dot2.since_heat_exchange >= HEAT_EXCHANGE_INTERVAL {
+        let temp_diff = dot1.material.temperature - dot2.material.temperature;
+        let avg_heat_conductivity =
+            (dot1.material.heat_conductivity + dot2.material.heat_conductivity) / 2.0;
+        let heat_transfer = (temp_diff * avg_heat_conductivity * HEAT_TRANSFER_COEFFICIENT).clamp(-1.0, 1.0); // NaN ガード
+
+        // エネルギー保存：dot1 が失う熱 = dot2 が得る熱
+        dot1.material.temperature = (dot1.material.temperature - heat_transfer).clamp(-1.0, 1.0);
+        dot2.material.temperature = (dot2.material.temperature + heat_transfer).clamp(-1.0, 1.0);
+
+        // 熱交換からの経過時間をリセット
+        dot1.since_heat_exchange = 0.0;
+        dot2.since_heat_exchange = 0.0;
+    }
+}
+
+// 2つのドットを一定の距離に保とうとする凝集力
+pub fn apply_cohesion(dot1: &mut Dot, dot2: &mut Dot) {
+    let avg_cohesion = (dot1.material.cohesion + dot2.material.cohesion) / 2.0;
+    if avg_cohesion > 0.01 { // 計算負荷を減らすために閾値を設ける
+        let ideal_dist = DOT_RADIUS * 1.5; // この距離に近づけようとする
+        let dist_sq = (dot1.x - dot2.x).powi(2) + (dot1.y - dot2.y).powi(2);
+        let dist = dist_sq.sqrt();
+
+        // 凝集力が働く範囲 (e.g., DOT_RADIUS * 4)
+        let effective_range = DOT_RADIUS * 4.0;
+
+        if dist < effective_range && dist > 1e-6 {
+            // 理想的な距離との差に基づいて力を計算
+            let force_magnitude = (ideal_dist - dist) * (avg_cohesion as f64) * 0.01; // 係数は要調整
+
+            let nx = (dot2.x - dot1.x) / dist;
+            let ny = (dot2.y - dot1.y) / dist;
+
+            let force_x = nx * force_magnitude;
+            let force_y = ny * force_magnitude;
+
+            // 質量に応じて力を適用
+            let m1 = dot1.material.density as f64;
+            let m2 = dot2.material.density as f64;
+            let total_mass = m1 + m2;
+            if total_mass > 1e-6 {
+                dot1.vx += force_x * (m2 / total_mass);
+                dot1.vy += force_y * (m2 / total_mass);
+                dot2.vx -= force_x * (m1 / total_mass);
+                dot2.vy -= force_y * (m1 / total_mass);
+            }
+        }
+    }
+}
+
 // Gas 間の衝突処理
 pub fn handle_gas_collision(dot1: &mut Dot, dot2: &mut Dot, nx: f64, ny: f64) {
     let e = (dot1.material.elasticity + dot2.material.elasticity) as f64 / 2.0;
@@ -143,27 +141,6 @@ pub fn handle_gas_collision(dot1: &mut Dot, dot2: &mut Dot, nx: f64, ny: f64) {
     dot2.vx += (v2n_new - v2n) * nx;
 
     dot2.vy += (v2n_new - v2n) * ny;
-
-    // --- 熱交換 ---
-    // 熱交換の頻度を制限
-    let now = std::time::Instant::now();
-    let elapsed1 = now.duration_since(dot1.last_heat_exchange_time).as_secs_f64();
-    let elapsed2 = now.duration_since(dot2.last_heat_exchange_time).as_secs_f64();
-    
-    if elapsed1 >= HEAT_EXCHANGE_INTERVAL && elapsed2 >= HEAT_EXCHANGE_INTERVAL {
-        let temp_diff = dot1.material.temperature - dot2.material.temperature;
-        let avg_heat_conductivity =
-            (dot1.material.heat_conductivity + dot2.material.heat_conductivity) / 2.0;
-        let heat_transfer = (temp_diff * avg_heat_conductivity * HEAT_TRANSFER_COEFFICIENT).clamp(-1.0, 1.0); // NaN ガード
-
-        // エネルギー保存：dot1 が失う熱 = dot2 が得る熱
-        dot1.material.temperature = (dot1.material.temperature - heat_transfer).clamp(-1.0, 1.0);
-        dot2.material.temperature = (dot2.material.temperature + heat_transfer).clamp(-1.0, 1.0);
-
-        // 熱交換時刻を更新
-        dot1.last_heat_exchange_time = now;
-        dot2.last_heat_exchange_time = now;
-    }
 }
 
 // Gas が他の物体に押される処理
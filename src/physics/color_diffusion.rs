@@ -0,0 +1,42 @@
+//! 液体の色の拡散 (見た目だけ)
+//!
+//! 隣り合う同系統の液体ドットの色相・彩度を少しずつ平均し、かき混ぜた液体が
+//! 均一な色に馴染んでいくようにする。DNA は変えないので、反応や名前には影響しない。
+
+use crate::app::Dot;
+use crate::material::State;
+
+const DIFFUSION_RATE: f64 = 0.5; // 1秒あたりに差を縮める割合
+const NEIGHBOR_DISTANCE: f64 = 1.5; // 半径の和の何倍以内を隣とみなすか
+
+/// update_collision で集めた衝突候補ペアのうち、隣り合う同系統の液体の色を近づける
+pub fn diffuse_liquid_colors(dots: &mut [Dot], pairs: &[(usize, usize)], dt: f64) {
+    let amount = ((DIFFUSION_RATE * dt).min(1.0) * 0.5) as f32; // 両側から近づけるので半分ずつ
+
+    for &(i, j) in pairs {
+        let (Some(a), Some(b)) = (dots.get(i), dots.get(j)) else {
+            continue;
+        };
+        if a.material.state != State::Liquid || b.material.state != State::Liquid {
+            continue;
+        }
+        let reach = (a.radius() + b.radius()) * NEIGHBOR_DISTANCE;
+        if (b.x - a.x).powi(2) + (b.y - a.y).powi(2) > reach * reach {
+            continue;
+        }
+        if !a.material_dna.same_family(&b.material_dna) {
+            continue;
+        }
+
+        // 色相は環状なので近い向きに回す
+        let hue_diff = (b.material.color_hue - a.material.color_hue + 0.5).rem_euclid(1.0) - 0.5;
+        let saturation_diff = b.material.color_saturation - a.material.color_saturation;
+
+        let a = &mut dots[i].material;
+        a.color_hue = (a.color_hue + hue_diff * amount).rem_euclid(1.0);
+        a.color_saturation += saturation_diff * amount;
+        let b = &mut dots[j].material;
+        b.color_hue = (b.color_hue - hue_diff * amount).rem_euclid(1.0);
+        b.color_saturation -= saturation_diff * amount;
+    }
+}
@@ -0,0 +1,133 @@
+//! 可燃性の物質の燃焼
+//!
+//! 可燃性 (flammability) が高いほど低い温度で発火する。燃えているドットは光りながら熱くなり、
+//! 接しているドットを温めるので、そこから燃え移る。ときどき煙 (気体のドット) を出し、
+//! 燃え尽きると可燃性のない灰になる。
+
+use super::GameRng;
+use crate::app::Dot;
+use crate::material::{from_dna, MaterialDNA, DNA_VERSION, GENE_COUNT};
+use rand::Rng;
+
+const MIN_FLAMMABILITY: f32 = 0.3; // これより燃えにくい物質は燃えない
+const FIRE_HEAT_RATE: f32 = 0.8; // 燃えているドットが自分と接しているドットを温める速さ (1秒あたり)
+const SMOKE_RATE: f64 = 0.5; // 燃えているドット1つが1秒あたりに出す煙の数
+const SMOKE_SPEED: f64 = 30.0;
+const SMOKE_SEED: u64 = 0x5_3A0C; // 煙はすべて同じ物質 (同じ seed 同士は反応しない)
+const FLAMMABILITY_GENE: usize = 19;
+
+/// この物質が燃え始める温度 (燃えやすいほど低い。燃えない物質は None)
+fn ignition_point(flammability: f32) -> Option<f32> {
+    (flammability >= MIN_FLAMMABILITY).then_some(1.0 - flammability * 0.5)
+}
+
+/// 燃え尽きるまでの時間 (燃えやすいほど早く燃え尽きる)
+fn burn_duration(flammability: f32) -> f64 {
+    1.0 + 4.0 * (1.0 - flammability as f64)
+}
+
+/// 燃焼を dt 秒進める
+/// 接しているかは update_collision で集めた衝突候補ペアで判定する。煙の id は next_id から順に割り当てる
+pub fn burn(dots: &mut Vec<Dot>, pairs: &[(usize, usize)], dt: f64, next_id: &mut u64, rng: &mut GameRng) {
+    // 1. 発火点を超えたドットに火をつける
+    for dot in dots.iter_mut().filter(|dot| dot.burn_time.is_none()) {
+        if ignition_point(dot.material.flammability).is_some_and(|point| dot.material.temperature >= point) {
+            dot.burn_time = Some(0.0);
+        }
+    }
+
+    // 2. 燃えているドットが接しているドットを温める (そこから燃え移る)
+    let heat = FIRE_HEAT_RATE * dt as f32;
+    for &(i, j) in pairs {
+        let (Some(a), Some(b)) = (dots.get(i), dots.get(j)) else {
+            continue;
+        };
+        let reach = a.radius() + b.radius();
+        if (b.x - a.x).powi(2) + (b.y - a.y).powi(2) > reach * reach {
+            continue;
+        }
+        match (a.burn_time.is_some(), b.burn_time.is_some()) {
+            (true, false) => dots[j].material.temperature = (dots[j].material.temperature + heat).min(1.0),
+            (false, true) => dots[i].material.temperature = (dots[i].material.temperature + heat).min(1.0),
+            _ => {}
+        }
+    }
+
+    // 3. 燃えているドットを熱くして光らせ、煙を出す。燃え尽きたら灰にする
+    let mut smoke = Vec::new();
+    let mut burnt_out = 0;
+    for dot in dots.iter_mut() {
+        let Some(burn_time) = dot.burn_time else {
+            continue;
+        };
+        if burn_time >= burn_duration(dot.material.flammability) {
+            let ash = ash(&dot.material_dna);
+            dot.material = from_dna(&ash);
+            dot.material.temperature = 1.0; // 燃えたての灰は熱い
+            dot.name = crate::naming::generate_name(&ash);
+            dot.material_dna = ash;
+            dot.burn_time = None;
+            dot.wake();
+            burnt_out += 1;
+            continue;
+        }
+
+        dot.material.temperature = (dot.material.temperature + heat).min(1.0);
+        dot.material.luminescence = 1.0;
+        if rng.gen::<f64>() < SMOKE_RATE * dt {
+            let dna = smoke_dna();
+            let mut puff = Dot::new(*next_id, dot.x, dot.y - dot.radius() * 2.0, from_dna(&dna), dna);
+            *next_id += 1;
+            puff.vx = rng.gen_range(-10.0..10.0);
+            puff.vy = -SMOKE_SPEED;
+            puff.group = dot.group;
+            puff.hidden = dot.hidden;
+            smoke.push(puff);
+        }
+    }
+
+    if burnt_out > 0 {
+        tracing::debug!(target: crate::logging::PHYSICS, count = burnt_out, "dots burnt out");
+    }
+    dots.extend(smoke);
+}
+
+/// 燃え尽きたあとの灰 (元の物質より軽く、もろく、色がくすむ)
+fn ash(dna: &MaterialDNA) -> MaterialDNA {
+    let mut genes = dna.genes;
+    genes[0] = 0.165; // 固体
+    genes[1] *= 0.5; // 密度
+    genes[3] *= 0.3; // 硬さ
+    genes[10] *= 0.2; // 彩度
+    genes[11] = 0.2 + genes[11] * 0.2; // 明度
+    genes[12] = 0.0; // 自発光
+    genes[FLAMMABILITY_GENE] = 0.0;
+    MaterialDNA {
+        seed: (dna.seed.rotate_left(17) ^ 0xA5A5_0A5A).max(1),
+        dna_version: DNA_VERSION,
+        genes,
+        unstable: false,
+    }
+}
+
+/// 煙 (軽くて熱い灰色の気体)
+fn smoke_dna() -> MaterialDNA {
+    let mut genes = [0.0; GENE_COUNT];
+    genes[0] = 0.825; // 気体
+    genes[1] = 0.1; // 密度
+    genes[5] = 0.85; // 温度
+    genes[6] = 0.3; // 熱伝導率
+    genes[7] = 1.0; // 熱容量 (高) - 発光しない
+    genes[8] = 0.5; // 熱容量 (低)
+    genes[11] = 0.35; // 明度 (彩度 0 の灰色)
+    genes[14] = 0.2; // 揮発性
+    genes[17] = 0.5; // 融点
+    genes[18] = 0.6; // 沸点
+    genes[22] = 0.5; // 電荷 (中性)
+    MaterialDNA {
+        seed: SMOKE_SEED,
+        dna_version: DNA_VERSION,
+        genes,
+        unstable: false,
+    }
+}
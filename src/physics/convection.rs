@@ -0,0 +1,49 @@
+use crate::app::Dot;
+
+/// 流れ場の可視化に使うセルの大きさ (ワールド座標)
+pub const FLOW_CELL_SIZE: f64 = 16.0;
+
+/// 流れ場の1セル (セル内のドットの平均)
+#[derive(Debug, Clone, Copy)]
+pub struct FlowCell {
+    pub x: f64, // セル中心 (ワールド座標)
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+    pub temperature: f32,
+}
+
+/// ドットの速度と温度を粗いグリッドで平均し、ドットを含むセルだけを返す
+pub fn flow_field(dots: &[Dot], cell_size: f64) -> Vec<FlowCell> {
+    use std::collections::HashMap;
+
+    // セルごとの合計と個数 (最後に個数で割って平均にする)
+    let mut cells: HashMap<(i32, i32), (FlowCell, usize)> = HashMap::new();
+    for dot in dots {
+        let key = ((dot.x / cell_size).floor() as i32, (dot.y / cell_size).floor() as i32);
+        let (cell, count) = cells.entry(key).or_insert_with(|| {
+            let cell = FlowCell {
+                x: (key.0 as f64 + 0.5) * cell_size,
+                y: (key.1 as f64 + 0.5) * cell_size,
+                vx: 0.0,
+                vy: 0.0,
+                temperature: 0.0,
+            };
+            (cell, 0)
+        });
+        cell.vx += dot.vx;
+        cell.vy += dot.vy;
+        cell.temperature += dot.material.temperature;
+        *count += 1;
+    }
+
+    cells
+        .into_values()
+        .map(|(cell, count)| FlowCell {
+            vx: cell.vx / count as f64,
+            vy: cell.vy / count as f64,
+            temperature: cell.temperature / count as f32,
+            ..cell
+        })
+        .collect()
+}
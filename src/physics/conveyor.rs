@@ -0,0 +1,77 @@
+//! コンベア (中のドットを一定の向きに運ぶ矩形の領域)
+//!
+//! 矩形の長い方の辺に沿って、中にある固体と液体をベルトの速さまで一定の加速度で加速する。
+//! 横長なら左右 (正で右)、縦長なら上下 (正で下) に運ぶ。気体は設定したコンベアだけが運ぶ。
+
+use crate::app::Dot;
+use crate::material::State;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_SPEED: f64 = 200.0;
+const ACCELERATION: f64 = 600.0; // ベルトの速さに近づける加速度
+const MIN_SIZE: f64 = 4.0; // これより小さくドラッグしたコンベアは置かない
+
+/// 次に置くコンベアの設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConveyorSettings {
+    pub speed: f64, // ベルトの速さ (負なら逆向き)
+    pub carries_gas: bool,
+}
+
+impl Default for ConveyorSettings {
+    fn default() -> Self {
+        Self { speed: 60.0, carries_gas: false }
+    }
+}
+
+/// ワールド座標の矩形のコンベア
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Conveyor {
+    pub min: (f64, f64),
+    pub max: (f64, f64),
+    pub settings: ConveyorSettings,
+}
+
+impl Conveyor {
+    /// ドラッグの始点と終点から作る (小さすぎれば None)
+    pub fn from_corners(a: (f64, f64), b: (f64, f64), settings: ConveyorSettings) -> Option<Self> {
+        let min = (a.0.min(b.0), a.1.min(b.1));
+        let max = (a.0.max(b.0), a.1.max(b.1));
+        (max.0 - min.0 >= MIN_SIZE && max.1 - min.1 >= MIN_SIZE).then_some(Self { min, max, settings })
+    }
+
+    /// ベルトの向き (単位ベクトル、速さが正のとき)
+    pub fn direction(&self) -> (f64, f64) {
+        if self.max.0 - self.min.0 >= self.max.1 - self.min.1 {
+            (1.0, 0.0)
+        } else {
+            (0.0, 1.0)
+        }
+    }
+
+    fn carries(&self, dot: &Dot) -> bool {
+        (self.min.0..=self.max.0).contains(&dot.x)
+            && (self.min.1..=self.max.1).contains(&dot.y)
+            && (self.settings.carries_gas || dot.material.state != State::Gas)
+    }
+}
+
+/// コンベアの中のドットをベルトの向きに dt 秒分だけ加速する (ベルトより速くはしない)
+pub fn apply(conveyors: &[Conveyor], dots: &mut [Dot], dt: f64) {
+    if conveyors.is_empty() {
+        return;
+    }
+    for dot in dots.iter_mut().filter(|dot| !dot.hidden) {
+        let Some(conveyor) = conveyors.iter().find(|conveyor| conveyor.carries(dot)) else {
+            continue;
+        };
+        let (ux, uy) = conveyor.direction();
+        let along = dot.vx * ux + dot.vy * uy;
+        let target = conveyor.settings.speed;
+        let change = (target - along).clamp(-ACCELERATION * dt, ACCELERATION * dt);
+        if change != 0.0 {
+            dot.vx += ux * change;
+            dot.vy += uy * change;
+        }
+    }
+}
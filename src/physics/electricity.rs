@@ -0,0 +1,126 @@
+//! 電荷と電気伝導
+//!
+//! 電荷を持つドット同士はクーロン力を及ぼし合う (同符号は反発し、異符号は引き合う)。
+//! 接しているドットの間では、電気伝導率の低い方に応じた速さで電荷が均されるので、
+//! 導体をつなげると電荷が端まで流れる。接しているドットの電荷の差が絶縁破壊の閾値を超えると
+//! 放電し、電荷を一気に均して両方が一瞬だけ強く光る (電池や雷のような仕掛けが作れる)。
+//! 磁性の強いドット同士は、極性に関係なく引き合う (磁石にくっつく鉄のように固まる)。
+
+use super::Physics;
+use crate::app::Dot;
+
+const MIN_CHARGE: f32 = 0.05; // これより電荷の小さいドットは力を及ぼさない
+const MIN_MAGNETISM: f32 = 0.8; // これより磁性の弱いドットは磁力を及ぼさない (ほとんどの物質は非磁性)
+const MAGNETIC_CONSTANT: f64 = 2000.0;
+const COULOMB_CONSTANT: f64 = 3000.0;
+const FORCE_RANGE: f64 = 40.0; // これより離れたドットの力は無視する
+const SOFTENING: f64 = 4.0; // 近づきすぎても力が発散しないように距離に足す
+const MIN_WAKE_ACCELERATION: f64 = 5.0; // 眠っているドットはこれより弱い力では動かさない
+const CONDUCTION_RATE: f32 = 5.0; // 伝導率 1.0 同士で1秒あたりに電荷の差を縮める速さ
+const BREAKDOWN: f32 = 1.2; // 接しているドットの電荷の差がこれを超えると放電する
+const SPARK_DURATION: f64 = 0.3; // 放電で光っている時間
+const LUMINESCENCE_GENE: usize = 12;
+
+/// クーロン力と電荷の受け渡しを dt 秒進める
+/// 空間グリッドと衝突候補ペアを使うので、update_collision の直後 (ドットを増減させる前) に呼ぶこと
+pub fn apply(physics: &Physics, dots: &mut [Dot], pairs: &[(usize, usize)], dt: f64) {
+    apply_forces(physics, dots, dt);
+
+    let mut discharges = 0;
+    for &(i, j) in pairs {
+        let (Some(a), Some(b)) = (dots.get(i), dots.get(j)) else {
+            continue;
+        };
+        let reach = a.radius() + b.radius();
+        if a.hidden || b.hidden || (b.x - a.x).powi(2) + (b.y - a.y).powi(2) > reach * reach {
+            continue;
+        }
+
+        let difference = a.material.charge - b.material.charge;
+        if difference.abs() > BREAKDOWN {
+            let mean = (a.material.charge + b.material.charge) / 2.0;
+            for k in [i, j] {
+                dots[k].material.charge = mean;
+                spark(&mut dots[k]);
+            }
+            discharges += 1;
+            continue;
+        }
+
+        let conductivity = a.material.conductivity.min(b.material.conductivity);
+        let flow = difference * 0.5 * (1.0 - (-CONDUCTION_RATE * conductivity * dt as f32).exp());
+        dots[i].material.charge -= flow;
+        dots[j].material.charge += flow;
+    }
+
+    if discharges > 0 {
+        tracing::debug!(target: crate::logging::PHYSICS, count = discharges, "electric discharges");
+    }
+}
+
+/// 磁力の強さ (MIN_MAGNETISM 以下は 0.0、磁性 1.0 で 1.0)
+fn magnetic_strength(dot: &Dot) -> f32 {
+    ((dot.material.magnetism - MIN_MAGNETISM) / (1.0 - MIN_MAGNETISM)).max(0.0)
+}
+
+/// 電荷を持つドットに近くの電荷からのクーロン力を、磁性のあるドットに近くの磁性のドットからの磁力を加える
+/// (重いドットほど動きにくい)
+fn apply_forces(physics: &Physics, dots: &mut [Dot], dt: f64) {
+    let charged = |dot: &Dot| dot.material.charge.abs() >= MIN_CHARGE && !dot.hidden;
+    let magnetic = |dot: &Dot| magnetic_strength(dot) > 0.0 && !dot.hidden;
+    let accelerations: Vec<(usize, f64, f64)> = (0..dots.len())
+        .filter(|&i| charged(&dots[i]) || magnetic(&dots[i]))
+        .filter_map(|i| {
+            let dot = &dots[i];
+            let (mut ax, mut ay) = (0.0, 0.0);
+            for j in physics.query_circle(dots, dot.x, dot.y, FORCE_RANGE) {
+                let other = &dots[j];
+                if j == i {
+                    continue;
+                }
+                let mut product = 0.0;
+                if charged(dot) && charged(other) {
+                    product += COULOMB_CONSTANT * (dot.material.charge * other.material.charge) as f64;
+                }
+                if magnetic(dot) && magnetic(other) {
+                    product -= MAGNETIC_CONSTANT * (magnetic_strength(dot) * magnetic_strength(other)) as f64;
+                }
+                if product == 0.0 {
+                    continue;
+                }
+                let (dx, dy) = (dot.x - other.x, dot.y - other.y);
+                let distance = (dx * dx + dy * dy).sqrt().max(1e-6);
+                // 正 (同符号の電荷) なら相手から離れる向き、負 (異符号の電荷や磁力) なら近づく向き
+                let strength = product / (distance + SOFTENING).powi(2);
+                ax += dx / distance * strength;
+                ay += dy / distance * strength;
+            }
+            let mass = 0.5 + dot.material.density as f64;
+            let (ax, ay) = (ax / mass, ay / mass);
+            let wakes = !dot.sleeping || ax.hypot(ay) >= MIN_WAKE_ACCELERATION;
+            wakes.then_some((i, ax, ay))
+        })
+        .collect();
+
+    for (i, ax, ay) in accelerations {
+        let dot = &mut dots[i];
+        dot.vx += ax * dt;
+        dot.vy += ay * dt;
+    }
+}
+
+/// 放電で一瞬強く光らせる (spark_time が尽きたら fade_sparks で元の明るさに戻す)
+fn spark(dot: &mut Dot) {
+    dot.material.luminescence = 1.0;
+    dot.spark_time = Some(SPARK_DURATION);
+}
+
+/// 放電の光が消えたドットを元の自発光度に戻す (電気を無効にしていても呼ぶ)
+pub fn fade_sparks(dots: &mut [Dot]) {
+    for dot in dots.iter_mut() {
+        if dot.spark_time.is_some_and(|time| time <= 0.0) {
+            dot.material.luminescence = dot.material_dna.genes[LUMINESCENCE_GENE];
+            dot.spark_time = None;
+        }
+    }
+}
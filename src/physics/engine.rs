@@ -5,17 +5,19 @@ use crate::{
 
 pub use crate::app::{HEIGHT, WIDTH};
 use bytemuck::{Pod, Zeroable};
-use rand::thread_rng;
 use rand::Rng;
+use rayon::prelude::*;
 use std::sync::mpsc;
-use std::time::Instant;
 use wgpu::util::DeviceExt;
 
 use super::state_manager::{update_state_for_dot, update_position_for_dot};
+use super::GameRng;
 use crate::physics::collision_helpers::{
-    handle_detailed_collision, handle_gas_collision, handle_gas_displacement,
-    handle_liquid_accumulation, handle_solid_spreading,
+    apply_cohesion, exchange_heat, handle_detailed_collision, handle_gas_collision,
+    handle_gas_displacement, handle_liquid_accumulation, handle_solid_spreading,
 };
+use crate::physics::interaction::{CollisionModel, InteractionMatrix};
+use crate::physics::shatter::record_impact;
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
@@ -87,28 +89,59 @@ impl GpuDot {
 }
 
 pub const COOL_DOWN_SECONDS: f64 = 1.0; // 1秒のクールダウン
+const STATE_CHANGE_HYSTERESIS: f32 = 0.05; // 融点・沸点をこれだけ下回るまで凝固・凝縮しない
 
 pub const DOT_RADIUS: f64 = 2.0;
 pub const GAS_REFERENCE_DENSITY: f32 = 0.5;
 pub const GAS_DIFFUSION_FACTOR: f64 = 5.0;
 const INITIAL_WAIT_TIME: f64 = 0.1; // seconds
 const DECAY_FACTOR: f64 = 0.5;
+const MIN_CELLS_PER_TASK: usize = 32; // 並列の衝突処理で1タスクが受け持つ最小のセル数
+const SLEEP_RADIUS: f64 = DOT_RADIUS; // 基準位置からこの範囲で揺れている間は静止とみなす
+const SLEEP_DELAY: f64 = 0.5; // この時間静止していたら眠らせてよい
+const WAKE_SPEED: f64 = 50.0; // 眠っているドットにこれより速く当たったら起こす
+const CONTACT_SLACK: f64 = 1.1; // 半径の和のこの倍まで離れていても接触しているとみなす
+
+/// 衝突した2つのドット (id, DNA)
+pub type CollisionEvent = ((u64, MaterialDNA), (u64, MaterialDNA));
+/// ブレンド処理のワーカーに衝突イベントを送るチャネル
+pub type CollisionSender = mpsc::Sender<CollisionEvent>;
 
 pub struct Physics {
     pub grid: Vec<Vec<usize>>,
+    grid_len: usize, // グリッドを作ったときのドットの数
     pub cols: usize,
     pub rows: usize,
     pub cell_size: f64,
-    pub collision_tx: mpsc::Sender<((usize, MaterialDNA), (usize, MaterialDNA))>,
+    pub collision_tx: CollisionSender,
+    worker_tx: Option<CollisionSender>, // 同期ブレンド中に退避しておくワーカーへのチャネル
+    blend_queue: Option<mpsc::Receiver<CollisionEvent>>, // 同期ブレンド中にためた衝突イベント
     pub compute_pipeline: Option<wgpu::ComputePipeline>,
     pub physics_bind_group_layout: Option<wgpu::BindGroupLayout>,
     pub physics_bind_group: Option<wgpu::BindGroup>,
     pub physics_params_buffer: Option<wgpu::Buffer>,
     pub dots_buffer: Option<wgpu::Buffer>,
+    collision_pairs: Vec<(usize, usize)>, // 衝突候補ペア (フレームをまたいで再利用する)
+    cell_ranges: Vec<(usize, usize, usize)>, // (セルの色, 開始, 終了): collision_pairs のうち1つのセルに属する範囲
+    pub interactions: InteractionMatrix, // 状態の組み合わせごとの衝突処理 (GUI から編集する)
+    island_parent: Vec<usize>,           // 接触している静止した固体の島 (union-find の親)
+    contacts: Vec<u32>,                  // ドットごとに接している固体の数
+    resting: Vec<bool>,                  // ドットごとに島に入れる (静止している) か
+    touching: Vec<(usize, usize)>,       // 接触している固体のペア
+    grounded: Vec<bool>,                 // 島の代表ごとに床に接しているか
+    sleeping_count: usize,
+}
+
+/// Physics が確保しているメモリ (バイト数)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhysicsMemory {
+    pub grid: usize,
+    pub collision_pairs: usize,
+    pub gpu_buffers: u64,
 }
 
 impl Physics {
-    pub fn new(collision_tx: mpsc::Sender<((usize, MaterialDNA), (usize, MaterialDNA))>) -> Self {
+    pub fn new(collision_tx: CollisionSender) -> Self {
         let cell_size = DOT_RADIUS * 2.0;
         let cols = (WIDTH as f64 / cell_size).ceil() as usize;
         let rows = (HEIGHT as f64 / cell_size).ceil() as usize;
@@ -116,15 +149,90 @@ impl Physics {
 
         Physics {
             grid,
+            grid_len: 0,
             cols,
             rows,
             cell_size,
             collision_tx,
+            worker_tx: None,
+            blend_queue: None,
             compute_pipeline: None,
             physics_bind_group_layout: None,
             physics_bind_group: None,
             physics_params_buffer: None,
             dots_buffer: None,
+            collision_pairs: Vec::new(),
+            cell_ranges: Vec::new(),
+            interactions: InteractionMatrix::default(),
+            island_parent: Vec::new(),
+            contacts: Vec::new(),
+            resting: Vec::new(),
+            touching: Vec::new(),
+            grounded: Vec::new(),
+            sleeping_count: 0,
+        }
+    }
+
+    /// 衝突イベントをワーカーに送らずにためておくか (決定論モード用)
+    /// ためたイベントは take_collisions で取り出し、同じステップのうちにブレンドする
+    pub fn set_synchronous_blends(&mut self, synchronous: bool) {
+        if synchronous == self.blend_queue.is_some() {
+            return;
+        }
+        if synchronous {
+            let (tx, rx) = mpsc::channel();
+            self.worker_tx = Some(std::mem::replace(&mut self.collision_tx, tx));
+            self.blend_queue = Some(rx);
+        } else {
+            self.blend_queue = None;
+            if let Some(worker_tx) = self.worker_tx.take() {
+                self.collision_tx = worker_tx;
+            }
+        }
+    }
+
+    pub fn synchronous_blends(&self) -> bool {
+        self.blend_queue.is_some()
+    }
+
+    /// ブレンド処理のワーカーへのチャネル (同期ブレンド中でもワーカーの方を返す)
+    pub fn worker_sender(&self) -> CollisionSender {
+        self.worker_tx.as_ref().unwrap_or(&self.collision_tx).clone()
+    }
+
+    /// 同期ブレンド中にためた衝突イベントを、並列処理の順に左右されない順 (ID 順) で取り出す
+    pub fn take_collisions(&mut self) -> Vec<CollisionEvent> {
+        let Some(queue) = &self.blend_queue else {
+            return Vec::new();
+        };
+        let mut events: Vec<CollisionEvent> = queue.try_iter().collect();
+        events.sort_unstable_by_key(|((id_a, _), (id_b, _))| (*id_a, *id_b));
+        events
+    }
+
+    /// 直近の update_collision で集めた衝突候補ペア (ドットのインデックス)
+    pub fn collision_pairs(&self) -> &[(usize, usize)] {
+        &self.collision_pairs
+    }
+
+    /// 直近の update_collision で眠っていたドットの数
+    pub fn sleeping_count(&self) -> usize {
+        self.sleeping_count
+    }
+
+    pub fn memory_usage(&self) -> PhysicsMemory {
+        let grid = self.grid.capacity() * std::mem::size_of::<Vec<usize>>()
+            + self.grid.iter().map(|cell| cell.capacity() * std::mem::size_of::<usize>()).sum::<usize>();
+        let gpu_buffers = [&self.physics_params_buffer, &self.dots_buffer]
+            .into_iter()
+            .flatten()
+            .map(wgpu::Buffer::size)
+            .sum();
+        PhysicsMemory {
+            grid,
+            collision_pairs: self.collision_pairs.capacity() * std::mem::size_of::<(usize, usize)>()
+                + self.cell_ranges.capacity() * std::mem::size_of::<(usize, usize, usize)>(),
+            gpu_buffers,
         }
     }
 
@@ -178,6 +286,7 @@ impl Physics {
 
         // バインドグループレイアウトを保存
         self.physics_bind_group_layout = Some(bind_group_layout);
+        tracing::info!(target: crate::logging::PHYSICS, "GPU physics pipeline created");
     }
 
     pub fn update_gpu_resources(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, dots: &[Dot], dt: f64) {
@@ -202,7 +311,7 @@ impl Physics {
         }
 
         // ドットデータバッファの作成・更新
-        let dots_data: Vec<GpuDot> = dots.iter().map(|dot| GpuDot::from_cpu_dot(dot)).collect();
+        let dots_data: Vec<GpuDot> = dots.iter().map(GpuDot::from_cpu_dot).collect();
         let dots_bytes = bytemuck::cast_slice(&dots_data);
 
         if let Some(buffer) = &self.dots_buffer {
@@ -254,11 +363,11 @@ impl Physics {
 
             compute_pass.set_pipeline(pipeline);
             compute_pass.set_bind_group(0, bind_group, &[]);
-            compute_pass.dispatch_workgroups((self.dots_buffer.as_ref().unwrap().size() as u32 / std::mem::size_of::<GpuDot>() as u32 + 63) / 64, 1, 1);
+            compute_pass.dispatch_workgroups((self.dots_buffer.as_ref().unwrap().size() as u32 / std::mem::size_of::<GpuDot>() as u32).div_ceil(64), 1, 1);
         }
     }
 
-    pub fn sync_gpu_to_cpu(&self, device: &wgpu::Device, queue: &wgpu::Queue, dots: &mut Vec<Dot>) {
+    pub fn sync_gpu_to_cpu(&self, device: &wgpu::Device, queue: &wgpu::Queue, dots: &mut [Dot]) {
         if let Some(buffer) = &self.dots_buffer {
             // GPUバッファからCPUにデータをコピー
             let size = buffer.size();
@@ -280,7 +389,7 @@ impl Physics {
             let buffer_slice = read_buffer.slice(..);
             
             // 非同期マッピングを同期的に待機
-            let _ = buffer_slice.map_async(wgpu::MapMode::Read, |_| {});
+            buffer_slice.map_async(wgpu::MapMode::Read, |_| {});
             device.poll(wgpu::Maintain::Wait);
 
             let data = buffer_slice.get_mapped_range();
@@ -288,7 +397,8 @@ impl Physics {
             
             // GPUデータをCPUデータに変換
             for (i, gpu_dot) in gpu_dots.iter().enumerate() {
-                if i < dots.len() {
+                // GPU 側は眠りを知らないので、眠っているドットは CPU 側の値を保つ
+                if i < dots.len() && !dots[i].sleeping {
                     dots[i].x = gpu_dot.position[0] as f64;
                     dots[i].y = gpu_dot.position[1] as f64;
                     dots[i].vx = gpu_dot.velocity[0] as f64;
@@ -302,13 +412,60 @@ impl Physics {
         }
     }
 
-    pub fn update_collision(&mut self, dots: &mut Vec<Dot>, dt: f64) -> bool {
-        // 1. グリッドをクリア
+    /// 矩形内のドットのインデックスを空間グリッドから探す
+    /// グリッドは直近の update_collision (rebuild_grid) 時点のものなので、その後の移動分として1セル余分に探し、
+    /// 判定は現在の位置で行う。その後にドットを増減させたときは先に rebuild_grid を呼ぶこと
+    pub fn query_rect<'a>(
+        &'a self,
+        dots: &'a [Dot],
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> impl Iterator<Item = usize> + 'a {
+        debug_assert_eq!(self.grid_len, dots.len(), "spatial grid is stale; call rebuild_grid first");
+        let cell_range = |min: f64, max: f64, count: usize| {
+            let first = ((min / self.cell_size).floor() as i64 - 1).clamp(0, count as i64) as usize;
+            let end = ((max / self.cell_size).floor() as i64 + 2).clamp(0, count as i64) as usize;
+            first..end
+        };
+        let cols = cell_range(min_x, max_x, self.cols);
+        let rows = cell_range(min_y, max_y, self.rows);
+
+        rows.flat_map(move |row| cols.clone().map(move |col| row * self.cols + col))
+            .flat_map(move |cell_idx| self.grid[cell_idx].iter().copied())
+            .filter(move |&i| {
+                dots.get(i).is_some_and(|dot| {
+                    dot.x >= min_x && dot.x <= max_x && dot.y >= min_y && dot.y <= max_y
+                })
+            })
+    }
+
+    /// 円内のドットのインデックスを空間グリッドから探す (query_rect と同じ制約)
+    pub fn query_circle<'a>(&'a self, dots: &'a [Dot], x: f64, y: f64, radius: f64) -> impl Iterator<Item = usize> + 'a {
+        self.query_rect(dots, x - radius, y - radius, x + radius, y + radius)
+            .filter(move |&i| {
+                let dx = dots[i].x - x;
+                let dy = dots[i].y - y;
+                dx * dx + dy * dy <= radius * radius
+            })
+    }
+
+    /// セルサイズを変更してグリッドを作り直す
+    fn resize_grid(&mut self, cell_size: f64) {
+        self.cell_size = cell_size;
+        self.cols = (WIDTH as f64 / cell_size).ceil() as usize;
+        self.rows = (HEIGHT as f64 / cell_size).ceil() as usize;
+        self.grid = vec![Vec::new(); self.cols * self.rows];
+    }
+
+    /// 今のドットの並びと位置で空間グリッドを作り直す (セルサイズは変えない)
+    /// update_collision の後にドットを増減させてから query_rect / query_circle を使うときに呼ぶ
+    pub fn rebuild_grid(&mut self, dots: &[Dot]) {
         for cell in self.grid.iter_mut() {
             cell.clear();
         }
-
-        // 2. ドットをグリッドに登録
+        self.grid_len = dots.len();
         for (i, dot) in dots.iter().enumerate() {
             let cell_x = (dot.x / self.cell_size).floor() as usize;
             let cell_y = (dot.y / self.cell_size).floor() as usize;
@@ -317,151 +474,342 @@ impl Physics {
                 self.grid[cell_idx].push(i);
             }
         }
+    }
+
+    pub fn update_collision(&mut self, dots: &mut [Dot], dt: f64) -> bool {
+        // 0. セルサイズを存在する最大の直径に合わせる
+        //    (セルが直径以上なら、衝突しうるペアは必ず隣接セルに収まる)
+        let max_radius = dots.iter().map(Dot::radius).fold(DOT_RADIUS, f64::max);
+        let cell_size = max_radius * 2.0;
+        if cell_size != self.cell_size {
+            tracing::debug!(target: crate::logging::PHYSICS, cell_size, "resizing collision grid");
+            self.resize_grid(cell_size);
+        }
 
-        let mut potentially_colliding_pairs = Vec::new();
-
-        // 3. 衝突候補ペアを収集
-        for i in 0..dots.len() {
-            let cell_x = (dots[i].x / self.cell_size).floor() as i32;
-            let cell_y = (dots[i].y / self.cell_size).floor() as i32;
-
-            for y_offset in -1..=1 {
-                for x_offset in -1..=1 {
-                    let check_x = cell_x + x_offset;
-                    let check_y = cell_y + y_offset;
-
-                    if check_x >= 0
-                        && check_x < self.cols as i32
-                        && check_y >= 0
-                        && check_y < self.rows as i32
-                    {
-                        let cell_idx = (check_y as usize) * self.cols + (check_x as usize);
-                        for &j in &self.grid[cell_idx] {
-                            if i < j {
-                                // ペアを一度だけ登録
-                                potentially_colliding_pairs.push((i, j));
+        // 1-2. グリッドをクリアしてドットを登録
+        self.rebuild_grid(dots);
+
+        let mut pairs = std::mem::take(&mut self.collision_pairs);
+        pairs.clear();
+        self.cell_ranges.clear();
+
+        // 3. 衝突候補ペアをセルごとに収集
+        //    ペア (i, j) は i のセルに属し、i のセルと j のセルは隣接 (または同じ)
+        for cell_idx in 0..self.grid.len() {
+            let (cell_x, cell_y) = ((cell_idx % self.cols) as i32, (cell_idx / self.cols) as i32);
+            let start = pairs.len();
+            for &i in &self.grid[cell_idx] {
+                for y_offset in -1..=1 {
+                    for x_offset in -1..=1 {
+                        let check_x = cell_x + x_offset;
+                        let check_y = cell_y + y_offset;
+
+                        if check_x >= 0
+                            && check_x < self.cols as i32
+                            && check_y >= 0
+                            && check_y < self.rows as i32
+                        {
+                            let neighbor_idx = (check_y as usize) * self.cols + (check_x as usize);
+                            for &j in &self.grid[neighbor_idx] {
+                                if i < j {
+                                    // ペアを一度だけ登録
+                                    pairs.push((i, j));
+                                }
                             }
                         }
                     }
                 }
             }
+            if pairs.len() > start {
+                // セルを (x % 3, y % 3) で9色に塗り分ける
+                let color = (cell_x % 3 + cell_y % 3 * 3) as usize;
+                self.cell_ranges.push((color, start, pairs.len()));
+            }
         }
 
-        // 4. 衝突判定と処理
-        for (i, j) in potentially_colliding_pairs {
-            let (dot1_x, dot1_y, dot2_x, dot2_y) = {
-                let dot1 = &dots[i];
-                let dot2 = &dots[j];
-                (dot1.x, dot1.y, dot2.x, dot2.y)
-            };
-
-            let dx = dot2_x - dot1_x;
-            let dy = dot2_y - dot1_y;
-            let distance_sq = dx * dx + dy * dy;
-            let min_dist = DOT_RADIUS * 2.0;
-
-            if distance_sq < min_dist * min_dist && distance_sq > 1e-6 {
-                let now = Instant::now();
-
-                // --- Reaction Logic ---
-                let dot1 = &dots[i];
-                let dot2 = &dots[j];
-                let wait_time1 =
-                    INITIAL_WAIT_TIME * (DECAY_FACTOR * dot1.reaction_count as f64).exp();
-                let wait_time2 =
-                    INITIAL_WAIT_TIME * (DECAY_FACTOR * dot2.reaction_count as f64).exp();
-                let elapsed1 = now.duration_since(dot1.last_reaction_time).as_secs_f64();
-                let elapsed2 = now.duration_since(dot2.last_reaction_time).as_secs_f64();
-
-                if elapsed1 >= wait_time1 && elapsed2 >= wait_time2 {
-                    // Send collision event for material blending
-                    let _ = self.collision_tx.send((
-                        (i, dots[i].material_dna.clone()),
-                        (j, dots[j].material_dna.clone()),
-                    ));
-
-                    // Update reaction counters and timestamps
-                    let (dot1_slice, dot2_slice) = dots.split_at_mut(j);
-                    let dot1 = &mut dot1_slice[i];
-                    let dot2 = &mut dot2_slice[0];
-                    dot1.reaction_count += 1;
-                    dot2.reaction_count += 1;
-                    dot1.last_reaction_time = now;
-                    dot2.last_reaction_time = now;
+        // 4. 床から積み上がった静止部分を眠らせ、乱されたドットを起こす
+        self.update_islands(dots, &pairs);
+
+        // 5. 衝突判定と処理
+        //    同じ色のセルを並列に処理する。同じ色のセルは3セル以上離れているので、
+        //    それぞれが触れる (隣接セルまでの) ドットは重ならない
+        self.cell_ranges.sort_unstable_by_key(|&(color, _, _)| color);
+        let dots_ptr = DotsPtr(dots.as_mut_ptr());
+        let collision_tx = &self.collision_tx;
+        let interactions = &self.interactions;
+        let resolve_cell = |&(_, start, end): &(usize, usize, usize)| {
+            for &(i, j) in &pairs[start..end] {
+                // SAFETY: i < j なので別のドット。同じ色の他のセルのタスクはこの2つに触れない (上記)
+                let (dot1, dot2) = unsafe { (dots_ptr.get(i), dots_ptr.get(j)) };
+                resolve_pair_with_sleepers(dot1, dot2, dt, collision_tx, interactions);
+            }
+        };
+        if rayon::current_num_threads() == 1 {
+            // 並列にできないときはタスク分割のオーバーヘッドを避ける
+            self.cell_ranges.iter().for_each(resolve_cell);
+        } else {
+            for same_color in self.cell_ranges.chunk_by(|a, b| a.0 == b.0) {
+                same_color.par_iter().with_min_len(MIN_CELLS_PER_TASK).for_each(resolve_cell);
+            }
+        }
+
+        self.collision_pairs = pairs;
+        true
+    }
+
+    /// 静止している固体のうち接触しているものを島にまとめ、床に接している島を眠らせる
+    /// (床から積み上がった静止部分だけが眠る)。眠っているドットに速度が加えられる (爆発やツール) か、
+    /// 接していたドットが離れたり消えたりすると起こす。支えを失った島は床に接しなくなるので起きる
+    fn update_islands(&mut self, dots: &mut [Dot], pairs: &[(usize, usize)]) {
+        // 眠ってよいか (しばらく静止している固体か) をまず決め、接触はその候補を含むペアだけ調べる
+        self.resting.clear();
+        self.resting.extend(dots.iter().map(|dot| {
+            dot.material.state == State::Solid && !dot.has_pending_timer() && dot.rest_time >= SLEEP_DELAY
+        }));
+        self.contacts.clear();
+        self.contacts.resize(dots.len(), 0);
+        self.touching.clear();
+        for &(i, j) in pairs {
+            if !self.resting[i] && !self.resting[j] {
+                continue;
+            }
+            let (dot1, dot2) = (&dots[i], &dots[j]);
+            if dot1.material.state != State::Solid || dot2.material.state != State::Solid {
+                continue;
+            }
+            let reach = (dot1.radius() + dot2.radius()) * CONTACT_SLACK;
+            let (dx, dy) = (dot2.x - dot1.x, dot2.y - dot1.y);
+            if dx * dx + dy * dy <= reach * reach {
+                self.contacts[i] += 1;
+                self.contacts[j] += 1;
+                self.touching.push((i, j));
+            }
+        }
+
+        // 眠っている間に速度が加えられたり、接していたドットが離れたものは島に入れない
+        for (i, dot) in dots.iter().enumerate() {
+            if dot.sleeping && (dot.vx != 0.0 || dot.vy != 0.0 || self.contacts[i] < dot.sleep_contacts) {
+                self.resting[i] = false;
+            }
+        }
+
+        let parent = &mut self.island_parent;
+        parent.clear();
+        parent.extend(0..dots.len());
+        for &(i, j) in &self.touching {
+            if self.resting[i] && self.resting[j] {
+                let (root1, root2) = (find_root(parent, i), find_root(parent, j));
+                if root1 != root2 {
+                    parent[root1] = root2;
                 }
+            }
+        }
 
-                // --- Physical Collision Response (always happens) ---
-                let (dot1_slice, dot2_slice) = dots.split_at_mut(j);
-                let dot1 = &mut dot1_slice[i];
-                let dot2 = &mut dot2_slice[0];
+        // 島の代表ごとに床に接しているか
+        self.grounded.clear();
+        self.grounded.resize(dots.len(), false);
+        for (i, dot) in dots.iter().enumerate() {
+            if self.resting[i] && dot.y >= HEIGHT as f64 - dot.radius() - 1.0 {
+                let root = find_root(parent, i);
+                self.grounded[root] = true;
+            }
+        }
 
-                let distance = distance_sq.sqrt();
-                let overlap = 0.5 * (min_dist - distance);
-                let nx = dx / distance;
-                let ny = dy / distance;
+        self.sleeping_count = 0;
+        for (i, dot) in dots.iter_mut().enumerate() {
+            if self.resting[i] && self.grounded[find_root(parent, i)] {
+                if !dot.sleeping {
+                    dot.sleeping = true;
+                    dot.vx = 0.0;
+                    dot.vy = 0.0;
+                    dot.sleep_contacts = self.contacts[i];
+                }
+                self.sleeping_count += 1;
+            } else if dot.sleeping {
+                dot.wake();
+            }
+        }
+    }
+}
 
-                dot1.x -= overlap * nx;
-                dot1.y -= overlap * ny;
-                dot2.x += overlap * nx;
-                dot2.y += overlap * ny;
+/// union-find の代表を探す (経路を半分に縮めながら)
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
 
-                match (dot1.material.state, dot2.material.state) {
-                    (State::Solid, State::Solid) | (State::Liquid, State::Liquid) => {
-                        handle_detailed_collision(dot1, dot2, nx, ny, dt);
+/// 並列の衝突処理でドットの配列を共有するためのポインタ
+/// (どのドットにも同時に1つのタスクしか触れないことは呼び出し側が保証する)
+#[derive(Clone, Copy)]
+struct DotsPtr(*mut Dot);
 
-                        if dot1.material.state == State::Liquid
-                            && dot2.material.state == State::Liquid
-                        {
-                            handle_liquid_accumulation(dot1, dot2, nx, ny, dt);
-                        } else if dot1.material.state == State::Solid
-                            && dot2.material.state == State::Solid
-                        {
-                            handle_solid_spreading(dot1, dot2, nx, ny, dt);
-                        }
-                    }
-                    (State::Solid, State::Liquid) => {
-                        if dot1.material.density > dot2.material.density
-                            && dot1.material.viscosity > dot2.material.viscosity
-                        {
-                            let e =
-                                (dot1.material.elasticity + dot2.material.elasticity) as f64 / 2.0;
-                            let v_liquid_n = dot2.vx * nx + dot2.vy * ny;
-                            if v_liquid_n < 0.0 {
-                                dot2.vx -= (1.0 + e) * v_liquid_n * nx;
-                                dot2.vy -= (1.0 + e) * v_liquid_n * ny;
-                            }
-                        } else {
-                            handle_detailed_collision(dot1, dot2, nx, ny, dt);
-                        }
-                    }
-                    (State::Liquid, State::Solid) => {
-                        if dot2.material.density > dot1.material.density
-                            && dot2.material.viscosity > dot1.material.viscosity
-                        {
-                            let e =
-                                (dot1.material.elasticity + dot2.material.elasticity) as f64 / 2.0;
-                            let v_liquid_n = dot1.vx * (-nx) + dot1.vy * (-ny);
-                            if v_liquid_n < 0.0 {
-                                dot1.vx -= (1.0 + e) * v_liquid_n * (-nx);
-                                dot1.vy -= (1.0 + e) * v_liquid_n * (-ny);
-                            }
-                        } else {
-                            handle_detailed_collision(dot1, dot2, nx, ny, dt);
-                        }
-                    }
-                    (State::Gas, State::Gas) => {
-                        handle_gas_collision(dot1, dot2, nx, ny);
-                    }
-                    (State::Solid, State::Gas) | (State::Liquid, State::Gas) => {
-                        handle_gas_displacement(dot2, dot1, nx, ny);
-                    }
-                    (State::Gas, State::Solid) | (State::Gas, State::Liquid) => {
-                        handle_gas_displacement(dot1, dot2, nx, ny);
+unsafe impl Send for DotsPtr {}
+unsafe impl Sync for DotsPtr {}
+
+impl DotsPtr {
+    /// # Safety
+    /// index は配列の範囲内で、返した参照が生きている間は他から同じドットに触れないこと
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn get(&self, index: usize) -> &mut Dot {
+        &mut *self.0.add(index)
+    }
+}
+
+/// 眠っているドットを含むペアの処理。眠っている島の内部は何もしない。
+/// 起きているドットが速く当たったら眠っている方を起こし、そうでなければ眠っている方を動かない壁として扱う
+fn resolve_pair_with_sleepers(
+    dot1: &mut Dot,
+    dot2: &mut Dot,
+    dt: f64,
+    collision_tx: &CollisionSender,
+    interactions: &InteractionMatrix,
+) {
+    if dot1.sleeping && dot2.sleeping {
+        return;
+    }
+    let fast = |dot: &Dot| dot.vx * dot.vx + dot.vy * dot.vy > WAKE_SPEED * WAKE_SPEED;
+    if dot1.sleeping && fast(dot2) {
+        dot1.wake();
+    }
+    if dot2.sleeping && fast(dot1) {
+        dot2.wake();
+    }
+
+    let pinned1 = dot1.sleeping.then_some((dot1.x, dot1.y));
+    let pinned2 = dot2.sleeping.then_some((dot2.x, dot2.y));
+    resolve_pair(dot1, dot2, dt, collision_tx, interactions);
+    if let Some(position) = pinned1 {
+        pin_sleeper(dot1, dot2, position);
+    }
+    if let Some(position) = pinned2 {
+        pin_sleeper(dot2, dot1, position);
+    }
+}
+
+/// 眠っているドットを元の位置に戻し、押し戻された分は相手を押し戻す
+fn pin_sleeper(sleeper: &mut Dot, other: &mut Dot, (x, y): (f64, f64)) {
+    other.x -= sleeper.x - x;
+    other.y -= sleeper.y - y;
+    sleeper.x = x;
+    sleeper.y = y;
+    sleeper.vx = 0.0;
+    sleeper.vy = 0.0;
+}
+
+/// 衝突候補ペア1組の判定と応答 (反応イベントの送信、押し戻し、状態の組み合わせごとの衝突処理)
+fn resolve_pair(
+    dot1: &mut Dot,
+    dot2: &mut Dot,
+    dt: f64,
+    collision_tx: &CollisionSender,
+    interactions: &InteractionMatrix,
+) {
+    let min_dist = dot1.radius() + dot2.radius();
+    let dx = dot2.x - dot1.x;
+    let dy = dot2.y - dot1.y;
+    let distance_sq = dx * dx + dy * dy;
+
+    if distance_sq < min_dist * min_dist && distance_sq > 1e-6 {
+        // --- Reaction Logic ---
+        let wait_time1 =
+            INITIAL_WAIT_TIME * (DECAY_FACTOR * dot1.reaction_count as f64).exp();
+        let wait_time2 =
+            INITIAL_WAIT_TIME * (DECAY_FACTOR * dot2.reaction_count as f64).exp();
+
+        if dot1.since_reaction >= wait_time1 && dot2.since_reaction >= wait_time2 {
+            // Send collision event for material blending
+            let _ = collision_tx.send((
+                (dot1.id, dot1.material_dna.clone()),
+                (dot2.id, dot2.material_dna.clone()),
+            ));
+
+            // Update reaction counters and timestamps
+            dot1.reaction_count += 1;
+            dot2.reaction_count += 1;
+            dot1.since_reaction = 0.0;
+            dot2.since_reaction = 0.0;
+        }
+
+        let interaction = interactions.get(dot1.material.state, dot2.material.state);
+
+        // --- Physical Collision Response ---
+        let distance = distance_sq.sqrt();
+        let nx = dx / distance;
+        let ny = dy / distance;
+
+        if interaction.model != CollisionModel::None {
+            let overlap = 0.5 * (min_dist - distance);
+            dot1.x -= overlap * nx;
+            dot1.y -= overlap * ny;
+            dot2.x += overlap * nx;
+            dot2.y += overlap * ny;
+
+            record_impact(dot1, dot2, nx, ny);
+        }
+
+        match interaction.model {
+            CollisionModel::Elastic => handle_elastic_collision(dot1, dot2, nx, ny, dt),
+            CollisionModel::Rebound => {
+                // エネルギーの低い状態 (同じなら密度の高い方) が重い側になる
+                let dot1_is_heavy = heavier_first(dot1, dot2);
+                let (heavy, light) = if dot1_is_heavy { (&*dot1, &*dot2) } else { (&*dot2, &*dot1) };
+                if heavy.material.density > light.material.density
+                    && heavy.material.viscosity > light.material.viscosity
+                {
+                    let e = (dot1.material.elasticity + dot2.material.elasticity) as f64 / 2.0;
+                    // (nx, ny) を重い側から軽い側への向きにする
+                    let (lx, ly) = if dot1_is_heavy { (nx, ny) } else { (-nx, -ny) };
+                    let light = if dot1_is_heavy { &mut *dot2 } else { &mut *dot1 };
+                    let v_light_n = light.vx * lx + light.vy * ly;
+                    if v_light_n < 0.0 {
+                        light.vx -= (1.0 + e) * v_light_n * lx;
+                        light.vy -= (1.0 + e) * v_light_n * ly;
                     }
+                } else {
+                    handle_elastic_collision(dot1, dot2, nx, ny, dt);
                 }
             }
+            CollisionModel::Light => handle_gas_collision(dot1, dot2, nx, ny),
+            CollisionModel::Displace => {
+                if heavier_first(dot1, dot2) {
+                    handle_gas_displacement(dot2, dot1, nx, ny);
+                } else {
+                    handle_gas_displacement(dot1, dot2, nx, ny);
+                }
+            }
+            CollisionModel::None => {}
         }
-        true
+
+        if interaction.heat_transfer {
+            exchange_heat(dot1, dot2);
+        }
+        if interaction.cohesion {
+            apply_cohesion(dot1, dot2);
+        }
+    }
+}
+
+/// 密度と硬さを質量とした反発。同じ状態同士なら液体の蓄積や固体の広がりも加える
+fn handle_elastic_collision(dot1: &mut Dot, dot2: &mut Dot, nx: f64, ny: f64, dt: f64) {
+    handle_detailed_collision(dot1, dot2, nx, ny, dt);
+    match (dot1.material.state, dot2.material.state) {
+        (State::Liquid, State::Liquid) => handle_liquid_accumulation(dot1, dot2, nx, ny, dt),
+        (State::Solid, State::Solid) => handle_solid_spreading(dot1, dot2, nx, ny, dt),
+        _ => {}
+    }
+}
+
+/// dot1 の方が「重い」側か (エネルギーの低い状態。同じ状態なら密度の高い方)
+fn heavier_first(dot1: &Dot, dot2: &Dot) -> bool {
+    let energy1 = dot1.material.state.get_energy_level();
+    let energy2 = dot2.material.state.get_energy_level();
+    if energy1 != energy2 {
+        energy1 < energy2
+    } else {
+        dot1.material.density >= dot2.material.density
     }
 }
 
@@ -474,99 +822,74 @@ pub struct Explosion {
     heat: f32,
 }
 
-pub fn update_state(dots: &mut Vec<Dot>, gravity: f64, dt: f64) {
-    let mut rng = thread_rng();
+pub fn update_state(dots: &mut Vec<Dot>, gravity: f64, dt: f64, convection_enabled: bool, rng: &mut GameRng) {
     let mut explosions: Vec<Explosion> = Vec::new();
     let mut dots_to_remove: Vec<usize> = Vec::new();
 
     // 1. 状態変化と爆発の検出
     for (i, dot) in dots.iter_mut().enumerate() {
+        dot.advance_timers(dt);
         if dots_to_remove.contains(&i) {
             continue;
         }
 
         // 発光中のGasの状態をチェック
-        if let Some(since) = dot.glowing_since {
-            if since.elapsed().as_secs_f64() > 5.0 {
+        if let Some(glow_time) = dot.glow_time {
+            if glow_time > 5.0 {
                 dot.material.state = State::Solid;
                 dot.material.temperature = 0.0;
                 dot.material.luminescence = 0.0;
-                dot.glowing_since = None;
+                dot.glow_time = None;
                 // 固体化したら、このフレームでの他の状態変化はスキップ
                 continue;
             }
         }
 
-        // 高温時の状態変化
-        if dot.material.temperature > dot.material.heat_capacity_high {
+        // 温度に応じた粘度・硬度
+        dot.material.apply_temperature_curves(&dot.material_dna, convection_enabled);
+
+        // 融点・沸点の遺伝子による状態変化 (1ステップに1段階だけ)
+        // 戻るときは閾値を STATE_CHANGE_HYSTERESIS だけ下回るまで待つので、閾値付近で行き来し続けない
+        let temperature = dot.material.temperature;
+        match dot.material.state {
+            State::Solid if temperature > dot.material.melting_point => dot.material.state = State::Liquid,
+            State::Liquid if temperature > dot.material.boiling_point => dot.material.state = State::Gas,
+            State::Liquid if temperature < dot.material.melting_point - STATE_CHANGE_HYSTERESIS => {
+                dot.material.state = State::Solid
+            }
+            State::Gas if temperature < dot.material.boiling_point - STATE_CHANGE_HYSTERESIS => {
+                dot.material.state = State::Liquid
+            }
+            _ => {}
+        }
+
+        // 高温の気体の発光
+        if dot.material.state == State::Gas && temperature > dot.material.heat_capacity_high {
             dot.material.heat_conductivity += 0.1 * dt as f32;
             // heat_conductivity の上限を 1.0 に制限
             dot.material.heat_conductivity = dot.material.heat_conductivity.min(1.0);
-            if dot.material.heat_conductivity > 1.0 {
-                // volatilityが0.5以上の場合のみ状態変化
-                if dot.material.volatility >= 0.5 {
-                    match dot.material.state {
-                        State::Solid => {
-                            dot.material.state = State::Liquid;
-                            // 連続した状態変化を防ぐためにパラメータをランダム化
-                            dot.material.heat_capacity_high = rng.gen(); // 0.0 ~ 1.0
-                            dot.material.temperature =
-                                dot.material.heat_capacity_high * rng.gen::<f32>(); // 新しい上限より低い値に
-                            dot.material.heat_conductivity = rng.gen(); // 0.0 ~ 1.0
-                        }
-                        State::Liquid => {
-                            dot.material.state = State::Gas;
-                            // 連続した状態変化を防ぐためにパラメータをランダム化
-                            dot.material.heat_capacity_high = rng.gen(); // 0.0 ~ 1.0
-                            dot.material.temperature =
-                                dot.material.heat_capacity_high * rng.gen::<f32>(); // 新しい上限より低い値に
-                            dot.material.heat_conductivity = rng.gen(); // 0.0 ~ 1.0
-                        }
-                        State::Gas => {
-                            // 発光状態に移行
-                            dot.material.luminescence = 1.0;
-                            dot.glowing_since = Some(Instant::now());
-                            // パラメータをリセットして、すぐに再発火しないようにする
-                            dot.material.heat_capacity_high = rng.gen();
-                            dot.material.temperature =
-                                dot.material.heat_capacity_high * rng.gen::<f32>();
-                            dot.material.heat_conductivity = rng.gen();
-                        }
-                    }
-                }
+            // volatilityが0.5以上の場合のみ発光
+            if dot.material.heat_conductivity > 1.0 && dot.material.volatility >= 0.5 {
+                // 発光状態に移行
+                dot.material.luminescence = 1.0;
+                dot.glow_time = Some(0.0);
+                // パラメータをリセットして、すぐに再発火しないようにする
+                dot.material.heat_capacity_high = rng.gen();
+                dot.material.temperature = dot.material.heat_capacity_high * rng.gen::<f32>();
+                dot.material.heat_conductivity = rng.gen();
             }
         }
 
-        // 低温時の状態変化 (plan.md L105-L111)
-        if dot.material.temperature < -dot.material.heat_capacity_low {
-            match dot.material.state {
-                State::Gas => {
-                    dot.material.state = State::Liquid;
-                    // 連続した状態変化を防ぐためにパラメータをランダム化
-                    dot.material.heat_capacity_low = rng.gen::<f32>() - 1.0; // 0.0 ~ 1.0
-                    dot.material.temperature =
-                        -dot.material.heat_capacity_low * rng.gen::<f32>(); // 新しい下限より高い値に
-                    dot.material.heat_conductivity = rng.gen(); // 0.0 ~ 1.0
-                }
-                State::Liquid => {
-                    dot.material.state = State::Solid;
-                    // 連続した状態変化を防ぐためにパラメータをランダム化
-                    dot.material.heat_capacity_low = rng.gen::<f32>() - 1.0; // 0.0 ~ 1.0
-                    dot.material.temperature =
-                        -dot.material.heat_capacity_low * rng.gen::<f32>(); // 新しい下限より高い値に
-                    dot.material.heat_conductivity = rng.gen(); // 0.0 ~ 1.0
-                }
-                State::Solid => {
-                    // クールダウンチェック
-                    if dot.last_check_time.elapsed().as_secs_f64() > COOL_DOWN_SECONDS {
-                        if rng.gen::<f32>() < 0.001 {
-                            // 0.1%の確率で崩壊
-                            dots_to_remove.push(i);
-                        }
-                        // 確率判定を行ったら時刻を更新
-                        dot.last_check_time = Instant::now();
-                    }
+        // 低温の固体の崩壊 (plan.md L105-L111)
+        if dot.material.state == State::Solid && temperature < -dot.material.heat_capacity_low {
+            // クールダウンチェック
+            if dot.since_check > COOL_DOWN_SECONDS {
+                if rng.gen::<f32>() < 0.001 {
+                    // 0.1%の確率で崩壊
+                    dots_to_remove.push(i);
                 }
+                // 確率判定を行ったら経過時間をリセット
+                dot.since_check = 0.0;
             }
         }
 
@@ -630,8 +953,13 @@ pub fn update_state(dots: &mut Vec<Dot>, gravity: f64, dt: f64) {
             continue;
         }
 
+        // 眠っているドットには重力などの力をかけない
+        if dot.sleeping {
+            continue;
+        }
+
         // Stateに応じた処理を呼び分ける
-        update_state_for_dot(dot, gravity, dt);
+        update_state_for_dot(dot, gravity, dt, rng);
     }
 
     // 4. 爆発したドットを削除
@@ -642,16 +970,30 @@ pub fn update_state(dots: &mut Vec<Dot>, gravity: f64, dt: f64) {
     }
 }
 
-pub fn update_position(dots: &mut Vec<Dot>, dt: f64) -> bool {
+pub fn update_position(dots: &mut Vec<Dot>, dt: f64, rng: &mut GameRng) -> bool {
     let mut all_stopped = true;
 
     for dot in dots {
+        // 眠っているドットは動かない (止まっているものとして数える)
+        if dot.sleeping {
+            continue;
+        }
+
         dot.x += dot.vx * dt;
 
         dot.y += dot.vy * dt;
 
         // Stateに応じた境界処理と減衰処理を呼び分ける
-        update_position_for_dot(dot, dt);
+        update_position_for_dot(dot, dt, rng);
+
+        // 基準位置の近くで揺れているだけなら静止している時間を数える
+        let (anchor_x, anchor_y) = dot.rest_anchor;
+        if (dot.x - anchor_x).powi(2) + (dot.y - anchor_y).powi(2) <= SLEEP_RADIUS * SLEEP_RADIUS {
+            dot.rest_time += dt;
+        } else {
+            dot.rest_anchor = (dot.x, dot.y);
+            dot.rest_time = 0.0;
+        }
 
         if dot.material.state != State::Gas {
             let velocity_small = dot.vy.abs() < 0.1 && dot.vx.abs() < 0.1;
@@ -1,19 +1,17 @@
 ﻿use crate::{
     app::Dot,
 };
-use rand::thread_rng;
 use rand::Rng;
 
-use super::{DOT_RADIUS, HEIGHT, WIDTH, GAS_REFERENCE_DENSITY, GAS_DIFFUSION_FACTOR}; // 必要な定数をインポート
+use super::{GameRng, DOT_RADIUS, HEIGHT, WIDTH, GAS_REFERENCE_DENSITY, GAS_DIFFUSION_FACTOR}; // 必要な定数をインポート
 
 // State::Gas に対する update_state 処理
-pub fn update_state_for_gas(dot: &mut Dot, gravity: f64, dt: f64) {
+pub fn update_state_for_gas(dot: &mut Dot, gravity: f64, dt: f64, rng: &mut GameRng) {
     // State::Gas は浮力の影響を受ける
     let buoyancy = (GAS_REFERENCE_DENSITY - dot.material.density) as f64 * gravity;
     dot.vy -= buoyancy * dt;
     let diffusion_strength =
         (1.0 - dot.material.viscosity) as f64 * GAS_DIFFUSION_FACTOR;
-    let mut rng = thread_rng();
     dot.vx += (rng.gen::<f64>() - 0.5) * diffusion_strength * dt;
     dot.vy += (rng.gen::<f64>() - 0.5) * diffusion_strength * dt;
 
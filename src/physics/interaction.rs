@@ -0,0 +1,132 @@
+//! 状態の組み合わせごとの相互作用 (3x3 の行列)
+//!
+//! 衝突したドットの状態の組 (Solid/Liquid/Gas) ごとに、衝突のモデル、熱交換をするか、
+//! 凝集力を働かせるかを決める。行列は対称で、デバッグ用の GUI から編集でき、
+//! カレントディレクトリの設定ファイルに保存される。既定値は従来の挙動と同じ。
+
+use crate::material::State;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const CONFIG_FILE: &str = "interactions.json";
+const FORMAT_VERSION: u32 = 1;
+
+/// 重なった2つのドットの速度をどう変えるか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollisionModel {
+    Elastic,  // 密度と硬さを質量とした反発 (重い方が沈み、同じ状態なら広がる)
+    Rebound,  // 重くて粘い方は動かず、もう一方だけを跳ね返す。そうでなければ Elastic
+    Light,    // 密度だけを質量とした軽い反発
+    Displace, // エネルギーの高い状態 (同じなら軽い方) だけが押しのけられる
+    None,     // すり抜ける (押し戻しもしない)
+}
+
+impl CollisionModel {
+    pub const ALL: [CollisionModel; 5] = [
+        CollisionModel::Elastic,
+        CollisionModel::Rebound,
+        CollisionModel::Light,
+        CollisionModel::Displace,
+        CollisionModel::None,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CollisionModel::Elastic => "Elastic",
+            CollisionModel::Rebound => "Rebound",
+            CollisionModel::Light => "Light",
+            CollisionModel::Displace => "Displace",
+            CollisionModel::None => "Pass through",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub model: CollisionModel,
+    pub heat_transfer: bool,
+    pub cohesion: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InteractionMatrix {
+    pub version: u32,
+    pairs: [[Interaction; 3]; 3], // [状態1][状態2] (対称)
+}
+
+pub const STATES: [State; 3] = [State::Solid, State::Liquid, State::Gas];
+
+fn index(state: State) -> usize {
+    match state {
+        State::Solid => 0,
+        State::Liquid => 1,
+        State::Gas => 2,
+    }
+}
+
+impl Default for InteractionMatrix {
+    fn default() -> Self {
+        let pair = |model, heat_transfer, cohesion| Interaction { model, heat_transfer, cohesion };
+        let solid_solid = pair(CollisionModel::Elastic, true, true);
+        let liquid_liquid = pair(CollisionModel::Elastic, true, true);
+        let gas_gas = pair(CollisionModel::Light, true, false);
+        let solid_liquid = pair(CollisionModel::Rebound, true, false);
+        let with_gas = pair(CollisionModel::Displace, false, false);
+        Self {
+            version: FORMAT_VERSION,
+            pairs: [
+                [solid_solid, solid_liquid, with_gas],
+                [solid_liquid, liquid_liquid, with_gas],
+                [with_gas, with_gas, gas_gas],
+            ],
+        }
+    }
+}
+
+impl InteractionMatrix {
+    pub fn get(&self, a: State, b: State) -> Interaction {
+        self.pairs[index(a)][index(b)]
+    }
+
+    /// 組み合わせの設定を変える (逆の組み合わせも同じにする)
+    pub fn set(&mut self, a: State, b: State, interaction: Interaction) {
+        self.pairs[index(a)][index(b)] = interaction;
+        self.pairs[index(b)][index(a)] = interaction;
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let mut matrix: InteractionMatrix = serde_json::from_str(&json).map_err(std::io::Error::other)?;
+        if matrix.version > FORMAT_VERSION {
+            return Err(std::io::Error::other(format!("unsupported interaction matrix version {}", matrix.version)));
+        }
+        // 手で編集されて対称でなくなっていたら上三角を正とする
+        for a in 0..3 {
+            for b in a + 1..3 {
+                matrix.pairs[b][a] = matrix.pairs[a][b];
+            }
+        }
+        Ok(matrix)
+    }
+}
+
+/// カレントディレクトリの設定ファイルを読み込む (なければ既定値。読めなければ警告して既定値)
+pub fn load_config() -> InteractionMatrix {
+    let path = Path::new(CONFIG_FILE);
+    match InteractionMatrix::load(path) {
+        Ok(matrix) => {
+            tracing::info!(target: crate::logging::PHYSICS, path = %path.display(), "loaded interaction matrix");
+            matrix
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => InteractionMatrix::default(),
+        Err(error) => {
+            tracing::warn!(target: crate::logging::PHYSICS, path = %path.display(), "interaction matrix load failed: {}", error);
+            InteractionMatrix::default()
+        }
+    }
+}
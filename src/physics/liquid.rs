@@ -1,10 +1,9 @@
 ﻿use crate::{
     app::Dot,
 };
-use rand::thread_rng;
 use rand::Rng;
 
-use super::{DOT_RADIUS, HEIGHT, WIDTH}; // DOT_RADIUS, HEIGHT, WIDTH を親モジュールからインポート
+use super::{GameRng, DOT_RADIUS, HEIGHT, WIDTH}; // DOT_RADIUS, HEIGHT, WIDTH を親モジュールからインポート
 
 // State::Liquid に対する update_state 処理
 pub fn update_state_for_liquid(dot: &mut Dot, gravity: f64, dt: f64) {
@@ -17,8 +16,7 @@ pub fn update_state_for_liquid(dot: &mut Dot, gravity: f64, dt: f64) {
 }
 
 // State::Liquid に対する update_position 処理
-pub fn update_position_for_liquid(dot: &mut Dot, _dt: f64) {
-    let mut rng = thread_rng();
+pub fn update_position_for_liquid(dot: &mut Dot, _dt: f64, rng: &mut GameRng) {
     let elasticity = dot.material.elasticity as f64;
 
     // 境界との衝突処理
@@ -1,10 +1,23 @@
 pub mod collision_helpers;
+pub mod color_diffusion;
+pub mod combustion;
+pub mod convection;
+pub mod conveyor;
+pub mod drain;
+pub mod electricity;
 pub mod engine;
 pub mod gas;
+pub mod interaction;
 pub mod liquid;
+pub mod portal;
+pub mod radioactivity;
+pub mod rng;
+pub mod shatter;
 pub mod solid;
 pub mod state_manager;
+pub mod thermostat;
 
+pub use rng::GameRng;
 pub use engine::{Physics, DOT_RADIUS, COOL_DOWN_SECONDS, GAS_REFERENCE_DENSITY, GAS_DIFFUSION_FACTOR, HEIGHT, WIDTH};
 
 // 熱交換係数
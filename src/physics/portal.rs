@@ -0,0 +1,75 @@
+//! ポータル (対になった2つの円の間でドットを移す)
+//!
+//! 片方の円に外から入ったドットは、もう片方の円の同じ位置に速度を保ったまま現れる。
+//! 回転を設定すると、出口では位置と速度をその角度だけ回す (逆向きに通ると逆に回す)。
+//! 入ったかどうかはステップ開始時の位置 (prev_x, prev_y) が円の外だったかで判定するので、
+//! 出口の円の中に現れたドットがすぐに戻されることはない。
+
+use super::{HEIGHT, WIDTH};
+use crate::app::Dot;
+use serde::{Deserialize, Serialize};
+
+pub const MIN_RADIUS: f64 = 6.0;
+pub const MAX_RADIUS: f64 = 40.0;
+
+/// 次に置くポータルの形
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PortalSettings {
+    pub radius: f64,
+    pub rotation: f64, // 入口から出口へ通るときに回す角度 (度、時計回り)
+}
+
+impl Default for PortalSettings {
+    fn default() -> Self {
+        Self { radius: 12.0, rotation: 0.0 }
+    }
+}
+
+/// 入口 a と出口 b の組 (逆向きにも通れる)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Portal {
+    pub a: (f64, f64),
+    pub b: (f64, f64),
+    pub settings: PortalSettings,
+}
+
+impl Portal {
+    /// ドットが from の円に外から入ったら、to の円へ移して回した位置と速度を返す
+    fn pass(&self, dot: &Dot, from: (f64, f64), to: (f64, f64), degrees: f64) -> Option<((f64, f64), (f64, f64))> {
+        let radius_sq = self.settings.radius * self.settings.radius;
+        let inside = |x: f64, y: f64| (x - from.0).powi(2) + (y - from.1).powi(2) <= radius_sq;
+        if !inside(dot.x, dot.y) || inside(dot.prev_x, dot.prev_y) {
+            return None;
+        }
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let rotate = |(x, y): (f64, f64)| (x * cos - y * sin, x * sin + y * cos);
+        let (dx, dy) = rotate((dot.x - from.0, dot.y - from.1));
+        Some(((to.0 + dx, to.1 + dy), rotate((dot.vx, dot.vy))))
+    }
+}
+
+/// ポータルに入ったドットを対になる円へ移す (位置を更新した後に呼ぶ)
+pub fn teleport(portals: &[Portal], dots: &mut [Dot]) {
+    if portals.is_empty() {
+        return;
+    }
+    for dot in dots.iter_mut().filter(|dot| !dot.hidden) {
+        let exit = portals.iter().find_map(|portal| {
+            portal
+                .pass(dot, portal.a, portal.b, portal.settings.rotation)
+                .or_else(|| portal.pass(dot, portal.b, portal.a, -portal.settings.rotation))
+        });
+        let Some(((x, y), (vx, vy))) = exit else {
+            continue;
+        };
+        let margin = dot.radius();
+        dot.x = x.clamp(margin, WIDTH as f64 - margin);
+        dot.y = y.clamp(margin, HEIGHT as f64 - margin);
+        dot.vx = vx;
+        dot.vy = vy;
+        // 描画の補間で入口から出口まで線を引かないように、出口から始まったことにする
+        dot.prev_x = dot.x;
+        dot.prev_y = dot.y;
+        dot.wake();
+    }
+}
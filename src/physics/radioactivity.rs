@@ -0,0 +1,36 @@
+//! 放射性の物質の放射
+//!
+//! 放射性のドットは崩壊を待つ間も、放射能に比例した頻度でエネルギーを放ち、周りのドットの
+//! 温度を一瞬で上げる。崩壊する時刻は Dot::decay_in (半減期から決まり、update_state で進む) で
+//! 決まり、崩壊そのもの (自分の DNA から派生した物質への変化) は App::update_decay で行う。
+
+use super::{GameRng, Physics, DOT_RADIUS};
+use crate::app::Dot;
+use rand::Rng;
+
+const EMISSION_RATE: f64 = 2.0; // 放射能 1.0 のドットが1秒あたりに放射する回数
+const EMISSION_HEAT: f32 = 0.15; // 1回の放射で周りのドットが温まる量
+const EMISSION_RANGE: f64 = DOT_RADIUS * 5.0;
+
+/// 放射性のドットに dt 秒分の放射をさせる
+/// 空間グリッドを使うので、update_collision の直後 (ドットを増減させる前) に呼ぶこと
+pub fn emit(physics: &Physics, dots: &mut [Dot], dt: f64, rng: &mut GameRng) {
+    let emitters: Vec<usize> = (0..dots.len())
+        .filter(|&i| {
+            let radioactivity = dots[i].material.radioactivity as f64;
+            radioactivity > 0.0 && !dots[i].hidden && rng.gen::<f64>() < EMISSION_RATE * radioactivity * dt
+        })
+        .collect();
+
+    for &i in &emitters {
+        let (x, y) = (dots[i].x, dots[i].y);
+        let neighbors: Vec<usize> = physics.query_circle(dots, x, y, EMISSION_RANGE).filter(|&j| j != i).collect();
+        for j in neighbors {
+            let temperature = &mut dots[j].material.temperature;
+            *temperature = (*temperature + EMISSION_HEAT).min(1.0);
+        }
+    }
+    if !emitters.is_empty() {
+        tracing::trace!(target: crate::logging::PHYSICS, count = emitters.len(), "radioactive emissions");
+    }
+}
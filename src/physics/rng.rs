@@ -0,0 +1,58 @@
+//! 物理で使う乱数
+//!
+//! 通常はエントロピーから作るが、seed を指定すると決定論モードになり、同じ seed と
+//! 同じ入力なら同じシミュレーションになる。決定論モードではブレンド反応もワーカースレッドに
+//! 送らずに同じステップのうちに解決し、乱数を使う処理はすべて固定ステップごとに行う。
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+pub struct GameRng {
+    inner: StdRng,
+    seed: Option<u64>, // 決定論モードなら seed
+}
+
+impl GameRng {
+    pub fn from_entropy() -> Self {
+        Self {
+            inner: StdRng::from_entropy(),
+            seed: None,
+        }
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            inner: StdRng::seed_from_u64(seed),
+            seed: Some(seed),
+        }
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// 決定論モードなら乱数列を seed の最初に戻す (シーンを読み込み直したときなど)
+    pub fn rewind(&mut self) {
+        if let Some(seed) = self.seed {
+            self.inner = StdRng::seed_from_u64(seed);
+        }
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.inner.try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,98 @@
+//! 硬くてもろい固体の破砕
+//!
+//! 衝突や壁への激突で受けた衝撃を Dot::impact に記録しておき、硬さが高く弾性の低い固体が
+//! しきい値を超える衝撃を受けたら、DNA を受け継いだ小さな破片に砕いて飛び散らせる。
+//! 破砕の判定はフレームごと (裏のタブではステップごと) なので、衝撃は判定するまでの全ステップの
+//! 最大値をためておき、判定したら 0 に戻す。
+
+use crate::app::Dot;
+use crate::material::State;
+use super::GameRng;
+use rand::Rng;
+use std::collections::HashSet;
+use std::f64::consts::TAU;
+
+const SHATTER_IMPULSE: f64 = 250.0; // これを超える衝撃で砕ける
+const BRITTLE_HARDNESS: f32 = 0.7; // これ以上硬く
+const BRITTLE_ELASTICITY: f32 = 0.3; // これ以下の弾性の固体がもろい
+const FRAGMENT_COUNT: usize = 4;
+const FRAGMENT_SCALE: f64 = 0.5; // 4 個で元の面積とほぼ同じになる
+const MIN_SHATTER_SCALE: f64 = 0.5; // これより小さい破片はもう砕けない
+const FRAGMENT_SPEED: f64 = 60.0; // 破片が飛び散る速さ (平均)
+
+/// 衝突判定と同じ質量 (密度と硬さから決める)
+fn mass(dot: &Dot) -> f64 {
+    dot.material.density as f64 * (1.0 + dot.material.hardness as f64)
+}
+
+/// 衝突するドット同士の衝撃 (接近する速さ × 換算質量) を両方に記録する
+/// (nx, ny) は dot1 から dot2 への単位ベクトル
+pub fn record_impact(dot1: &mut Dot, dot2: &mut Dot, nx: f64, ny: f64) {
+    let closing_speed = (dot1.vx - dot2.vx) * nx + (dot1.vy - dot2.vy) * ny;
+    if closing_speed <= 0.0 {
+        return;
+    }
+    let (m1, m2) = (mass(dot1), mass(dot2));
+    if m1 + m2 <= 0.0 {
+        return;
+    }
+    let impulse = closing_speed * m1 * m2 / (m1 + m2);
+    dot1.impact = dot1.impact.max(impulse);
+    dot2.impact = dot2.impact.max(impulse);
+}
+
+/// 壁にぶつかったドットの衝撃を記録する (壁は動かないので質量は無限大として扱う)
+pub fn record_wall_impact(dot: &mut Dot, normal_speed: f64) {
+    dot.impact = dot.impact.max(normal_speed.abs() * mass(dot));
+}
+
+fn is_brittle(dot: &Dot) -> bool {
+    dot.material.state == State::Solid
+        && dot.material.hardness >= BRITTLE_HARDNESS
+        && dot.material.elasticity <= BRITTLE_ELASTICITY
+        && dot.scale >= MIN_SHATTER_SCALE
+}
+
+/// しきい値を超える衝撃を受けたもろいドットを破片に置き換え、ためた衝撃を 0 に戻す。砕けたドットの数を返す
+/// 破片の id は next_id から順に割り当てる。ドットの並びが変わるので、空間グリッドを使う処理の後に行う
+pub fn shatter(dots: &mut Vec<Dot>, next_id: &mut u64, rng: &mut GameRng) -> usize {
+    let mut shattered = HashSet::new();
+    let mut fragments = Vec::new();
+
+    for dot in dots.iter().filter(|dot| dot.impact > SHATTER_IMPULSE && is_brittle(dot)) {
+        shattered.insert(dot.id);
+        let offset = dot.radius() * 0.5;
+        let start_angle = rng.gen::<f64>() * TAU;
+        for k in 0..FRAGMENT_COUNT {
+            let angle = start_angle + k as f64 / FRAGMENT_COUNT as f64 * TAU;
+            let (dx, dy) = (angle.cos(), angle.sin());
+            let speed = FRAGMENT_SPEED * rng.gen_range(0.5..1.5);
+
+            let mut fragment = dot.clone();
+            fragment.id = *next_id;
+            *next_id += 1;
+            fragment.scale = dot.scale * FRAGMENT_SCALE;
+            fragment.impact = 0.0;
+            fragment.is_selected = false;
+            if k > 0 {
+                fragment.note = None; // メモは最初の破片だけが引き継ぐ
+            }
+            fragment.wake();
+            fragment.x += dx * offset;
+            fragment.y += dy * offset;
+            fragment.vx = dot.vx * 0.5 + dx * speed;
+            fragment.vy = dot.vy * 0.5 + dy * speed;
+            fragments.push(fragment);
+        }
+    }
+
+    for dot in dots.iter_mut() {
+        dot.impact = 0.0;
+    }
+    if !shattered.is_empty() {
+        tracing::debug!(target: crate::logging::PHYSICS, count = shattered.len(), "brittle dots shattered");
+        dots.retain(|dot| !shattered.contains(&dot.id));
+        dots.extend(fragments);
+    }
+    shattered.len()
+}
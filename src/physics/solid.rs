@@ -1,14 +1,14 @@
 use crate::{
     app::Dot,
 };
-use rand::thread_rng;
 use rand::Rng;
-use std::time::Instant;
 
 use super::{DOT_RADIUS, HEIGHT, WIDTH}; // DOT_RADIUS, HEIGHT, WIDTH を親モジュールからインポート
+use super::shatter::record_wall_impact;
+use super::GameRng;
 
 // State::Solid に対する update_state 処理
-pub fn update_state_for_solid(dot: &mut Dot, gravity: f64, dt: f64) {
+pub fn update_state_for_solid(dot: &mut Dot, gravity: f64, dt: f64, rng: &mut GameRng) {
     // State::Solid は重力の影響を受ける
     dot.vy += gravity * dt;
 
@@ -19,31 +19,31 @@ pub fn update_state_for_solid(dot: &mut Dot, gravity: f64, dt: f64) {
     // 低温時の状態変化 (plan.md L105-L111)
     // State::Solid -> State::Liquid は update_state 全体で処理
     // State::Solid -> 崩壊 (0.1%の確率)
-    let mut rng = thread_rng();
     if dot.material.temperature < -dot.material.heat_capacity_low {
         // クールダウンチェック
-        if dot.last_check_time.elapsed().as_secs_f64() > super::COOL_DOWN_SECONDS {
+        if dot.since_check > super::COOL_DOWN_SECONDS {
             if rng.gen::<f32>() < 0.001 {
                 // 0.1%の確率で崩壊 (この処理はengine.rs側でdots_to_removeに追加する)
                 // ここでは単に崩壊フラグを返すか、engine.rs側で処理する
                 // 一旦、何もしない。engine.rs側で処理。
             }
-            // 確率判定を行ったら時刻を更新
-            dot.last_check_time = Instant::now();
+            // 確率判定を行ったら経過時間をリセット
+            dot.since_check = 0.0;
         }
     }
 }
 
 // State::Solid に対する update_position 処理
-pub fn update_position_for_solid(dot: &mut Dot, _dt: f64) {
-    let mut rng = thread_rng();
+pub fn update_position_for_solid(dot: &mut Dot, _dt: f64, rng: &mut GameRng) {
     let elasticity = dot.material.elasticity as f64;
+    let radius = dot.radius();
 
     // 境界との衝突処理
-    if dot.y >= (HEIGHT as f64 - DOT_RADIUS) {
-        dot.y = HEIGHT as f64 - DOT_RADIUS;
+    if dot.y >= (HEIGHT as f64 - radius) {
+        dot.y = HEIGHT as f64 - radius;
 
         // Solids bounce with elasticity and stop when velocity is low
+        record_wall_impact(dot, dot.vy);
         dot.vy *= -elasticity;
         // 床との摩擦を適用
         let friction_factor = dot.material.viscosity as f64 * 0.7; // 係数を調整
@@ -55,36 +55,39 @@ pub fn update_position_for_solid(dot: &mut Dot, _dt: f64) {
         }
     }
 
-    if dot.y <= DOT_RADIUS {
-        dot.y = DOT_RADIUS;
+    if dot.y <= radius {
+        dot.y = radius;
 
         // Apply viscosity effect when hitting top boundary too
         if dot.material.viscosity < 0.6 {
             let spread_factor = (1.0 - dot.material.viscosity as f64) * 0.3;
             dot.vx += (rng.gen::<f64>() - 0.5) * spread_factor * 0.3; // Very limited horizontal variability
         }
+        record_wall_impact(dot, dot.vy);
         dot.vy *= -elasticity;
     }
 
-    if dot.x >= (WIDTH as f64 - DOT_RADIUS) {
-        dot.x = WIDTH as f64 - DOT_RADIUS;
+    if dot.x >= (WIDTH as f64 - radius) {
+        dot.x = WIDTH as f64 - radius;
 
         // Apply viscosity effect when hitting side walls too
         if dot.material.viscosity < 0.6 {
             let spread_factor = (1.0 - dot.material.viscosity as f64) * 0.3;
             dot.vy += (rng.gen::<f64>() - 0.5) * spread_factor * 0.3; // Very limited vertical variability
         }
+        record_wall_impact(dot, dot.vx);
         dot.vx *= -elasticity;
     }
 
-    if dot.x <= DOT_RADIUS {
-        dot.x = DOT_RADIUS;
+    if dot.x <= radius {
+        dot.x = radius;
 
         // Apply viscosity effect when hitting side walls too
         if dot.material.viscosity < 0.6 {
             let spread_factor = (1.0 - dot.material.viscosity as f64) * 0.3;
             dot.vy += (rng.gen::<f64>() - 0.5) * spread_factor * 0.3; // Very limited vertical variability
         }
+        record_wall_impact(dot, dot.vx);
         dot.vx *= -elasticity;
     }
 
@@ -1,29 +1,27 @@
 use crate::app::Dot;
 use crate::material::State;
-use std::time::Instant;
-use rand::thread_rng;
 use rand::Rng;
 
 // physics/engine.rs で定義されている定数をインポート
-use super::{DOT_RADIUS, HEIGHT, COOL_DOWN_SECONDS};
+use super::{GameRng, DOT_RADIUS, HEIGHT, COOL_DOWN_SECONDS};
 
 // 各状態モジュールをインポート
 use super::{solid, liquid, gas};
 
 // State に応じた update_state 処理を呼び分ける
-pub fn update_state_for_dot(dot: &mut Dot, gravity: f64, dt: f64) {
+pub fn update_state_for_dot(dot: &mut Dot, gravity: f64, dt: f64, rng: &mut GameRng) {
     match dot.material.state {
-        State::Solid => solid::update_state_for_solid(dot, gravity, dt),
+        State::Solid => solid::update_state_for_solid(dot, gravity, dt, rng),
         State::Liquid => liquid::update_state_for_liquid(dot, gravity, dt),
-        State::Gas => gas::update_state_for_gas(dot, gravity, dt),
+        State::Gas => gas::update_state_for_gas(dot, gravity, dt, rng),
     }
 }
 
 // State に応じた update_position 処理を呼び分ける
-pub fn update_position_for_dot(dot: &mut Dot, dt: f64) {
+pub fn update_position_for_dot(dot: &mut Dot, dt: f64, rng: &mut GameRng) {
     match dot.material.state {
-        State::Solid => solid::update_position_for_solid(dot, dt),
-        State::Liquid => liquid::update_position_for_liquid(dot, dt),
+        State::Solid => solid::update_position_for_solid(dot, dt, rng),
+        State::Liquid => liquid::update_position_for_liquid(dot, dt, rng),
         State::Gas => gas::update_position_for_gas(dot, dt),
     }
 }
@@ -58,7 +56,7 @@ pub fn handle_collision_between_states(dot1: &mut Dot, dot2: &mut Dot, nx: f64,
 
             // --- 広がりと圧力の処理 ---
             if avg_viscosity < 0.8 && avg_hardness < 0.5 {
-                let spread_factor = (1.0 - avg_viscosity as f64) * (1.0 - avg_hardness as f64) * 0.01;
+                let spread_factor = (1.0 - avg_viscosity as f64) * (1.0 - avg_hardness) * 0.01;
                 if ny.abs() > 0.8 {
                     let spread_force = spread_factor * dt;
                     if dot1.x < dot2.x {
@@ -165,19 +163,18 @@ pub fn handle_collision_between_states(dot1: &mut Dot, dot2: &mut Dot, nx: f64,
 // これは update_state_for_dot から呼び出すか、state_manager.rs で共通処理として定義する
 // 今回は共通処理として定義し、update_state_for_dot から呼び出す
 #[allow(dead_code)]
-pub fn handle_cool_down_for_solid(dot: &mut Dot) {
-    let mut rng = thread_rng();
+pub fn handle_cool_down_for_solid(dot: &mut Dot, rng: &mut GameRng) {
     // State::Solid のみが崩壊の対象
     if dot.material.state == State::Solid && dot.material.temperature < -dot.material.heat_capacity_low {
         // クールダウンチェック
-        if dot.last_check_time.elapsed().as_secs_f64() > COOL_DOWN_SECONDS {
+        if dot.since_check > COOL_DOWN_SECONDS {
             if rng.gen::<f32>() < 0.001 {
                 // 0.1%の確率で崩壊 (engine.rs側でdots_to_removeに追加する)
                 // ここでは単に崩壊フラグを返すか、engine.rs側で処理する
                 // 一旦、何もしない。engine.rs側で処理。
             }
-            // 確率判定を行ったら時刻を更新
-            dot.last_check_time = Instant::now();
+            // 確率判定を行ったら経過時間をリセット
+            dot.since_check = 0.0;
         }
     }
 }
\ No newline at end of file
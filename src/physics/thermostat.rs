@@ -0,0 +1,53 @@
+//! 壁のサーモスタット
+//!
+//! 有効にした壁 (床・天井・左右) は設定した温度を保ち、触れているドットの温度を熱伝導率に
+//! 応じてその温度へ近づける。壁は熱を無限に出し入れできるので、床を熱く天井を冷たくすれば
+//! 対流が止まらずに続き、全体を冷やせば凍らせる実験ができる。
+
+use super::{HEIGHT, WIDTH};
+use crate::app::Dot;
+use serde::{Deserialize, Serialize};
+
+const WALL_HEAT_RATE: f32 = 2.0; // 熱伝導率 1.0 のドットが1秒で差を縮める速さ
+const TOUCH_MARGIN: f64 = 1.0; // 半径にこれを足した距離まで近づいていれば触れているとみなす
+
+/// 壁ごとの温度 (None の壁は断熱)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct WallThermostat {
+    pub floor: Option<f32>,
+    pub ceiling: Option<f32>,
+    pub sides: Option<f32>, // 左右の壁
+}
+
+impl WallThermostat {
+    pub fn is_active(&self) -> bool {
+        self.floor.is_some() || self.ceiling.is_some() || self.sides.is_some()
+    }
+
+    /// 壁に触れているドットの温度を壁の温度へ近づける (角では触れている壁の平均)
+    pub fn apply(&self, dots: &mut [Dot], dt: f64) {
+        if !self.is_active() {
+            return;
+        }
+        for dot in dots.iter_mut() {
+            let reach = dot.radius() + TOUCH_MARGIN;
+            let touching = [
+                (self.floor, dot.y >= HEIGHT as f64 - reach),
+                (self.ceiling, dot.y <= reach),
+                (self.sides, dot.x <= reach || dot.x >= WIDTH as f64 - reach),
+            ];
+            let (sum, count) = touching
+                .iter()
+                .filter_map(|&(temperature, touches)| temperature.filter(|_| touches))
+                .fold((0.0, 0), |(sum, count), temperature| (sum + temperature, count + 1));
+            if count == 0 {
+                continue;
+            }
+
+            let target = sum / count as f32;
+            let amount = 1.0 - (-WALL_HEAT_RATE * dot.material.heat_conductivity * dt as f32).exp();
+            let material = &mut dot.material;
+            material.temperature = (material.temperature + (target - material.temperature) * amount).clamp(-1.0, 1.0);
+        }
+    }
+}
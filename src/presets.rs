@@ -0,0 +1,135 @@
+//! バイナリに同梱するサンドボックスのプリセット (Gallery)
+//!
+//! presets/*.json は「領域をどの物質で埋めるか」の並びで、読み込むと
+//! 各領域に一定間隔でドットを配置する。物質は seed から生成した上で、
+//! 指定された特性だけを上書きする。
+
+use crate::commands::{Command, CommandRegistry};
+use crate::material::{from_seed, BaseMaterialParams, State};
+use serde::Deserialize;
+
+const BUILTIN: [&str; 4] = [
+    include_str!("../presets/volcano.json"),
+    include_str!("../presets/rainstorm_over_oil.json"),
+    include_str!("../presets/gas_chamber.json"),
+    include_str!("../presets/glass_furnace.json"),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub description: String,
+    pub fills: Vec<Fill>,
+}
+
+/// 領域の形 (ワールド座標)
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shape {
+    Rect { x: f64, y: f64, width: f64, height: f64 },
+    Circle { x: f64, y: f64, radius: f64 },
+}
+
+impl Shape {
+    fn contains(&self, px: f64, py: f64) -> bool {
+        match *self {
+            Shape::Rect { x, y, width, height } => px >= x && px <= x + width && py >= y && py <= y + height,
+            Shape::Circle { x, y, radius } => (px - x).powi(2) + (py - y).powi(2) <= radius * radius,
+        }
+    }
+
+    /// 外接矩形 (min_x, min_y, max_x, max_y)
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+        match *self {
+            Shape::Rect { x, y, width, height } => (x, y, x + width, y + height),
+            Shape::Circle { x, y, radius } => (x - radius, y - radius, x + radius, y + radius),
+        }
+    }
+}
+
+/// 1つの領域を同じ物質のドットで埋める
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fill {
+    pub shape: Shape,
+    pub spacing: f64, // ドットの間隔
+    pub seed: u64,
+    #[serde(default)]
+    pub material: MaterialOverrides,
+}
+
+impl Fill {
+    pub fn material(&self) -> BaseMaterialParams {
+        self.material.apply(from_seed(self.seed))
+    }
+
+    /// 領域内のドットの配置位置
+    pub fn positions(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        let (min_x, min_y, max_x, max_y) = self.shape.bounds();
+        let spacing = self.spacing.max(1.0);
+        let cols = ((max_x - min_x) / spacing).floor() as usize + 1;
+        let rows = ((max_y - min_y) / spacing).floor() as usize + 1;
+        (0..rows)
+            .flat_map(move |row| (0..cols).map(move |col| (min_x + col as f64 * spacing, min_y + row as f64 * spacing)))
+            .filter(move |&(x, y)| self.shape.contains(x, y))
+    }
+}
+
+/// seed から生成した物質に上書きする特性 (指定したものだけ)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MaterialOverrides {
+    pub state: Option<State>,
+    pub density: Option<f32>,
+    pub viscosity: Option<f32>,
+    pub hardness: Option<f32>,
+    pub temperature: Option<f32>,
+    pub heat_capacity_high: Option<f32>,
+    pub heat_capacity_low: Option<f32>,
+    pub color_hue: Option<f32>,
+    pub color_saturation: Option<f32>,
+    pub color_luminance: Option<f32>,
+    pub luminescence: Option<f32>,
+    pub entropy_bias: Option<f32>,
+    pub volatility: Option<f32>,
+}
+
+impl MaterialOverrides {
+    pub fn apply(&self, mut material: BaseMaterialParams) -> BaseMaterialParams {
+        if let Some(state) = self.state {
+            material.state = state;
+        }
+        let overrides = [
+            (self.density, &mut material.density),
+            (self.viscosity, &mut material.viscosity),
+            (self.hardness, &mut material.hardness),
+            (self.temperature, &mut material.temperature),
+            (self.heat_capacity_high, &mut material.heat_capacity_high),
+            (self.heat_capacity_low, &mut material.heat_capacity_low),
+            (self.color_hue, &mut material.color_hue),
+            (self.color_saturation, &mut material.color_saturation),
+            (self.color_luminance, &mut material.color_luminance),
+            (self.luminescence, &mut material.luminescence),
+            (self.entropy_bias, &mut material.entropy_bias),
+            (self.volatility, &mut material.volatility),
+        ];
+        for (value, field) in overrides {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        material
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry, presets: &[Preset]) {
+    for (index, preset) in presets.iter().enumerate() {
+        registry.register("Scenario", format!("Load {}", preset.name), Command::LoadPreset(index));
+    }
+}
+
+/// 同梱のプリセットを読み込む
+pub fn builtin() -> Vec<Preset> {
+    BUILTIN
+        .iter()
+        .map(|source| serde_json::from_str(source).expect("built-in preset must be valid JSON"))
+        .collect()
+}
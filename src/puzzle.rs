@@ -0,0 +1,110 @@
+//! 錬金術パズル (目標の物質を作るモード)
+//!
+//! ランダムな2つの物質をブレンドした DNA を目標にし、画面上のドットの中で
+//! 目標に最も近いものを毎フレーム評価する。すべての特性が許容範囲に入ればクリア。
+
+use crate::app::Dot;
+use crate::material::{from_dna, from_seed, to_dna, BaseMaterialParams, MaterialDNA};
+use rand::{thread_rng, Rng};
+
+/// 特性ごとの許容誤差 (遺伝子の値 0.0 ~ 1.0 での差)
+const TOLERANCE: f32 = 0.12;
+
+/// 評価する特性 (表示名, 遺伝子のインデックス)。色相だけは環状に比較する
+const TARGET_GENES: [(&str, usize); 6] = [
+    ("Density", 1),
+    ("Viscosity", 2),
+    ("Hardness", 3),
+    ("Color Hue", 9),
+    ("Color Saturation", 10),
+    ("Luminescence", 12),
+];
+const HUE_GENE: usize = 9;
+
+pub struct Puzzle {
+    pub target: MaterialDNA,
+    pub target_material: BaseMaterialParams,
+    pub target_name: String,
+    pub best_similarity: f32,      // 0.0 ~ 1.0
+    pub best_name: Option<String>, // 最も近いドットの物質名
+    pub completed_at: Option<std::time::Instant>,
+}
+
+impl Puzzle {
+    /// ランダムな2つの物質のブレンドを目標にする (ブレンドで到達できる物質になる)
+    pub fn random() -> Self {
+        let mut rng = thread_rng();
+        let (seed_a, seed_b) = (rng.gen(), rng.gen());
+        let dna_a = to_dna(&from_seed(seed_a), seed_a);
+        let dna_b = to_dna(&from_seed(seed_b), seed_b);
+        let target = dna_a.blend(&dna_b, rng.gen_range(0.3..0.7));
+
+        Self {
+            target_material: from_dna(&target),
+            target_name: crate::naming::generate_name(&target),
+            target,
+            best_similarity: 0.0,
+            best_name: None,
+            completed_at: None,
+        }
+    }
+
+    /// 特性ごとの許容範囲 (表示名, 下限, 上限)
+    pub fn ranges(&self) -> Vec<(&'static str, f32, f32)> {
+        TARGET_GENES
+            .iter()
+            .map(|&(label, gene)| {
+                let value = self.target.genes[gene];
+                (label, (value - TOLERANCE).max(0.0), (value + TOLERANCE).min(1.0))
+            })
+            .collect()
+    }
+
+    /// 状態が一致し、すべての特性が許容範囲内か
+    fn is_match(&self, dna: &MaterialDNA) -> bool {
+        from_dna(dna).state == self.target_material.state
+            && TARGET_GENES
+                .iter()
+                .all(|&(_, gene)| gene_distance(gene, dna.genes[gene], self.target.genes[gene]) <= TOLERANCE)
+    }
+
+    /// 目標との類似度 (1.0 で完全一致、状態が違えば半分)
+    fn similarity(&self, dna: &MaterialDNA) -> f32 {
+        let mean_distance = TARGET_GENES
+            .iter()
+            .map(|&(_, gene)| gene_distance(gene, dna.genes[gene], self.target.genes[gene]))
+            .sum::<f32>()
+            / TARGET_GENES.len() as f32;
+        let state_factor = if from_dna(dna).state == self.target_material.state { 1.0 } else { 0.5 };
+        (1.0 - mean_distance) * state_factor
+    }
+
+    /// 画面上のドットを評価する (クリア後は何もしない)
+    pub fn evaluate(&mut self, dots: &[Dot]) {
+        if self.completed_at.is_some() {
+            return;
+        }
+        for dot in dots {
+            let similarity = self.similarity(&dot.material_dna);
+            if similarity > self.best_similarity {
+                self.best_similarity = similarity;
+                self.best_name = Some(dot.name.clone());
+            }
+            if self.is_match(&dot.material_dna) {
+                self.best_similarity = self.best_similarity.max(similarity);
+                self.best_name = Some(dot.name.clone());
+                self.completed_at = Some(std::time::Instant::now());
+                return;
+            }
+        }
+    }
+}
+
+fn gene_distance(gene: usize, a: f32, b: f32) -> f32 {
+    let diff = (a - b).abs();
+    if gene == HUE_GENE {
+        diff.min(1.0 - diff)
+    } else {
+        diff
+    }
+}
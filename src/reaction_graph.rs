@@ -0,0 +1,151 @@
+//! ブレンド反応の記録 (反応ネットワーク)
+//!
+//! 物質 (DNA の seed) をノード、ブレンドを 親2つ → 子 の辺として記録し、
+//! Graphviz の DOT 形式または JSON で書き出せるようにする。
+
+use crate::commands::{Command, CommandRegistry};
+use crate::material::{from_dna, MaterialDNA, ReactionType, State};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 書き出し形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Dot,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Dot => "dot",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Reactions", "Export graph as DOT", Command::ExportReactions(ExportFormat::Dot));
+    registry.register("Reactions", "Export graph as JSON", Command::ExportReactions(ExportFormat::Json));
+}
+
+/// 反応ネットワーク上の物質
+#[derive(Debug, Clone, Serialize)]
+pub struct MaterialNode {
+    pub seed: u64,
+    pub name: String,
+    pub state: State,
+    pub color: (u8, u8, u8),
+    pub dna: MaterialDNA,
+}
+
+/// 1種類の反応 (同じ親と子の組み合わせは count にまとめる)
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactionEdge {
+    pub parents: [u64; 2],
+    pub child: u64,
+    pub reaction: ReactionType,
+    pub count: u32,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReactionGraph {
+    pub nodes: Vec<MaterialNode>,
+    pub edges: Vec<ReactionEdge>,
+    #[serde(skip)]
+    node_index: HashMap<u64, usize>, // seed -> nodes のインデックス
+    #[serde(skip)]
+    edge_index: HashMap<([u64; 2], u64, ReactionType), usize>,
+}
+
+impl ReactionGraph {
+    /// ブレンド1回分を記録する
+    pub fn record(&mut self, parents: [&MaterialDNA; 2], child: &MaterialDNA, reaction: ReactionType) {
+        for dna in parents.into_iter().chain(std::iter::once(child)) {
+            self.add_node(dna);
+        }
+
+        let parents = [parents[0].seed, parents[1].seed];
+        let key = (parents, child.seed, reaction);
+        match self.edge_index.get(&key) {
+            Some(&index) => self.edges[index].count += 1,
+            None => {
+                self.edge_index.insert(key, self.edges.len());
+                self.edges.push(ReactionEdge {
+                    parents,
+                    child: child.seed,
+                    reaction,
+                    count: 1,
+                });
+            }
+        }
+    }
+
+    fn add_node(&mut self, dna: &MaterialDNA) {
+        self.add_material(dna, crate::naming::generate_name(dna));
+    }
+
+    /// 物質だけを (反応なしで) 追加する。同じ seed の物質が既にあれば追加せず false を返す
+    pub fn add_material(&mut self, dna: &MaterialDNA, name: String) -> bool {
+        if self.node_index.contains_key(&dna.seed) {
+            return false;
+        }
+        let material = from_dna(dna);
+        self.node_index.insert(dna.seed, self.nodes.len());
+        self.nodes.push(MaterialNode {
+            seed: dna.seed,
+            name,
+            state: material.state,
+            color: material.get_color_rgb(),
+            dna: dna.clone(),
+        });
+        true
+    }
+
+    /// 命名スタイルの変更後に、すべての物質の名前を付け直す
+    pub fn rename_all(&mut self) {
+        for node in &mut self.nodes {
+            node.name = crate::naming::generate_name(&node.dna);
+        }
+    }
+
+    /// Graphviz の DOT 形式に変換する (ノードは物質の色で塗る)
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph reactions {\n    node [shape=box, style=filled];\n");
+        for node in &self.nodes {
+            let (r, g, b) = node.color;
+            out.push_str(&format!(
+                "    m{} [label=\"{}\\n{:?}\", fillcolor=\"#{:02x}{:02x}{:02x}\"];\n",
+                node.seed,
+                node.name.replace('"', "\\\""),
+                node.state,
+                r,
+                g,
+                b
+            ));
+        }
+        for edge in &self.edges {
+            for parent in edge.parents {
+                out.push_str(&format!(
+                    "    m{} -> m{} [label=\"{:?} x{}\"];\n",
+                    parent, edge.child, edge.reaction, edge.count
+                ));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn export(&self, path: &Path, format: ExportFormat) -> std::io::Result<()> {
+        let contents = match format {
+            ExportFormat::Dot => self.to_dot(),
+            ExportFormat::Json => self.to_json().map_err(std::io::Error::other)?,
+        };
+        std::fs::write(path, contents)
+    }
+}
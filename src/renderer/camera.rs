@@ -0,0 +1,62 @@
+use crate::app::{HEIGHT, WIDTH};
+
+const PAN_SPEED: f64 = 400.0; // 最大パン速度 (画面px/秒)
+const PAN_SMOOTHING: f64 = 10.0; // 速度の追従の速さ (大きいほど機敏)
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 8.0;
+
+/// ドット描画用の2Dカメラ
+/// center はワールド座標系での画面中心、zoom は拡大率 (1.0で等倍)
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub center: (f64, f64),
+    pub zoom: f64,
+    velocity: (f64, f64),
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            center: (WIDTH as f64 / 2.0, HEIGHT as f64 / 2.0),
+            zoom: 1.0,
+            velocity: (0.0, 0.0),
+        }
+    }
+}
+
+impl Camera {
+    /// 入力方向 (-1.0 ~ 1.0) に向けて滑らかにパンする
+    pub fn update(&mut self, direction: (f64, f64), dt: f64) {
+        let target = (direction.0 * PAN_SPEED, direction.1 * PAN_SPEED);
+        let t = (PAN_SMOOTHING * dt).min(1.0);
+        self.velocity.0 += (target.0 - self.velocity.0) * t;
+        self.velocity.1 += (target.1 - self.velocity.1) * t;
+
+        // 拡大時も画面上の速度が一定になるようにズームで割る
+        self.center.0 += self.velocity.0 / self.zoom * dt;
+        self.center.1 += self.velocity.1 / self.zoom * dt;
+    }
+
+    /// 画面座標の一点を固定したままズームする
+    pub fn zoom_at(&mut self, screen: (f64, f64), factor: f64) {
+        let anchor = self.screen_to_world(screen);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let moved = self.screen_to_world(screen);
+        self.center.0 += anchor.0 - moved.0;
+        self.center.1 += anchor.1 - moved.1;
+    }
+
+    pub fn world_to_screen(&self, world: (f64, f64)) -> (f64, f64) {
+        (
+            (world.0 - self.center.0) * self.zoom + WIDTH as f64 / 2.0,
+            (world.1 - self.center.1) * self.zoom + HEIGHT as f64 / 2.0,
+        )
+    }
+
+    pub fn screen_to_world(&self, screen: (f64, f64)) -> (f64, f64) {
+        (
+            self.center.0 + (screen.0 - WIDTH as f64 / 2.0) / self.zoom,
+            self.center.1 + (screen.1 - HEIGHT as f64 / 2.0) / self.zoom,
+        )
+    }
+}
@@ -0,0 +1,174 @@
+//! デモ録画用のカメラパス
+//!
+//! カメラの位置とズームをキーフレームとして時刻付きで並べ、再生中はその間を
+//! 滑らかに補間してカメラを動かす。再生はフォトモード (HUD 非表示) で行うので、
+//! 画面を録画すれば大きなシミュレーションのフライスルー映像になる。
+
+use super::camera::Camera;
+use crate::commands::{Command, CommandRegistry};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const PATH_FILE: &str = "camera_path.json";
+const KEYFRAME_INTERVAL: f64 = 2.0; // 追加したキーフレームを前のものから何秒後に置くか
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f64, // パスの先頭からの秒数
+    pub center: (f64, f64),
+    pub zoom: f64,
+}
+
+/// GUI やコマンドから要求されるカメラパスの操作
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraPathAction {
+    AddKeyframe, // 現在のカメラを最後のキーフレームの後に追加する
+    RemoveKeyframe(usize),
+    SetKeyframeTime(usize, f64),
+    Play,
+    Stop,
+    SetLooping(bool),
+    Clear,
+    Save,
+    Load,
+}
+
+#[derive(Debug, Default)]
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>, // 時刻順
+    playhead: Option<f64>,    // 再生中なら先頭からの経過秒数
+    pub looping: bool,
+}
+
+impl CameraPath {
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn playhead(&self) -> Option<f64> {
+        self.playhead
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playhead.is_some()
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    pub fn add_keyframe(&mut self, camera: &Camera) {
+        let time = self.keyframes.last().map_or(0.0, |keyframe| keyframe.time + KEYFRAME_INTERVAL);
+        self.keyframes.push(Keyframe { time, center: camera.center, zoom: camera.zoom });
+    }
+
+    pub fn remove_keyframe(&mut self, index: usize) {
+        if index < self.keyframes.len() {
+            self.keyframes.remove(index);
+        }
+    }
+
+    /// キーフレームの時刻を変更し、時刻順に並べ直す
+    pub fn set_keyframe_time(&mut self, index: usize, time: f64) {
+        if let Some(keyframe) = self.keyframes.get_mut(index) {
+            keyframe.time = time.max(0.0);
+            self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+        self.playhead = None;
+    }
+
+    /// 先頭から再生する。キーフレームが2つ未満なら何もしない
+    pub fn play(&mut self) -> bool {
+        if self.keyframes.len() < 2 {
+            return false;
+        }
+        self.playhead = Some(0.0);
+        true
+    }
+
+    pub fn stop(&mut self) {
+        self.playhead = None;
+    }
+
+    /// 再生中なら dt 秒進めてカメラを動かす。最後まで再生したら (ループしない場合) 止まる
+    pub fn advance(&mut self, camera: &mut Camera, dt: f64) {
+        let duration = self.duration();
+        let Some(playhead) = &mut self.playhead else {
+            return;
+        };
+        *playhead += dt;
+        if *playhead > duration {
+            if self.looping && duration > 0.0 {
+                *playhead %= duration;
+            } else {
+                self.playhead = None;
+            }
+        }
+        if let Some((center, zoom)) = self.sample(self.playhead.unwrap_or(duration)) {
+            camera.center = center;
+            camera.zoom = zoom;
+        }
+    }
+
+    /// 時刻 t のカメラの中心とズーム
+    /// 中心は Catmull-Rom 曲線で、ズームは対数空間で補間する (拡大・縮小の速さが一定に見える)
+    pub fn sample(&self, t: f64) -> Option<((f64, f64), f64)> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if t <= first.time {
+            return Some((first.center, first.zoom));
+        }
+        if t >= last.time {
+            return Some((last.center, last.zoom));
+        }
+
+        let i = self.keyframes.iter().rposition(|keyframe| keyframe.time <= t)?;
+        let (k1, k2) = (&self.keyframes[i], &self.keyframes[i + 1]);
+        let k0 = &self.keyframes[i.saturating_sub(1)];
+        let k3 = &self.keyframes[(i + 2).min(self.keyframes.len() - 1)];
+        let span = k2.time - k1.time;
+        let u = if span > 0.0 { (t - k1.time) / span } else { 1.0 };
+        let u = u * u * (3.0 - 2.0 * u); // キーフレームで止まるように緩急を付ける
+
+        let center = (
+            catmull_rom(k0.center.0, k1.center.0, k2.center.0, k3.center.0, u),
+            catmull_rom(k0.center.1, k1.center.1, k2.center.1, k3.center.1, u),
+        );
+        let zoom = (k1.zoom.ln() + (k2.zoom.ln() - k1.zoom.ln()) * u).exp();
+        Some((center, zoom))
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.keyframes).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(&mut self, path: &Path) -> std::io::Result<()> {
+        let json = std::fs::read_to_string(path)?;
+        let mut keyframes: Vec<Keyframe> = serde_json::from_str(&json).map_err(std::io::Error::other)?;
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.keyframes = keyframes;
+        self.playhead = None;
+        Ok(())
+    }
+}
+
+fn catmull_rom(p0: f64, p1: f64, p2: f64, p3: f64, t: f64) -> f64 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Camera path", "Add keyframe", Command::CameraPath(CameraPathAction::AddKeyframe));
+    registry.register("Camera path", "Play", Command::CameraPath(CameraPathAction::Play));
+    registry.register("Camera path", "Stop", Command::CameraPath(CameraPathAction::Stop));
+    registry.register("Camera path", "Clear", Command::CameraPath(CameraPathAction::Clear));
+    registry.register("Camera path", "Save", Command::CameraPath(CameraPathAction::Save));
+    registry.register("Camera path", "Load", Command::CameraPath(CameraPathAction::Load));
+    registry.register("Window", "Toggle camera path timeline", Command::ToggleCameraPath);
+}
@@ -0,0 +1,81 @@
+//! コマンドパレット (Ctrl+P)
+//!
+//! 入力欄であいまい検索し、↑↓ で選択、Enter で実行、Esc で閉じる。
+
+use crate::commands::{Command, CommandRegistry};
+
+const MAX_VISIBLE: usize = 12;
+
+#[derive(Default)]
+pub struct CommandPalette {
+    open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    /// Ctrl+P で開閉し、表示中なら選ばれたコマンドを返す
+    pub fn show(&mut self, ctx: &egui::Context, registry: &CommandRegistry) -> Option<Command> {
+        if ctx.input_mut(|input| input.consume_key(egui::Modifiers::COMMAND, egui::Key::P)) {
+            self.open = !self.open;
+            self.query.clear();
+            self.selected = 0;
+        }
+        if !self.open {
+            return None;
+        }
+
+        let matches = registry.search(&self.query);
+        let (up, down, enter, escape) = ctx.input_mut(|input| {
+            (
+                input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                input.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                input.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        let visible = matches.len().min(MAX_VISIBLE);
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down && self.selected + 1 < visible {
+            self.selected += 1;
+        }
+        self.selected = self.selected.min(visible.saturating_sub(1));
+
+        let mut chosen = enter.then(|| matches.get(self.selected).map(|entry| entry.command.clone())).flatten();
+
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+            .fixed_size([320.0, 0.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command...")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.selected = 0;
+                }
+
+                for (index, entry) in matches.iter().take(MAX_VISIBLE).enumerate() {
+                    let label = format!("{}: {}", entry.category, entry.label);
+                    if ui.selectable_label(index == self.selected, label).clicked() {
+                        chosen = Some(entry.command.clone());
+                    }
+                }
+                if matches.is_empty() {
+                    ui.weak("No matching commands");
+                }
+            });
+
+        if chosen.is_some() || escape {
+            self.open = false;
+        }
+        chosen
+    }
+}
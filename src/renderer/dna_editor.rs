@@ -0,0 +1,115 @@
+//! 物質の DNA を手で編集するパネル
+//!
+//! ブラシの物質の DNA を読み込み、すべての遺伝子をスライダーで変更できる。色のプレビューと
+//! 変換後の状態・名前を表示し、編集した DNA はブラシの物質にするか、選択中のドットに書き戻す。
+//! 遺伝子を変えると新しい seed を振るので、元の物質とは別の物質として反応する。
+
+use crate::material::{from_dna, MaterialDNA, GENE_COUNT};
+
+/// 遺伝子の見出し (並びは from_dna / to_dna と同じ)
+const GENE_LABELS: [&str; GENE_COUNT] = [
+    "State",
+    "Density",
+    "Viscosity",
+    "Hardness",
+    "Elasticity",
+    "Temperature",
+    "Heat Conductivity",
+    "Heat Capacity High",
+    "Heat Capacity Low",
+    "Color Hue",
+    "Color Saturation",
+    "Color Luminance",
+    "Luminescence",
+    "Entropy Bias",
+    "Volatility",
+    "Cohesion",
+    "Thermal Expansion",
+    "Melting Point",
+    "Boiling Point",
+    "Flammability",
+    "Conductivity",
+    "Magnetism",
+    "Charge",
+    "Radioactivity",
+];
+
+/// 編集した DNA の使い道 (App 側で反映する)
+pub enum DnaEdit {
+    SetBrush(MaterialDNA),
+    WriteToSelected(u64, MaterialDNA), // ドットの ID
+}
+
+#[derive(Default)]
+pub struct DnaEditorView {
+    pub open: bool,
+    draft: Option<MaterialDNA>, // None ならブラシの物質から読み込む
+}
+
+impl DnaEditorView {
+    pub fn show(&mut self, ctx: &egui::Context, brush: &MaterialDNA, selected_dot: Option<u64>) -> Option<DnaEdit> {
+        if !self.open {
+            return None;
+        }
+        let mut edit = None;
+        let mut reload = false;
+        let mut open = self.open;
+        egui::Window::new("DNA Editor")
+            .open(&mut open)
+            .default_pos(egui::pos2(420.0, 60.0))
+            .resizable(false)
+            .show(ctx, |ui| {
+                let draft = self.draft.get_or_insert_with(|| brush.clone());
+
+                // プレビュー (色・状態・名前)
+                let params = from_dna(draft);
+                ui.horizontal(|ui| {
+                    let (r, g, b) = params.get_color_rgb();
+                    let (swatch, _) = ui.allocate_exact_size(egui::vec2(32.0, 32.0), egui::Sense::hover());
+                    ui.painter().rect_filled(swatch, 4.0, egui::Color32::from_rgb(r, g, b));
+                    ui.vertical(|ui| {
+                        ui.strong(crate::naming::generate_name(draft));
+                        ui.weak(format!("{:?}  RGB({}, {}, {})  seed {}", params.state, r, g, b, draft.seed));
+                    });
+                });
+                ui.separator();
+
+                let mut changed = false;
+                egui::Grid::new("dna_editor_grid").num_columns(2).show(ui, |ui| {
+                    for (label, gene) in GENE_LABELS.iter().zip(draft.genes.iter_mut()) {
+                        ui.label(*label);
+                        changed |= ui.add(egui::Slider::new(gene, 0.0..=1.0).fixed_decimals(3)).changed();
+                        ui.end_row();
+                    }
+                    ui.label("Unstable");
+                    changed |= ui.checkbox(&mut draft.unstable, "").changed();
+                    ui.end_row();
+                });
+                // 手で作った物質は元の物質と別の seed にする (同じ seed 同士は反応しない)
+                if changed && draft.seed == brush.seed {
+                    draft.seed = rand::random();
+                }
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Use as brush").clicked() {
+                        edit = Some(DnaEdit::SetBrush(draft.clone()));
+                    }
+                    if let Some(id) = selected_dot {
+                        if ui.button("Write to selected dot").clicked() {
+                            edit = Some(DnaEdit::WriteToSelected(id, draft.clone()));
+                        }
+                    } else {
+                        ui.add_enabled(false, egui::Button::new("Write to selected dot"))
+                            .on_disabled_hover_text("Click a dot to select it");
+                    }
+                });
+                reload = ui.button("Reload from brush").clicked();
+            });
+        if reload {
+            self.draft = Some(brush.clone());
+        }
+        self.open = open;
+        edit
+    }
+}
@@ -0,0 +1,343 @@
+//! 描画パスを宣言的に組み立てるための簡易フレームグラフ
+//!
+//! 各パスは書き込むテクスチャ (カラーターゲット) と読み込むテクスチャ (バインドグループ) を宣言する。
+//! 中間テクスチャは `TexturePool` から最初に使うパスの直前に割り当てられ、
+//! 最後に使うパスの後で解放されて同じ形式の別テクスチャに再利用される。
+//! 結果がどこからも読まれないパスは実行しない。
+
+use std::ops::Range;
+
+/// フレームグラフ内の中間テクスチャ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureHandle(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureDesc {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+}
+
+impl TextureDesc {
+    pub fn size_bytes(&self) -> u64 {
+        let texel_size = self.format.block_copy_size(None).unwrap_or(4);
+        self.width as u64 * self.height as u64 * texel_size as u64
+    }
+}
+
+/// カラーターゲット (中間テクスチャ、外部から渡されるサーフェス、またはグラフ外で管理するテクスチャ)
+/// Surface と Imported への書き込みはグラフの外から参照されるため、常に実行される
+#[derive(Debug, Clone, Copy)]
+pub enum Target<'a> {
+    Texture(TextureHandle),
+    Surface,
+    Imported(&'a wgpu::TextureView),
+}
+
+impl From<TextureHandle> for Target<'_> {
+    fn from(handle: TextureHandle) -> Self {
+        Target::Texture(handle)
+    }
+}
+
+/// バインドグループの1エントリ (binding 番号は並び順)
+pub enum Binding<'a> {
+    Texture(TextureHandle),
+    Sampler(&'a wgpu::Sampler),
+    Buffer(&'a wgpu::Buffer),
+}
+
+pub struct DrawCall<'a> {
+    pub vertex_buffers: Vec<wgpu::BufferSlice<'a>>,
+    pub vertices: Range<u32>,
+    pub instances: Range<u32>,
+}
+
+impl DrawCall<'_> {
+    /// 画面全体を覆う三角形 (頂点シェーダー側で生成)
+    pub fn fullscreen_triangle() -> Self {
+        Self {
+            vertex_buffers: Vec::new(),
+            vertices: 0..3,
+            instances: 0..1,
+        }
+    }
+}
+
+struct BindGroupDecl<'a> {
+    label: &'static str,
+    layout: &'a wgpu::BindGroupLayout,
+    entries: Vec<Binding<'a>>,
+}
+
+struct PassNode<'a> {
+    label: &'static str,
+    color_targets: Vec<(Target<'a>, wgpu::LoadOp<wgpu::Color>)>,
+    pipeline: Option<&'a wgpu::RenderPipeline>,
+    bind_groups: Vec<BindGroupDecl<'a>>,
+    draw: Option<DrawCall<'a>>,
+}
+
+impl<'a> PassNode<'a> {
+    fn writes(&self) -> impl Iterator<Item = Target<'a>> + '_ {
+        self.color_targets.iter().map(|(target, _)| *target)
+    }
+
+    fn reads(&self) -> impl Iterator<Item = TextureHandle> + '_ {
+        self.bind_groups
+            .iter()
+            .flat_map(|group| group.entries.iter())
+            .filter_map(|entry| match entry {
+                Binding::Texture(handle) => Some(*handle),
+                _ => None,
+            })
+    }
+
+    fn uses(&self, handle: TextureHandle) -> bool {
+        self.writes().any(|target| matches!(target, Target::Texture(written) if written == handle))
+            || self.reads().any(|read| read == handle)
+    }
+}
+
+pub struct PassBuilder<'g, 'a> {
+    graph: &'g mut FrameGraph<'a>,
+    node: PassNode<'a>,
+}
+
+impl<'a> PassBuilder<'_, 'a> {
+    /// 黒でクリアしてから書き込む
+    pub fn clear(mut self, target: impl Into<Target<'a>>) -> Self {
+        self.node.color_targets.push((target.into(), wgpu::LoadOp::Clear(wgpu::Color::BLACK)));
+        self
+    }
+
+    /// 既存の内容の上に書き込む
+    pub fn load(mut self, target: impl Into<Target<'a>>) -> Self {
+        self.node.color_targets.push((target.into(), wgpu::LoadOp::Load));
+        self
+    }
+
+    pub fn pipeline(mut self, pipeline: &'a wgpu::RenderPipeline) -> Self {
+        self.node.pipeline = Some(pipeline);
+        self
+    }
+
+    /// バインドグループを宣言する (group 番号は宣言順)。実行時に作成される
+    pub fn bind_group(mut self, label: &'static str, layout: &'a wgpu::BindGroupLayout, entries: Vec<Binding<'a>>) -> Self {
+        self.node.bind_groups.push(BindGroupDecl { label, layout, entries });
+        self
+    }
+
+    /// 描画を行わない場合 (クリアのみ) は呼ばなくてよい
+    pub fn draw(mut self, draw: DrawCall<'a>) -> Self {
+        self.node.draw = Some(draw);
+        self
+    }
+
+    pub fn finish(self) {
+        self.graph.passes.push(self.node);
+    }
+}
+
+/// フレームをまたいで再利用される中間テクスチャの実体
+#[derive(Default)]
+pub struct TexturePool {
+    textures: Vec<(TextureDesc, wgpu::TextureView)>,
+}
+
+impl TexturePool {
+    /// 他のテクスチャに割り当て済みでない、同じ形式のテクスチャを探す (無ければ作成)
+    fn acquire(&mut self, device: &wgpu::Device, label: &str, desc: TextureDesc, in_use: &[usize]) -> usize {
+        if let Some(index) = self
+            .textures
+            .iter()
+            .enumerate()
+            .position(|(index, (pooled, _))| *pooled == desc && !in_use.contains(&index))
+        {
+            return index;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: desc.width,
+                height: desc.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: desc.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        self.textures.push((desc, texture.create_view(&Default::default())));
+        self.textures.len() - 1
+    }
+
+    fn view(&self, index: usize) -> &wgpu::TextureView {
+        &self.textures[index].1
+    }
+
+    /// プールが保持しているテクスチャの合計サイズ
+    pub fn size_bytes(&self) -> u64 {
+        self.textures.iter().map(|(desc, _)| desc.size_bytes()).sum()
+    }
+}
+
+#[derive(Default)]
+pub struct FrameGraph<'a> {
+    textures: Vec<(&'static str, TextureDesc)>,
+    passes: Vec<PassNode<'a>>,
+}
+
+impl<'a> FrameGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 中間テクスチャを宣言する (実体はパスで使われる時点で割り当てる)
+    pub fn create_texture(&mut self, label: &'static str, desc: TextureDesc) -> TextureHandle {
+        self.textures.push((label, desc));
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    /// パスを追加する。パスは追加した順に実行される
+    pub fn add_pass(&mut self, label: &'static str) -> PassBuilder<'_, 'a> {
+        PassBuilder {
+            graph: self,
+            node: PassNode {
+                label,
+                color_targets: Vec::new(),
+                pipeline: None,
+                bind_groups: Vec::new(),
+                draw: None,
+            },
+        }
+    }
+
+    pub fn execute(
+        self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        surface_view: &wgpu::TextureView,
+        pool: &mut TexturePool,
+    ) {
+        let passes = self.cull_unused_passes();
+
+        // --- 各テクスチャの寿命 (最初と最後に使われるパス) に合わせてプールから割り当てる ---
+        let mut assigned: Vec<Option<usize>> = vec![None; self.textures.len()];
+        let mut in_use: Vec<usize> = Vec::new();
+        for (pass_index, pass) in passes.iter().enumerate() {
+            for (texture_index, (label, desc)) in self.textures.iter().enumerate() {
+                let handle = TextureHandle(texture_index);
+                if assigned[texture_index].is_none() && pass.uses(handle) {
+                    let pooled = pool.acquire(device, label, *desc, &in_use);
+                    assigned[texture_index] = Some(pooled);
+                    in_use.push(pooled);
+                }
+            }
+            for (texture_index, pooled) in assigned.iter().enumerate() {
+                let handle = TextureHandle(texture_index);
+                let is_last_use = pass.uses(handle) && !passes[pass_index + 1..].iter().any(|later| later.uses(handle));
+                if let (Some(pooled), true) = (pooled, is_last_use) {
+                    in_use.retain(|index| index != pooled);
+                }
+            }
+        }
+
+        // 割り当て後はプールを読み取り専用で参照する
+        let pool = &*pool;
+        let texture_view = |handle: TextureHandle| {
+            let pooled = assigned[handle.0].expect("texture used by a pass is always assigned");
+            pool.view(pooled)
+        };
+        let target_view = |target: Target<'a>| match target {
+            Target::Texture(handle) => texture_view(handle),
+            Target::Surface => surface_view,
+            Target::Imported(view) => view,
+        };
+
+        // --- パスの実行 ---
+        for pass in &passes {
+            let bind_groups: Vec<wgpu::BindGroup> = pass
+                .bind_groups
+                .iter()
+                .map(|group| {
+                    let entries: Vec<wgpu::BindGroupEntry> = group
+                        .entries
+                        .iter()
+                        .enumerate()
+                        .map(|(binding, entry)| wgpu::BindGroupEntry {
+                            binding: binding as u32,
+                            resource: match entry {
+                                Binding::Texture(handle) => wgpu::BindingResource::TextureView(texture_view(*handle)),
+                                Binding::Sampler(sampler) => wgpu::BindingResource::Sampler(sampler),
+                                Binding::Buffer(buffer) => buffer.as_entire_binding(),
+                            },
+                        })
+                        .collect();
+                    device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some(group.label),
+                        layout: group.layout,
+                        entries: &entries,
+                    })
+                })
+                .collect();
+
+            let color_attachments: Vec<Option<wgpu::RenderPassColorAttachment>> = pass
+                .color_targets
+                .iter()
+                .map(|(target, load)| {
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: target_view(*target),
+                        resolve_target: None,
+                        ops: wgpu::Operations { load: *load, store: wgpu::StoreOp::Store },
+                    })
+                })
+                .collect();
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(pass.label),
+                color_attachments: &color_attachments,
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            let (Some(pipeline), Some(draw)) = (pass.pipeline, &pass.draw) else {
+                continue; // クリアのみ
+            };
+            render_pass.set_pipeline(pipeline);
+            for (index, bind_group) in bind_groups.iter().enumerate() {
+                render_pass.set_bind_group(index as u32, bind_group, &[]);
+            }
+            for (slot, buffer) in draw.vertex_buffers.iter().enumerate() {
+                render_pass.set_vertex_buffer(slot as u32, *buffer);
+            }
+            render_pass.draw(draw.vertices.clone(), draw.instances.clone());
+        }
+    }
+
+    /// サーフェスに書き込むパスから逆にたどり、結果が使われないパスを取り除く
+    fn cull_unused_passes(&self) -> Vec<&PassNode<'a>> {
+        let mut needed_textures: Vec<TextureHandle> = Vec::new();
+        let mut needed = vec![false; self.passes.len()];
+        for (index, pass) in self.passes.iter().enumerate().rev() {
+            let is_needed = pass.writes().any(|target| match target {
+                Target::Surface | Target::Imported(_) => true,
+                Target::Texture(handle) => needed_textures.contains(&handle),
+            });
+            if is_needed {
+                needed[index] = true;
+                needed_textures.extend(pass.reads());
+            }
+        }
+
+        self.passes
+            .iter()
+            .zip(needed)
+            .filter_map(|(pass, needed)| needed.then_some(pass))
+            .collect()
+    }
+}
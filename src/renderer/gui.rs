@@ -1,19 +1,196 @@
-use crate::material::{BaseMaterialParams, MaterialDNA};
+use super::camera::Camera;
+use super::camera_path::{CameraPath, CameraPathAction};
+use super::command_palette::CommandPalette;
+use super::orchestrator::{RenderSettings, Tonemapping};
+use super::dna_editor::{DnaEdit, DnaEditorView};
+use super::material_compare::MaterialCompareView;
+use super::reaction_view::ReactionGraphView;
+use super::temperature::{heat_color, TemperatureUnit};
+use super::wgpu_render::RendererMemory;
+use crate::brush::{Brush, BrushShape, Launch, MAX_LAUNCH_SPEED, MAX_RADIUS as MAX_BRUSH_RADIUS};
+use crate::commands::{Command, CommandRegistry};
+use crate::gamepad::{GamepadBindings, PadAction, PadButton, MAX_DEAD_ZONE};
+use crate::groups::{GroupAction, GroupId, GroupStats};
+use crate::journal::{format_elapsed, Journal};
+use crate::logging::LogBuffer;
+use crate::macros::{MacroAction, MacroKey, Macros, MAX_SCALE as MAX_MACRO_SCALE, MIN_SCALE as MIN_MACRO_SCALE};
+use crate::material::library::{LibraryAction, MaterialLibrary, PresetFilter, SortKey, TemperatureClass, LIBRARY_DIR};
+use crate::material::mutation::{Mutation, MAX_MAGNITUDE as MAX_MUTATION_MAGNITUDE, MAX_RATE as MAX_MUTATION_RATE};
+use crate::material::{from_dna, BaseMaterialParams, MaterialDNA, State};
+use crate::physics::convection::FlowCell;
+use crate::physics::engine::PhysicsMemory;
+use crate::physics::interaction::{CollisionModel, InteractionMatrix, STATES};
+use crate::physics::conveyor::{Conveyor, ConveyorSettings, MAX_SPEED as MAX_CONVEYOR_SPEED};
+use crate::physics::drain::{Drain, DrainFilter};
+use crate::physics::portal::{Portal, PortalSettings, MAX_RADIUS as MAX_PORTAL_RADIUS, MIN_RADIUS as MIN_PORTAL_RADIUS};
+use crate::physics::thermostat::WallThermostat;
+use crate::weather::{Precipitation, WeatherSettings, MAX_RATE as MAX_WEATHER_RATE, MAX_WIND};
+use crate::presets::{Preset, Shape};
+use crate::puzzle::Puzzle;
+use crate::reaction_graph::{ExportFormat, ReactionGraph};
+use crate::replay::{ReplayControl, ReplayMode, REPLAY_FILE};
+use crate::scene::SCENE_FILE;
+use crate::tabs::TabAction;
+use crate::tools::Tool;
 use egui_wgpu::{wgpu, Renderer, ScreenDescriptor};
 use egui_winit::winit;
 
-pub struct UiData {
+pub struct UiData<'a> {
     pub fps: f64,
     pub dot_count: usize,
+    pub sleeping_dots: usize,
+    pub physics_seed: Option<u64>, // 決定論モードの seed
     pub selected_material: Option<BaseMaterialParams>,
     pub selected_dot_dna: Option<MaterialDNA>,
     pub selected_dot_name: Option<String>,
+    pub selected_dot_id: Option<u64>,
+    pub selected_dot_note: Option<String>,
+    pub hovered_note: Option<String>, // カーソル下のドットのメモ
+    pub notes: Vec<NoteSummary>,
+    pub gamepad_name: Option<String>,
+    pub gamepad_dead_zone: f32,
+    pub gamepad_bindings: GamepadBindings,
+    pub cursor_position: Option<(f64, f64)>,
+    pub photo_mode: bool,
+    pub shader_error: Option<String>,
+    pub groups: Vec<GroupSummary>,
+    pub brush_group: Option<GroupId>,
+    pub selected_dot_group: Option<GroupId>,
+    pub convection_enabled: bool,
+    pub combustion_enabled: bool,
+    pub electricity_enabled: bool,
+    pub thermostat: WallThermostat,
+    pub weather: WeatherSettings,
+    pub weather_material: MaterialDNA, // 実際に降らせる物質 (状態は雨・雪で決まる)
+    pub weather_dots: usize,
+    pub drains: &'a [Drain],
+    pub drain_filter: &'a DrainFilter,
+    pub conveyors: &'a [Conveyor],
+    pub conveyor_settings: ConveyorSettings,
+    pub region_preview: Option<((f64, f64), (f64, f64))>, // Drain / Conveyor ツールでドラッグ中の矩形
+    pub portals: &'a [Portal],
+    pub portal_settings: PortalSettings,
+    pub portal_pending: bool, // 入口だけ置いて出口を待っているか
+    pub color_diffusion_enabled: bool,
+    pub flow_field: Vec<FlowCell>, // show_flow が有効なときだけ中身がある
+    pub camera: Camera,
+    pub camera_path: &'a CameraPath,
+    pub reaction_graph: &'a ReactionGraph,
+    pub puzzle: Option<&'a Puzzle>,
+    pub presets: &'a [Preset],
+    pub commands: &'a CommandRegistry,
+    pub logs: &'a LogBuffer,
+    pub previous_crash_report: Option<&'a std::path::Path>,
+    pub reaction_export_status: Option<String>,
+    pub journal: &'a Journal,
+    pub journal_status: Option<String>,
+    pub scene_status: Option<String>,
+    pub macros: &'a Macros,
+    pub macro_status: Option<String>,
+    pub material_library: &'a MaterialLibrary,
+    pub material_library_status: Option<String>,
+    pub replay: &'a ReplayMode,
+    pub replay_status: Option<String>,
+    pub tab_names: Vec<String>,
+    pub active_tab: usize,
+    pub background_tabs_running: bool,
+    pub tool: Tool,
+    pub brush: Brush,
+    pub brush_dna: MaterialDNA,
+    pub mutation: Mutation,
+    pub brush_preview_count: Option<usize>, // カーソル位置でクリックしたときに置かれるドットの数
+    pub memory: MemoryUsage,
+    pub interactions: InteractionMatrix,
+}
+
+/// Memory パネルに表示するメモリ使用量 (バイト数)
+pub struct MemoryUsage {
+    pub dots: usize,
+    pub physics: PhysicsMemory,
+    pub renderer: RendererMemory,
+}
+
+/// Notes パネルに表示するメモ
+pub struct NoteSummary {
+    pub id: u64,
+    pub name: String,
+    pub text: String,
+    pub position: (f64, f64), // ワールド座標
+}
+
+/// Groups パネルに表示するグループの情報
+pub struct GroupSummary {
+    pub id: GroupId,
+    pub name: String,
+    pub visible: bool,
+    pub frozen: bool,
+    pub stats: GroupStats,
+}
+
+/// GUI で操作された内容 (App 側で反映する)
+#[derive(Default)]
+pub struct UiActions {
+    pub randomize: bool,
+    pub clear: bool,
+    pub toggle_convection: bool,
+    pub toggle_combustion: bool,
+    pub toggle_electricity: bool,
+    pub set_thermostat: Option<WallThermostat>,
+    pub set_weather: Option<WeatherSettings>,
+    pub weather_use_brush: bool,
+    pub set_drain_filter: Option<DrainFilter>,
+    pub remove_drain: Option<usize>,
+    pub set_conveyor_settings: Option<ConveyorSettings>,
+    pub remove_conveyor: Option<usize>,
+    pub set_portal_settings: Option<PortalSettings>,
+    pub remove_portal: Option<usize>,
+    pub toggle_color_diffusion: bool,
+    pub export_reactions: Option<ExportFormat>,
+    pub set_brush: Option<MaterialDNA>, // 反応ネットワークでクリックされた物質
+    pub dna_edit: Option<DnaEdit>,      // DNA エディタで編集された物質
+    pub set_mutation: Option<Mutation>,
+    pub load_preset: Option<usize>, // Gallery で選ばれたプリセット
+    pub command: Option<Command>,   // コマンドパレットで選ばれた (App 側で処理する) コマンド
+    pub open_crash_report: bool,
+    pub dismiss_crash_report: bool,
+    pub new_puzzle: bool,
+    pub quit_puzzle: bool,
+    pub group_action: Option<GroupAction>,
+    pub set_tool: Option<Tool>,
+    pub brush: Option<Brush>, // 形・半径・密度が変更されたブラシ
+    pub camera_path: Option<CameraPathAction>,
+    pub macro_action: Option<MacroAction>,
+    pub library_action: Option<LibraryAction>,
+    pub tab: Option<TabAction>,
+    pub set_interactions: Option<InteractionMatrix>, // Interactions パネルで編集された行列
+    pub set_note: Option<(u64, String)>,              // メモを書き換えるドットと内容 (空なら削除)
+    pub select_dot: Option<u64>,                      // Notes パネルで選ばれたドット
+    pub set_gamepad_dead_zone: Option<f32>,
+    pub set_gamepad_bindings: Option<GamepadBindings>,
 }
 
 pub struct Gui {
     pub ctx: egui::Context,
     pub state: egui_winit::State,
     pub renderer: Renderer,
+    new_group_name: String, // Groups パネルの名前入力欄
+    reaction_view: ReactionGraphView,
+    material_compare: MaterialCompareView,
+    dna_editor: DnaEditorView,
+    gallery_open: bool,
+    command_palette: CommandPalette,
+    log_open: bool,
+    log_level: tracing::Level, // Log ウィンドウに表示する最低レベル
+    camera_path_open: bool,
+    notes_open: bool,
+    journal_open: bool,
+    macros_open: bool,
+    material_library_open: bool,
+    material_name_draft: String, // Materials パネルの名前入力欄
+    material_sort: SortKey,
+    material_filter: PresetFilter,
+    material_rename: Option<(usize, String)>, // 名前を変えているプリセットと入力中の名前
+    note_draft: (Option<u64>, String), // 編集中のメモ (対象のドット, 内容)
 }
 
 impl Gui {
@@ -31,6 +208,24 @@ impl Gui {
             ctx,
             state,
             renderer,
+            new_group_name: String::new(),
+            reaction_view: ReactionGraphView::default(),
+            material_compare: MaterialCompareView::default(),
+            dna_editor: DnaEditorView::default(),
+            gallery_open: false,
+            command_palette: CommandPalette::default(),
+            log_open: false,
+            log_level: tracing::Level::INFO,
+            camera_path_open: false,
+            notes_open: false,
+            journal_open: false,
+            macros_open: false,
+            material_library_open: false,
+            material_name_draft: String::new(),
+            material_sort: SortKey::default(),
+            material_filter: PresetFilter::default(),
+            material_rename: None,
+            note_draft: (None, String::new()),
         }
     }
 
@@ -42,7 +237,8 @@ impl Gui {
         self.state.on_window_event(window, event).consumed
     }
 
-    // render関数は、ランダム化ボタンが押された場合にtrueを返す
+    // render関数は、ボタンなどで操作された内容を返す
+    #[allow(clippy::too_many_arguments)]
     pub fn render(
         &mut self,
         window: &winit::window::Window,
@@ -51,12 +247,116 @@ impl Gui {
         encoder: &mut wgpu::CommandEncoder,
         view: &wgpu::TextureView,
         ui_data: &UiData,
-    ) -> (bool, bool) { // 戻り値を (randomize_clicked, clear_clicked) に変更
-        let mut randomize_button_clicked = false;
-        let mut clear_button_clicked = false; // 新しいフラグ
+        settings: &mut RenderSettings,
+    ) -> UiActions {
+        let mut actions = UiActions::default();
+        let new_group_name = &mut self.new_group_name;
+        let reaction_view = &mut self.reaction_view;
+        let material_compare = &mut self.material_compare;
+        let dna_editor = &mut self.dna_editor;
+        let gallery_open = &mut self.gallery_open;
+        let command_palette = &mut self.command_palette;
+        let log_open = &mut self.log_open;
+        let log_level = &mut self.log_level;
+        let camera_path_open = &mut self.camera_path_open;
+        let notes_open = &mut self.notes_open;
+        let journal_open = &mut self.journal_open;
+        let macros_open = &mut self.macros_open;
+        let material_library_open = &mut self.material_library_open;
+        let material_name_draft = &mut self.material_name_draft;
+        let material_sort = &mut self.material_sort;
+        let material_filter = &mut self.material_filter;
+        let material_rename = &mut self.material_rename;
+        let note_draft = &mut self.note_draft;
 
         let raw_input = self.state.take_egui_input(window);
         let full_output = self.ctx.run(raw_input, |ctx| {
+            // シェーダーのコンパイルエラー (ホットリロード時)
+            if let Some(error) = &ui_data.shader_error {
+                egui::Window::new("Shader Error")
+                    .default_pos(egui::pos2(200.0, 10.0))
+                    .show(ctx, |ui| {
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            ui.colored_label(egui::Color32::LIGHT_RED, error);
+                        });
+                    });
+            }
+
+            // 前回のセッションがクラッシュしていた場合の通知
+            if let Some(path) = ui_data.previous_crash_report {
+                egui::Window::new("Previous session crashed")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.label("A crash report was saved. Attaching it to a bug report helps a lot.");
+                        ui.weak(path.display().to_string());
+                        ui.horizontal(|ui| {
+                            if ui.button("Open report").clicked() {
+                                actions.open_crash_report = true;
+                            }
+                            if ui.button("Dismiss").clicked() {
+                                actions.dismiss_crash_report = true;
+                            }
+                        });
+                    });
+            }
+
+            // コマンドパレット (フォトモード中も使える)。GUI の状態に関するものはここで処理する
+            match command_palette.show(ctx, ui_data.commands) {
+                Some(Command::ToggleGallery) => *gallery_open = !*gallery_open,
+                Some(Command::ToggleReactionGraph) => reaction_view.open = !reaction_view.open,
+                Some(Command::ToggleMaterialCompare) => material_compare.open = !material_compare.open,
+                Some(Command::ToggleDnaEditor) => dna_editor.open = !dna_editor.open,
+                Some(Command::ToggleFlowField) => settings.show_flow = !settings.show_flow,
+                Some(Command::ToggleSkipComposite) => settings.skip_composite = !settings.skip_composite,
+                Some(Command::ToggleLog) => *log_open = !*log_open,
+                Some(Command::ToggleCameraPath) => *camera_path_open = !*camera_path_open,
+                Some(Command::ToggleNotes) => *notes_open = !*notes_open,
+                Some(Command::ToggleJournal) => *journal_open = !*journal_open,
+                Some(Command::ToggleMacros) => *macros_open = !*macros_open,
+                Some(Command::ToggleMaterialLibrary) => *material_library_open = !*material_library_open,
+                command => actions.command = command,
+            }
+            // テキストの編集中は編集欄の取り消しに任せる
+            if !ctx.wants_keyboard_input() && ctx.input_mut(|input| input.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)) {
+                actions.command = Some(Command::UndoErase);
+            }
+
+            // フォトモード中はHUDを非表示にする
+            if ui_data.photo_mode {
+                return;
+            }
+
+            // シミュレーションのタブ
+            egui::Area::new(egui::Id::new("simulation_tabs"))
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 4.0))
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            for (index, name) in ui_data.tab_names.iter().enumerate() {
+                                if ui.selectable_label(index == ui_data.active_tab, name).clicked() {
+                                    actions.tab = Some(TabAction::Switch(index));
+                                }
+                                if ui_data.tab_names.len() > 1 && ui.small_button("×").on_hover_text("Close").clicked() {
+                                    actions.tab = Some(TabAction::Close(index));
+                                }
+                            }
+                            if ui_data.tab_names.len() < crate::tabs::MAX_TABS
+                                && ui.button("+").on_hover_text("New simulation").clicked()
+                            {
+                                actions.tab = Some(TabAction::New);
+                            }
+                            if ui_data.tab_names.len() > 1 {
+                                let mut running = ui_data.background_tabs_running;
+                                if ui.checkbox(&mut running, "Run others").on_hover_text("Tick background tabs at reduced speed").changed() {
+                                    actions.tab = Some(TabAction::SetBackgroundRunning(running));
+                                }
+                            }
+                        });
+                    });
+                });
+
             // FPSとドット数を表示するウィンドウ
             egui::Window::new("Info")
                 .title_bar(false)
@@ -65,24 +365,1148 @@ impl Gui {
                 .default_pos(egui::pos2(10.0, 10.0))
                 .show(ctx, |ui| {
                     ui.label(format!("FPS: {:.2}", ui_data.fps));
-                    ui.label(format!("Dots: {}", ui_data.dot_count));
+                    ui.label(format!("Dots: {} ({} sleeping)", ui_data.dot_count, ui_data.sleeping_dots));
+                    if let Some(seed) = ui_data.physics_seed {
+                        ui.weak(format!("Deterministic (seed {})", seed));
+                    }
                     if ui
                         .button("RND")
                         .on_hover_text("Randomize brush material")
                         .clicked()
                     {
-                        randomize_button_clicked = true;
+                        actions.randomize = true;
                     }
+                    ui.checkbox(&mut dna_editor.open, "DNA").on_hover_text("Edit the brush material gene by gene");
                     // CLSボタンを追加
                     if ui
                         .button("CLS")
                         .on_hover_text("Clear all dots")
                         .clicked()
                     {
-                        clear_button_clicked = true;
+                        actions.clear = true;
+                    }
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").on_hover_text(format!("Save all dots to {}", SCENE_FILE)).clicked() {
+                            actions.command = Some(Command::SaveScene);
+                        }
+                        if ui.button("Load").on_hover_text(format!("Replace the scene with {}", SCENE_FILE)).clicked() {
+                            actions.command = Some(Command::LoadScene);
+                        }
+                        if let Some(status) = &ui_data.scene_status {
+                            ui.weak(status);
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Replay:");
+                        match ui_data.replay {
+                            ReplayMode::Idle => {
+                                if ui.button("Rec").on_hover_text("Clears the world and records spawns, randomize and clear").clicked() {
+                                    actions.command = Some(Command::Replay(ReplayControl::StartRecording));
+                                }
+                                if ui.button("Play").on_hover_text(format!("Play {}", REPLAY_FILE)).clicked() {
+                                    actions.command = Some(Command::Replay(ReplayControl::Play));
+                                }
+                            }
+                            ReplayMode::Recording(recorder) => {
+                                if ui.button("Stop").on_hover_text(format!("Save to {}", REPLAY_FILE)).clicked() {
+                                    actions.command = Some(Command::Replay(ReplayControl::StopRecording));
+                                }
+                                ui.colored_label(egui::Color32::LIGHT_RED, format!("● {} inputs", recorder.event_count()));
+                            }
+                            ReplayMode::Playing(player) => {
+                                if ui.button("Stop").clicked() {
+                                    actions.command = Some(Command::Replay(ReplayControl::StopPlayback));
+                                }
+                                let (done, total) = player.progress();
+                                ui.label(format!("▶ {}/{}", done, total));
+                            }
+                        }
+                        if let Some(status) = &ui_data.replay_status {
+                            ui.weak(status);
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Tool:");
+                        for (key, tool) in Tool::ALL.into_iter().enumerate() {
+                            if ui
+                                .selectable_label(ui_data.tool == tool, tool.label())
+                                .on_hover_text(format!("Key {}", key + 1))
+                                .clicked()
+                            {
+                                actions.set_tool = Some(tool);
+                            }
+                        }
+                    });
+
+                    if ui_data.tool == Tool::Brush {
+                        let mut brush = ui_data.brush;
+                        ui.horizontal(|ui| {
+                            ui.label("Shape:");
+                            for shape in BrushShape::ALL {
+                                ui.selectable_value(&mut brush.shape, shape, shape.label());
+                            }
+                        });
+                        ui.add(egui::Slider::new(&mut brush.radius, 0.0..=MAX_BRUSH_RADIUS).text("Radius"))
+                            .on_hover_text("0 places a single dot");
+                        ui.add(egui::Slider::new(&mut brush.density, 0.05..=1.0).text("Density"));
+                        ui.add(egui::Slider::new(&mut brush.temperature_offset, -1.0..=1.0).text("Heat"))
+                            .on_hover_text("Added to the temperature of placed dots");
+                        // 初速 (向きを指定するか、ドラッグの向きに合わせる)
+                        let speed = match brush.launch {
+                            Launch::Still => MAX_LAUNCH_SPEED / 2.0,
+                            Launch::Direction { speed, .. } | Launch::Drag { speed } => speed,
+                        };
+                        egui::ComboBox::from_label("Launch")
+                            .selected_text(brush.launch.label())
+                            .show_ui(ui, |ui| {
+                                for launch in [Launch::Still, Launch::Direction { angle: 90.0, speed }, Launch::Drag { speed }] {
+                                    let selected = std::mem::discriminant(&launch) == std::mem::discriminant(&brush.launch);
+                                    if ui.selectable_label(selected, launch.label()).clicked() && !selected {
+                                        brush.launch = launch;
+                                    }
+                                }
+                            });
+                        match &mut brush.launch {
+                            Launch::Still => {}
+                            Launch::Direction { angle, speed } => {
+                                ui.add(egui::Slider::new(angle, 0.0..=360.0).suffix("°").text("Angle"))
+                                    .on_hover_text("0° = right, 90° = down");
+                                ui.add(egui::Slider::new(speed, 0.0..=MAX_LAUNCH_SPEED).text("Speed"));
+                            }
+                            Launch::Drag { speed } => {
+                                ui.add(egui::Slider::new(speed, 0.0..=MAX_LAUNCH_SPEED).text("Speed"));
+                            }
+                        }
+                        if brush != ui_data.brush {
+                            actions.brush = Some(brush);
+                        }
+                    }
+
+                    if ui_data.tool == Tool::Drain {
+                        ui.weak("Drag to place a drain that removes dots entering it");
+                        egui::ComboBox::from_label("Removes")
+                            .selected_text(ui_data.drain_filter.label())
+                            .show_ui(ui, |ui| {
+                                let brush_material = DrainFilter::Material {
+                                    seed: ui_data.brush_dna.seed,
+                                    name: crate::naming::generate_name(&ui_data.brush_dna),
+                                };
+                                let choices = std::iter::once(DrainFilter::Any)
+                                    .chain(STATES.into_iter().map(DrainFilter::State))
+                                    .chain(std::iter::once(brush_material));
+                                for filter in choices {
+                                    if ui.selectable_label(*ui_data.drain_filter == filter, filter.label()).clicked() {
+                                        actions.set_drain_filter = Some(filter);
+                                    }
+                                }
+                            });
+                        egui::Grid::new("drains_grid").num_columns(2).striped(true).show(ui, |ui| {
+                            for (index, drain) in ui_data.drains.iter().enumerate() {
+                                ui.label(format!("#{} {}", index + 1, drain.filter.label()));
+                                if ui.small_button("DEL").clicked() {
+                                    actions.remove_drain = Some(index);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if !ui_data.drains.is_empty() && ui.button("Clear drains").clicked() {
+                            actions.command = Some(Command::ClearDrains);
+                        }
+                    }
+
+                    if ui_data.tool == Tool::Conveyor {
+                        ui.weak("Drag to place a belt along the longer side (+ right / down)");
+                        let mut conveyor_settings = ui_data.conveyor_settings;
+                        ui.add(egui::Slider::new(&mut conveyor_settings.speed, -MAX_CONVEYOR_SPEED..=MAX_CONVEYOR_SPEED).text("Speed"));
+                        ui.checkbox(&mut conveyor_settings.carries_gas, "Carry gases");
+                        if conveyor_settings != ui_data.conveyor_settings {
+                            actions.set_conveyor_settings = Some(conveyor_settings);
+                        }
+                        egui::Grid::new("conveyors_grid").num_columns(2).striped(true).show(ui, |ui| {
+                            for (index, conveyor) in ui_data.conveyors.iter().enumerate() {
+                                let gas = if conveyor.settings.carries_gas { " +gas" } else { "" };
+                                ui.label(format!("#{} {:+.0}{}", index + 1, conveyor.settings.speed, gas));
+                                if ui.small_button("DEL").clicked() {
+                                    actions.remove_conveyor = Some(index);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if !ui_data.conveyors.is_empty() && ui.button("Clear conveyors").clicked() {
+                            actions.command = Some(Command::ClearConveyors);
+                        }
+                    }
+
+                    if ui_data.tool == Tool::Portal {
+                        ui.weak(if ui_data.portal_pending {
+                            "Click to place the exit (blue)"
+                        } else {
+                            "Click to place the entrance (orange)"
+                        });
+                        let mut portal_settings = ui_data.portal_settings;
+                        ui.add(egui::Slider::new(&mut portal_settings.radius, MIN_PORTAL_RADIUS..=MAX_PORTAL_RADIUS).text("Radius"));
+                        ui.add(egui::Slider::new(&mut portal_settings.rotation, -180.0..=180.0).step_by(15.0).suffix("°").text("Rotation"))
+                            .on_hover_text("Turns position and velocity on the way out (reversed when passing back)");
+                        if portal_settings != ui_data.portal_settings {
+                            actions.set_portal_settings = Some(portal_settings);
+                        }
+                        egui::Grid::new("portals_grid").num_columns(2).striped(true).show(ui, |ui| {
+                            for (index, portal) in ui_data.portals.iter().enumerate() {
+                                ui.label(format!("#{} r{:.0} {:+.0}°", index + 1, portal.settings.radius, portal.settings.rotation));
+                                if ui.small_button("DEL").clicked() {
+                                    actions.remove_portal = Some(index);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if (!ui_data.portals.is_empty() || ui_data.portal_pending) && ui.button("Clear portals").clicked() {
+                            actions.command = Some(Command::ClearPortals);
+                        }
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.checkbox(gallery_open, "Gallery").on_hover_text("Load a built-in scenario");
+                        ui.checkbox(log_open, "Log");
+                        ui.checkbox(notes_open, "Notes").on_hover_text("Notes attached to dots");
+                        ui.checkbox(journal_open, "Journal").on_hover_text("Milestones recorded automatically");
+                        ui.checkbox(camera_path_open, "Camera path").on_hover_text("Keyframed camera for recordings");
+                        ui.checkbox(macros_open, "Macros").on_hover_text("Record and replay brush strokes");
+                        ui.checkbox(material_library_open, "Materials").on_hover_text("Saved materials for the brush");
+                    });
+
+                    if ui_data.puzzle.is_none()
+                        && ui
+                            .button("Puzzle")
+                            .on_hover_text("Try to create a target material by blending")
+                            .clicked()
+                    {
+                        actions.new_puzzle = true;
+                    }
+
+                    ui.weak("F2: Photo mode (WASD / wheel)");
+                    ui.weak("Ctrl+P: Command palette");
+                    ui.weak("Right click: Select, right drag: Erase (Ctrl+Z: undo)");
+
+                    ui.collapsing("Rendering", |ui| {
+                        ui.label(format!("Quality: {:?} (auto)", settings.quality));
+                        egui::ComboBox::from_label("Tonemapping")
+                            .selected_text(format!("{:?}", settings.tonemapping))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut settings.tonemapping, Tonemapping::Reinhard, "Reinhard");
+                                ui.selectable_value(&mut settings.tonemapping, Tonemapping::Aces, "ACES");
+                            });
+                        ui.checkbox(&mut settings.skip_composite, "Skip glow / composite");
+                        let mut color_diffusion_enabled = ui_data.color_diffusion_enabled;
+                        if ui
+                            .checkbox(&mut color_diffusion_enabled, "Liquid color mixing")
+                            .on_hover_text("Neighbouring liquids of the same family blend their colors (no DNA change)")
+                            .changed()
+                        {
+                            actions.toggle_color_diffusion = true;
+                        }
+                    });
+
+                    ui.collapsing("Heat flow", |ui| {
+                        let mut convection_enabled = ui_data.convection_enabled;
+                        if ui
+                            .checkbox(&mut convection_enabled, "Convection")
+                            .on_hover_text("Thermal expansion makes hot dots lighter")
+                            .changed()
+                        {
+                            actions.toggle_convection = true;
+                        }
+                        let mut combustion_enabled = ui_data.combustion_enabled;
+                        if ui
+                            .checkbox(&mut combustion_enabled, "Combustion")
+                            .on_hover_text("Hot flammable dots catch fire, spread it, smoke and turn to ash")
+                            .changed()
+                        {
+                            actions.toggle_combustion = true;
+                        }
+                        let mut electricity_enabled = ui_data.electricity_enabled;
+                        if ui
+                            .checkbox(&mut electricity_enabled, "Electricity")
+                            .on_hover_text("Charged dots attract or repel, magnetic dots attract, conductors share charge and large differences spark")
+                            .changed()
+                        {
+                            actions.toggle_electricity = true;
+                        }
+                        ui.checkbox(&mut settings.show_flow, "Show flow field")
+                            .on_hover_text("Average velocity per cell, colored by temperature");
+
+                        // 一定の温度を保つ壁 (チェックを外した壁は断熱)
+                        let mut thermostat = ui_data.thermostat;
+                        egui::Grid::new("thermostat_grid").num_columns(2).show(ui, |ui| {
+                            for (label, wall) in [
+                                ("Floor", &mut thermostat.floor),
+                                ("Ceiling", &mut thermostat.ceiling),
+                                ("Side walls", &mut thermostat.sides),
+                            ] {
+                                let mut enabled = wall.is_some();
+                                if ui.checkbox(&mut enabled, label).on_hover_text("Heats or cools dots touching this wall").changed() {
+                                    *wall = enabled.then_some(0.0);
+                                }
+                                if let Some(temperature) = wall {
+                                    ui.add(
+                                        egui::Slider::new(temperature, -1.0..=1.0)
+                                            .custom_formatter(|value, _| settings.temperature.format(value as f32)),
+                                    );
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if thermostat != ui_data.thermostat {
+                            actions.set_thermostat = Some(thermostat);
+                        }
+
+                        let scale = &mut settings.temperature;
+                        egui::ComboBox::from_label("Temperature unit")
+                            .selected_text(scale.unit.label())
+                            .show_ui(ui, |ui| {
+                                for unit in TemperatureUnit::ALL {
+                                    ui.selectable_value(&mut scale.unit, unit, unit.label());
+                                }
+                            });
+                        if scale.unit != TemperatureUnit::Raw {
+                            // 換算の幅 (°C)。-1.0 / 0.0 / 1.0 が何度に当たるか
+                            egui::Grid::new("temperature_scale_grid").num_columns(2).show(ui, |ui| {
+                                ui.label("Coldest (-1.0)");
+                                ui.add(egui::DragValue::new(&mut scale.cold_celsius).suffix(" °C").range(-273.0..=scale.ambient_celsius));
+                                ui.end_row();
+                                ui.label("Ambient (0.0)");
+                                ui.add(egui::DragValue::new(&mut scale.ambient_celsius).suffix(" °C").range(scale.cold_celsius..=scale.hot_celsius));
+                                ui.end_row();
+                                ui.label("Hottest (1.0)");
+                                ui.add(egui::DragValue::new(&mut scale.hot_celsius).suffix(" °C").range(scale.ambient_celsius..=10000.0));
+                                ui.end_row();
+                            });
+                        }
+                    });
+
+                    ui.collapsing("Weather", |ui| {
+                        let mut weather = ui_data.weather;
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut weather.enabled, "Enabled")
+                                .on_hover_text("Drops dots from the top edge; they vanish when they reach the floor");
+                            for kind in Precipitation::ALL {
+                                ui.selectable_value(&mut weather.kind, kind, kind.label());
+                            }
+                        });
+                        ui.add(egui::Slider::new(&mut weather.rate, 1.0..=MAX_WEATHER_RATE).text("Drops / s"));
+                        ui.add(egui::Slider::new(&mut weather.wind, -MAX_WIND..=MAX_WIND).text("Wind"));
+                        if weather != ui_data.weather {
+                            actions.set_weather = Some(weather);
+                        }
+
+                        ui.horizontal(|ui| {
+                            let (r, g, b) = from_dna(&ui_data.weather_material).get_color_rgb();
+                            let (swatch, _) = ui.allocate_exact_size(egui::vec2(16.0, 16.0), egui::Sense::hover());
+                            ui.painter().rect_filled(swatch, 3.0, egui::Color32::from_rgb(r, g, b));
+                            ui.label(crate::naming::generate_name(&ui_data.weather_material));
+                            if ui.button("Use brush material").clicked() {
+                                actions.weather_use_brush = true;
+                            }
+                        });
+                        ui.weak(format!("{} falling dots", ui_data.weather_dots));
+                    });
+
+                    ui.collapsing("Groups", |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(new_group_name).desired_width(100.0));
+                            if ui.button("New").clicked() {
+                                actions.group_action = Some(GroupAction::Create(std::mem::take(new_group_name)));
+                            }
+                        });
+
+                        egui::ComboBox::from_label("Brush group")
+                            .selected_text(group_name(&ui_data.groups, ui_data.brush_group))
+                            .show_ui(ui, |ui| {
+                                for id in std::iter::once(None).chain(ui_data.groups.iter().map(|group| Some(group.id))) {
+                                    if ui
+                                        .selectable_label(ui_data.brush_group == id, group_name(&ui_data.groups, id))
+                                        .clicked()
+                                    {
+                                        actions.group_action = Some(GroupAction::SetBrushGroup(id));
+                                    }
+                                }
+                            });
+
+                        egui::Grid::new("groups_grid")
+                            .num_columns(5)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for group in &ui_data.groups {
+                                    ui.label(&group.name).on_hover_text(format!(
+                                        "Mean temperature: {}\nSolid / Liquid / Gas: {} / {} / {}",
+                                        settings.temperature.format(group.stats.mean_temperature),
+                                        group.stats.solid,
+                                        group.stats.liquid,
+                                        group.stats.gas,
+                                    ));
+                                    ui.label(format!("{}", group.stats.count));
+
+                                    let mut visible = group.visible;
+                                    if ui.checkbox(&mut visible, "Show").changed() {
+                                        actions.group_action = Some(GroupAction::SetVisible(group.id, visible));
+                                    }
+                                    let mut frozen = group.frozen;
+                                    if ui.checkbox(&mut frozen, "Freeze").changed() {
+                                        actions.group_action = Some(GroupAction::SetFrozen(group.id, frozen));
+                                    }
+                                    if ui
+                                        .button("DEL")
+                                        .on_hover_text("Delete this group and its dots")
+                                        .clicked()
+                                    {
+                                        actions.group_action = Some(GroupAction::Delete(group.id));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                    ui.collapsing("Reactions", |ui| {
+                        ui.label(format!(
+                            "Materials: {}  Reactions: {}",
+                            ui_data.reaction_graph.nodes.len(),
+                            ui_data.reaction_graph.edges.len()
+                        ));
+                        ui.checkbox(&mut reaction_view.open, "Graph view");
+                        ui.checkbox(&mut material_compare.open, "Compare materials");
+                        // ブレンド結果の突然変異 (エントロピーバイアスが高い物質ほど変異しやすい)
+                        let mut mutation = ui_data.mutation;
+                        let rate = ui
+                            .add(egui::Slider::new(&mut mutation.rate, 0.0..=MAX_MUTATION_RATE).text("Mutation rate"))
+                            .on_hover_text("Chance per gene for a material with full entropy bias (0 = off)");
+                        let magnitude = ui.add(
+                            egui::Slider::new(&mut mutation.magnitude, 0.0..=MAX_MUTATION_MAGNITUDE).text("Mutation size"),
+                        );
+                        if rate.changed() || magnitude.changed() {
+                            actions.set_mutation = Some(mutation);
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Export DOT").on_hover_text("Write reaction_graph.dot (Graphviz)").clicked() {
+                                actions.export_reactions = Some(ExportFormat::Dot);
+                            }
+                            if ui.button("Export JSON").on_hover_text("Write reaction_graph.json").clicked() {
+                                actions.export_reactions = Some(ExportFormat::Json);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("Export palette")
+                                .on_hover_text("Write every discovered material to seed_bank.json")
+                                .clicked()
+                            {
+                                actions.command = Some(Command::ExportSeedBank);
+                            }
+                            if ui
+                                .button("Import palette")
+                                .on_hover_text("Add the materials in seed_bank.json (known ones are skipped)")
+                                .clicked()
+                            {
+                                actions.command = Some(Command::ImportSeedBank);
+                            }
+                        });
+                        let naming_style = crate::naming::style();
+                        egui::ComboBox::from_label("Name style")
+                            .selected_text(naming_style.label())
+                            .show_ui(ui, |ui| {
+                                for style in crate::naming::NamingStyle::ALL {
+                                    if ui.selectable_label(naming_style == style, style.label()).clicked() {
+                                        actions.command = Some(Command::SetNamingStyle(style));
+                                    }
+                                }
+                            });
+                        if let Some(status) = &ui_data.reaction_export_status {
+                            ui.weak(status);
+                        }
+                    });
+
+                    ui.collapsing("Interactions", |ui| {
+                        ui.weak("Collision response per state pair (saved to interactions.json)");
+                        let mut matrix = ui_data.interactions;
+                        egui::Grid::new("interaction_matrix_grid")
+                            .num_columns(STATES.len() + 1)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.label("");
+                                for state in STATES {
+                                    ui.strong(format!("{:?}", state));
+                                }
+                                ui.end_row();
+                                for (i, a) in STATES.into_iter().enumerate() {
+                                    ui.strong(format!("{:?}", a));
+                                    for (j, b) in STATES.into_iter().enumerate() {
+                                        let mut interaction = matrix.get(a, b);
+                                        ui.vertical(|ui| {
+                                            egui::ComboBox::from_id_source(("interaction_model", i, j))
+                                                .selected_text(interaction.model.label())
+                                                .show_ui(ui, |ui| {
+                                                    for model in CollisionModel::ALL {
+                                                        ui.selectable_value(&mut interaction.model, model, model.label());
+                                                    }
+                                                });
+                                            ui.checkbox(&mut interaction.heat_transfer, "Heat");
+                                            ui.checkbox(&mut interaction.cohesion, "Cohesion");
+                                        });
+                                        if interaction != matrix.get(a, b) {
+                                            matrix.set(a, b, interaction);
+                                            actions.set_interactions = Some(matrix);
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        if ui.button("Reset to defaults").clicked() {
+                            actions.set_interactions = Some(InteractionMatrix::default());
+                        }
+                    });
+
+                    ui.collapsing("Memory", |ui| {
+                        let memory = &ui_data.memory;
+                        egui::Grid::new("memory_grid").num_columns(2).striped(true).show(ui, |ui| {
+                            let rows = [
+                                ("Dots", memory.dots as u64),
+                                ("Grid", memory.physics.grid as u64),
+                                ("Collision pairs", memory.physics.collision_pairs as u64),
+                                ("Instance data", memory.renderer.instance_data as u64),
+                                ("GPU buffers", memory.renderer.gpu_buffers + memory.physics.gpu_buffers),
+                                ("GPU textures", memory.renderer.gpu_textures),
+                            ];
+                            for (label, bytes) in rows {
+                                ui.label(label);
+                                ui.label(format_bytes(bytes));
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                    ui.collapsing("Gamepad", |ui| {
+                        match &ui_data.gamepad_name {
+                            Some(name) => ui.label(format!("Connected: {}", name)),
+                            None => ui.label("Not connected"),
+                        };
+                        let mut dead_zone = ui_data.gamepad_dead_zone;
+                        if ui.add(egui::Slider::new(&mut dead_zone, 0.0..=MAX_DEAD_ZONE).text("Dead zone")).changed() {
+                            actions.set_gamepad_dead_zone = Some(dead_zone);
+                        }
+                        let mut bindings = ui_data.gamepad_bindings;
+                        egui::Grid::new("gamepad_bindings_grid")
+                            .num_columns(2)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.label("Left Stick");
+                                ui.label("Move cursor");
+                                ui.end_row();
+                                for action in PadAction::ALL {
+                                    let current = bindings.button(action);
+                                    egui::ComboBox::from_id_source(("gamepad_binding", action.label()))
+                                        .selected_text(current.label())
+                                        .show_ui(ui, |ui| {
+                                            for button in PadButton::ALL {
+                                                if ui.selectable_label(button == current, button.label()).clicked() {
+                                                    bindings.assign(action, button);
+                                                }
+                                            }
+                                        });
+                                    ui.label(action.label());
+                                    ui.end_row();
+                                }
+                            });
+                        if bindings != ui_data.gamepad_bindings {
+                            actions.set_gamepad_bindings = Some(bindings);
+                        }
+                        if ui.button("Reset bindings").clicked() {
+                            actions.set_gamepad_bindings = Some(GamepadBindings::default());
+                        }
+                    });
+                });
+
+            actions.set_brush = reaction_view.show(ctx, ui_data.reaction_graph);
+            material_compare.show(ctx, &settings.temperature);
+            actions.dna_edit = dna_editor.show(ctx, &ui_data.brush_dna, ui_data.selected_dot_id);
+
+            // ドットに付けたメモの一覧
+            egui::Window::new("Notes")
+                .open(notes_open)
+                .default_pos(egui::pos2(200.0, 120.0))
+                .default_size([320.0, 200.0])
+                .show(ctx, |ui| {
+                    if ui_data.notes.is_empty() {
+                        ui.weak("Select a dot (right click) and write a note in its window.");
+                        return;
                     }
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        egui::Grid::new("notes_grid").num_columns(3).striped(true).show(ui, |ui| {
+                            for note in &ui_data.notes {
+                                ui.vertical(|ui| {
+                                    ui.strong(&note.name);
+                                    ui.label(&note.text);
+                                });
+                                if ui.small_button("Select").clicked() {
+                                    actions.select_dot = Some(note.id);
+                                }
+                                if ui.small_button("Delete").clicked() {
+                                    actions.set_note = Some((note.id, String::new()));
+                                    note_draft.0 = None; // 選択中のドットなら編集欄を読み直す
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
                 });
 
+            // 実験ジャーナル (自動で記録された節目)
+            egui::Window::new("Journal")
+                .open(journal_open)
+                .default_pos(egui::pos2(200.0, 160.0))
+                .default_size([360.0, 240.0])
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.button("Export Markdown").clicked() {
+                            actions.command = Some(Command::ExportJournal);
+                        }
+                        if let Some(status) = &ui_data.journal_status {
+                            ui.weak(status);
+                        }
+                    });
+                    ui.separator();
+                    if ui_data.journal.entries.is_empty() {
+                        ui.weak("Nothing recorded yet.");
+                        return;
+                    }
+                    egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                        for entry in &ui_data.journal.entries {
+                            ui.horizontal(|ui| {
+                                ui.monospace(format_elapsed(entry.elapsed));
+                                ui.strong(&entry.title);
+                                if entry.screenshot.is_some() {
+                                    ui.weak("📷");
+                                }
+                            });
+                            ui.label(&entry.detail);
+                        }
+                    });
+                });
+
+            // ツール操作のマクロ (ホットキーで押したときはカーソル位置に置く)
+            egui::Window::new("Macros")
+                .open(macros_open)
+                .default_pos(egui::pos2(200.0, 180.0))
+                .resizable(false)
+                .show(ctx, |ui| {
+                    let macros = ui_data.macros;
+                    ui.horizontal(|ui| {
+                        if let Some(recorder) = &macros.recording {
+                            if ui.button("Stop").clicked() {
+                                actions.macro_action = Some(MacroAction::StopRecording);
+                            }
+                            ui.colored_label(egui::Color32::LIGHT_RED, format!("● Recording ({} stamps)", recorder.stamp_count()));
+                        } else if ui.button("Record").on_hover_text("Brush strokes, material and brush changes").clicked() {
+                            actions.macro_action = Some(MacroAction::StartRecording);
+                        }
+                    });
+                    let mut scale = macros.scale;
+                    if ui.add(egui::Slider::new(&mut scale, MIN_MACRO_SCALE..=MAX_MACRO_SCALE).logarithmic(true).text("Scale")).changed() {
+                        actions.macro_action = Some(MacroAction::SetScale(scale));
+                    }
+                    ui.separator();
+
+                    egui::Grid::new("macros_grid").num_columns(5).striped(true).show(ui, |ui| {
+                        for (index, recorded) in macros.library.macros.iter().enumerate() {
+                            let mut name = recorded.name.clone();
+                            if ui.add(egui::TextEdit::singleline(&mut name).desired_width(100.0)).changed() {
+                                actions.macro_action = Some(MacroAction::Rename(index, name));
+                            }
+                            ui.weak(format!("{} stamps", recorded.stamp_count()));
+                            egui::ComboBox::from_id_source(("macro_hotkey", index))
+                                .width(50.0)
+                                .selected_text(recorded.hotkey.map_or("-", MacroKey::label))
+                                .show_ui(ui, |ui| {
+                                    let mut hotkey = recorded.hotkey;
+                                    ui.selectable_value(&mut hotkey, None, "-");
+                                    for key in MacroKey::ALL {
+                                        ui.selectable_value(&mut hotkey, Some(key), key.label());
+                                    }
+                                    if hotkey != recorded.hotkey {
+                                        actions.macro_action = Some(MacroAction::SetHotkey(index, hotkey));
+                                    }
+                                });
+                            if ui.button("Play").on_hover_text("Places at the center of the view").clicked() {
+                                actions.macro_action = Some(MacroAction::Play(index));
+                            }
+                            if ui.button("DEL").clicked() {
+                                actions.macro_action = Some(MacroAction::Delete(index));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    if macros.library.macros.is_empty() {
+                        ui.weak("Record some brush strokes, then bind them to F5 - F12");
+                    }
+                    if let Some(status) = &ui_data.macro_status {
+                        ui.weak(status);
+                    }
+                });
+
+            // 名前を付けて保存した物質 (materials/ に置いたファイルも Reload で読み込む)
+            egui::Window::new("Materials")
+                .open(material_library_open)
+                .default_pos(egui::pos2(200.0, 240.0))
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(material_name_draft).hint_text("Name").desired_width(120.0));
+                        if ui.button("Save brush").on_hover_text("Empty name uses the material's name").clicked() {
+                            actions.library_action = Some(LibraryAction::SaveBrush(std::mem::take(material_name_draft)));
+                        }
+                        if ui
+                            .add_enabled(ui_data.selected_dot_id.is_some(), egui::Button::new("Save selected"))
+                            .on_hover_text("Saves the material of the selected dot")
+                            .clicked()
+                        {
+                            actions.library_action = Some(LibraryAction::SaveSelected(std::mem::take(material_name_draft)));
+                        }
+                    });
+                    ui.separator();
+
+                    // 並べ替えと絞り込み
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source("material_sort")
+                            .width(90.0)
+                            .selected_text(material_sort.label())
+                            .show_ui(ui, |ui| {
+                                for key in SortKey::ALL {
+                                    ui.selectable_value(material_sort, key, key.label());
+                                }
+                            });
+                        egui::ComboBox::from_id_source("material_state_filter")
+                            .width(60.0)
+                            .selected_text(material_filter.state.map_or("All".to_string(), |state| format!("{:?}", state)))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut material_filter.state, None, "All");
+                                for state in [State::Solid, State::Liquid, State::Gas] {
+                                    ui.selectable_value(&mut material_filter.state, Some(state), format!("{:?}", state));
+                                }
+                            });
+                        ui.checkbox(&mut material_filter.luminous_only, "Glowing");
+                    });
+                    ui.add(egui::TextEdit::singleline(&mut material_filter.search).hint_text("Search names").desired_width(f32::INFINITY));
+
+                    let library = ui_data.material_library;
+                    let shown = library.view(*material_sort, material_filter);
+                    if material_filter.is_active() {
+                        ui.weak(format!("{} of {}", shown.len(), library.presets.len()));
+                    }
+                    egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        egui::Grid::new("material_library_grid").num_columns(4).striped(true).show(ui, |ui| {
+                            for index in shown {
+                                let preset = &library.presets[index];
+                                let (r, g, b) = preset.params.get_color_rgb();
+                                let (swatch, _) = ui.allocate_exact_size(egui::vec2(16.0, 16.0), egui::Sense::hover());
+                                ui.painter().rect_filled(swatch, 3.0, egui::Color32::from_rgb(r, g, b));
+                                match material_rename {
+                                    Some((renaming, draft)) if *renaming == index => {
+                                        let response = ui.add(egui::TextEdit::singleline(draft).desired_width(120.0));
+                                        if response.lost_focus() {
+                                            if ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                                                actions.library_action = Some(LibraryAction::Rename(index, draft.clone()));
+                                            }
+                                            *material_rename = None;
+                                        } else {
+                                            response.request_focus();
+                                        }
+                                    }
+                                    _ => {
+                                        // 付けた名前の下に物質の名前を小さく出す
+                                        let class = TemperatureClass::of(preset.params.temperature);
+                                        let generated = crate::naming::generate_name(&preset.dna);
+                                        ui.vertical(|ui| {
+                                            let label = ui.label(&preset.name).on_hover_text(format!(
+                                                "{:?}, {}, density {:.2}\nDouble-click to rename",
+                                                preset.params.state,
+                                                class.label(),
+                                                preset.params.density
+                                            ));
+                                            if label.double_clicked() {
+                                                *material_rename = Some((index, preset.name.clone()));
+                                            }
+                                            if generated != preset.name {
+                                                ui.weak(generated);
+                                            }
+                                        });
+                                    }
+                                }
+                                if ui.button("Use").on_hover_text("Use as the brush material").clicked() {
+                                    actions.library_action = Some(LibraryAction::Use(index));
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.button("Copy").on_hover_text("Duplicate").clicked() {
+                                        actions.library_action = Some(LibraryAction::Duplicate(index));
+                                    }
+                                    let in_use = preset.is_material(&ui_data.brush_dna);
+                                    if ui
+                                        .add_enabled(!in_use, egui::Button::new("DEL"))
+                                        .on_disabled_hover_text("The brush is using this material")
+                                        .clicked()
+                                    {
+                                        actions.library_action = Some(LibraryAction::Delete(index));
+                                    }
+                                });
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    if ui_data.material_library.presets.is_empty() {
+                        ui.weak(format!("Save a material, or drop shared files into {}/", LIBRARY_DIR));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Reload").on_hover_text(format!("Rescan {}/", LIBRARY_DIR)).clicked() {
+                            actions.library_action = Some(LibraryAction::Reload);
+                        }
+                        if let Some(deleted) = library.trash.last() {
+                            if ui.button("Undo delete").on_hover_text(format!("Restore {}", deleted.name)).clicked() {
+                                actions.library_action = Some(LibraryAction::UndoDelete);
+                            }
+                        }
+                        if let Some(status) = &ui_data.material_library_status {
+                            ui.weak(status);
+                        }
+                    });
+                });
+
+            // ログ (RUST_LOG で出力されたもののうち、選んだレベル以上を表示)
+            egui::Window::new("Log")
+                .open(log_open)
+                .default_pos(egui::pos2(200.0, 300.0))
+                .default_size([420.0, 160.0])
+                .show(ctx, |ui| {
+                    egui::ComboBox::from_label("Level")
+                        .selected_text(log_level.as_str())
+                        .show_ui(ui, |ui| {
+                            for level in [
+                                tracing::Level::ERROR,
+                                tracing::Level::WARN,
+                                tracing::Level::INFO,
+                                tracing::Level::DEBUG,
+                                tracing::Level::TRACE,
+                            ] {
+                                ui.selectable_value(log_level, level, level.as_str());
+                            }
+                        });
+                    ui.separator();
+                    egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                        for entry in ui_data.logs.entries(*log_level) {
+                            let color = match entry.level {
+                                tracing::Level::ERROR => egui::Color32::LIGHT_RED,
+                                tracing::Level::WARN => egui::Color32::YELLOW,
+                                tracing::Level::INFO => egui::Color32::LIGHT_GRAY,
+                                _ => egui::Color32::GRAY,
+                            };
+                            ui.colored_label(
+                                color,
+                                format!("{:>8.2} {:<5} {}: {}", entry.elapsed, entry.level.as_str(), entry.target, entry.message),
+                            );
+                        }
+                    });
+                });
+
+            // カメラパスのタイムライン (キーフレームはフォトモード中に K キーで追加する)
+            egui::Window::new("Camera path")
+                .open(camera_path_open)
+                .default_pos(egui::pos2(200.0, 200.0))
+                .resizable(false)
+                .show(ctx, |ui| {
+                    let path = ui_data.camera_path;
+                    let duration = path.duration().max(1.0);
+                    let (timeline, _) = ui.allocate_exact_size(egui::vec2(240.0, 16.0), egui::Sense::hover());
+                    let painter = ui.painter_at(timeline);
+                    painter.rect_filled(timeline, 2.0, egui::Color32::from_gray(40));
+                    let x_at = |time: f64| timeline.left() + timeline.width() * (time / duration) as f32;
+                    for keyframe in path.keyframes() {
+                        painter.circle_filled(egui::pos2(x_at(keyframe.time), timeline.center().y), 4.0, egui::Color32::from_rgb(255, 200, 80));
+                    }
+                    if let Some(playhead) = path.playhead() {
+                        let x = x_at(playhead);
+                        painter.line_segment([egui::pos2(x, timeline.top()), egui::pos2(x, timeline.bottom())], egui::Stroke::new(2.0, egui::Color32::WHITE));
+                    }
+
+                    egui::Grid::new("camera_keyframes_grid").num_columns(4).striped(true).show(ui, |ui| {
+                        for (index, keyframe) in path.keyframes().iter().enumerate() {
+                            let mut time = keyframe.time;
+                            if ui.add(egui::DragValue::new(&mut time).speed(0.05).suffix(" s").range(0.0..=600.0)).changed() {
+                                actions.camera_path = Some(CameraPathAction::SetKeyframeTime(index, time));
+                            }
+                            ui.label(format!("({:.0}, {:.0})", keyframe.center.0, keyframe.center.1));
+                            ui.label(format!("x{:.2}", keyframe.zoom));
+                            if ui.button("DEL").clicked() {
+                                actions.camera_path = Some(CameraPathAction::RemoveKeyframe(index));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    if path.keyframes().is_empty() {
+                        ui.weak("F2 for photo mode, move the camera and press K to add keyframes");
+                    }
+
+                    ui.horizontal(|ui| {
+                        if path.is_playing() {
+                            if ui.button("Stop").clicked() {
+                                actions.camera_path = Some(CameraPathAction::Stop);
+                            }
+                        } else if ui
+                            .add_enabled(path.keyframes().len() >= 2, egui::Button::new("Play"))
+                            .on_hover_text("Plays in photo mode (Esc to stop)")
+                            .clicked()
+                        {
+                            actions.camera_path = Some(CameraPathAction::Play);
+                        }
+                        let mut looping = path.looping;
+                        if ui.checkbox(&mut looping, "Loop").changed() {
+                            actions.camera_path = Some(CameraPathAction::SetLooping(looping));
+                        }
+                        if ui.button("Clear").clicked() {
+                            actions.camera_path = Some(CameraPathAction::Clear);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").on_hover_text("Write camera_path.json").clicked() {
+                            actions.camera_path = Some(CameraPathAction::Save);
+                        }
+                        if ui.button("Load").on_hover_text("Read camera_path.json").clicked() {
+                            actions.camera_path = Some(CameraPathAction::Load);
+                        }
+                    });
+                });
+
+            // 同梱プリセットの一覧 (サムネイルは領域を物質の色で塗って描く)
+            egui::Window::new("Gallery")
+                .open(gallery_open)
+                .default_pos(egui::pos2(200.0, 60.0))
+                .resizable(false)
+                .show(ctx, |ui| {
+                    egui::Grid::new("gallery_grid").num_columns(2).spacing([8.0, 8.0]).show(ui, |ui| {
+                        for (index, preset) in ui_data.presets.iter().enumerate() {
+                            let thumbnail = preset_thumbnail(ui, preset).on_hover_text(&preset.description);
+                            ui.vertical(|ui| {
+                                ui.strong(&preset.name);
+                                ui.label(egui::RichText::new(&preset.description).small());
+                                if ui.button("Load").clicked() || thumbnail.clicked() {
+                                    actions.load_preset = Some(index);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+                });
+
+            // 錬金術パズルの目標と進捗
+            if let Some(puzzle) = ui_data.puzzle {
+                egui::Window::new("Puzzle")
+                    .default_pos(egui::pos2(420.0, 10.0))
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            let (r, g, b) = puzzle.target_material.get_color_rgb();
+                            let (swatch, _) = ui.allocate_exact_size(egui::vec2(24.0, 24.0), egui::Sense::hover());
+                            ui.painter().rect_filled(swatch, 4.0, egui::Color32::from_rgb(r, g, b));
+                            ui.vertical(|ui| {
+                                ui.strong(&puzzle.target_name);
+                                ui.label(format!("{:?}", puzzle.target_material.state));
+                            });
+                        });
+
+                        egui::Grid::new("puzzle_ranges_grid")
+                            .num_columns(2)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (label, min, max) in puzzle.ranges() {
+                                    ui.label(label);
+                                    ui.label(format!("{:.2} - {:.2}", min, max));
+                                    ui.end_row();
+                                }
+                            });
+
+                        ui.separator();
+                        if puzzle.completed_at.is_some() {
+                            ui.colored_label(egui::Color32::LIGHT_GREEN, "Completed!");
+                        }
+                        ui.label(format!("Best match: {:.0}%", puzzle.best_similarity * 100.0));
+                        if let Some(name) = &puzzle.best_name {
+                            ui.weak(name);
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("New puzzle").clicked() {
+                                actions.new_puzzle = true;
+                            }
+                            if ui.button("Quit").clicked() {
+                                actions.quit_puzzle = true;
+                            }
+                        });
+                    });
+            }
+
+            // 流れ場: セルの平均速度を線で、平均温度を色 (青=低温, 赤=高温) で表示する
+            if !ui_data.flow_field.is_empty() {
+                let pixels_per_point = ctx.pixels_per_point();
+                let painter = ctx.layer_painter(egui::LayerId::new(
+                    egui::Order::Background,
+                    egui::Id::new("flow_field"),
+                ));
+                for cell in &ui_data.flow_field {
+                    let (x, y) = ui_data.camera.world_to_screen((cell.x, cell.y));
+                    let start = egui::pos2(x as f32 / pixels_per_point, y as f32 / pixels_per_point);
+                    let scale = 0.1 * ui_data.camera.zoom as f32 / pixels_per_point; // 速度 (px/秒) を線の長さに
+                    let end = start + egui::vec2(cell.vx as f32, cell.vy as f32) * scale;
+                    let color = heat_color(cell.temperature);
+                    painter.line_segment([start, end], egui::Stroke::new(1.5, color));
+                    painter.circle_filled(start, 1.5, color);
+                }
+            }
+
+            // 流れ場の色の凡例 (選択中の単位で目盛りを付ける)
+            if settings.show_flow {
+                let scale = settings.temperature;
+                egui::Area::new(egui::Id::new("temperature_legend"))
+                    .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.label("Temperature");
+                            let (bar, _) = ui.allocate_exact_size(egui::vec2(160.0, 10.0), egui::Sense::hover());
+                            let steps = 32;
+                            for step in 0..steps {
+                                let t = step as f32 / steps as f32;
+                                let x0 = bar.left() + bar.width() * t;
+                                let x1 = bar.left() + bar.width() * (step + 1) as f32 / steps as f32;
+                                let rect = egui::Rect::from_x_y_ranges(x0..=x1, bar.y_range());
+                                ui.painter().rect_filled(rect, 0.0, heat_color(t * 2.0 - 1.0));
+                            }
+                            ui.horizontal(|ui| {
+                                ui.small(scale.format(-1.0));
+                                ui.add_space(8.0);
+                                ui.small(scale.format(0.0));
+                                ui.add_space(8.0);
+                                ui.small(scale.format(1.0));
+                            });
+                        });
+                    });
+            }
+
+            // メモの付いたドットの目印と、カーソル下のドットのメモ
+            let pixels_per_point = ctx.pixels_per_point();
+            let note_painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("note_markers")));
+            for note in &ui_data.notes {
+                let (x, y) = ui_data.camera.world_to_screen(note.position);
+                let position = egui::pos2(x as f32 / pixels_per_point, y as f32 / pixels_per_point - 6.0);
+                note_painter.text(
+                    position,
+                    egui::Align2::CENTER_BOTTOM,
+                    "✎",
+                    egui::FontId::proportional(12.0),
+                    egui::Color32::from_rgb(255, 230, 120),
+                );
+            }
+            if let (Some(text), Some((x, y))) = (&ui_data.hovered_note, ui_data.cursor_position) {
+                egui::Area::new(egui::Id::new("hovered_note"))
+                    .order(egui::Order::Tooltip)
+                    .fixed_pos(egui::pos2(x as f32 / pixels_per_point + 14.0, y as f32 / pixels_per_point + 14.0))
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.set_max_width(240.0);
+                            ui.label(text);
+                        });
+                    });
+            }
+
+            // ツールの作用範囲
+            if let Some(radius) = ui_data.tool.radius() {
+                if let Some((x, y)) = ui_data.cursor_position {
+                    let pixels_per_point = ctx.pixels_per_point();
+                    let center = egui::pos2(x as f32 / pixels_per_point, y as f32 / pixels_per_point);
+                    let radius = (radius * ui_data.camera.zoom) as f32 / pixels_per_point;
+                    ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("tool_radius")))
+                        .circle_stroke(center, radius, egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 200, 80)));
+                }
+            }
+
+            // 排水口とコンベア (ドラッグ中のものも含む)
+            if !ui_data.drains.is_empty() || !ui_data.conveyors.is_empty() || ui_data.region_preview.is_some() {
+                let pixels_per_point = ctx.pixels_per_point();
+                let to_screen = |world: (f64, f64)| {
+                    let (x, y) = ui_data.camera.world_to_screen(world);
+                    egui::pos2(x as f32 / pixels_per_point, y as f32 / pixels_per_point)
+                };
+                let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("regions")));
+                let color = egui::Color32::from_rgb(80, 160, 255);
+                for drain in ui_data.drains {
+                    let rect = egui::Rect::from_two_pos(to_screen(drain.min), to_screen(drain.max));
+                    painter.rect(rect, 0.0, color.gamma_multiply(0.15), egui::Stroke::new(1.0, color));
+                }
+                let color = egui::Color32::from_rgb(120, 220, 120);
+                for conveyor in ui_data.conveyors {
+                    let rect = egui::Rect::from_two_pos(to_screen(conveyor.min), to_screen(conveyor.max));
+                    painter.rect(rect, 0.0, color.gamma_multiply(0.15), egui::Stroke::new(1.0, color));
+                    // 中央にベルトの向きの矢印
+                    let (ux, uy) = conveyor.direction();
+                    let sign = conveyor.settings.speed.signum() as f32;
+                    let length = if ux > 0.0 { rect.width() } else { rect.height() };
+                    let half = egui::vec2(ux as f32, uy as f32) * sign * (length * 0.25).min(30.0);
+                    painter.arrow(rect.center() - half, half * 2.0, egui::Stroke::new(1.5, color));
+                }
+                if let Some((start, end)) = ui_data.region_preview {
+                    let stroke = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(160));
+                    painter.rect_stroke(egui::Rect::from_two_pos(to_screen(start), to_screen(end)), 0.0, stroke);
+                }
+            }
+
+            // ブラシの範囲
+            if ui_data.tool == Tool::Brush && ui_data.brush.radius > 0.0 {
+                if let Some((x, y)) = ui_data.cursor_position {
+                    let pixels_per_point = ctx.pixels_per_point();
+                    let center = egui::pos2(x as f32 / pixels_per_point, y as f32 / pixels_per_point);
+                    let radius = (ui_data.brush.radius * ui_data.camera.zoom) as f32 / pixels_per_point;
+                    let stroke = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(160));
+                    let painter =
+                        ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("brush_radius")));
+                    match ui_data.brush.shape {
+                        BrushShape::Square => {
+                            painter.rect_stroke(egui::Rect::from_center_size(center, egui::vec2(radius, radius) * 2.0), 0.0, stroke);
+                        }
+                        BrushShape::Circle | BrushShape::Spray => {
+                            painter.circle_stroke(center, radius, stroke);
+                        }
+                    }
+                    if let Some(count) = ui_data.brush_preview_count {
+                        painter.text(
+                            center + egui::vec2(radius, -radius),
+                            egui::Align2::LEFT_BOTTOM,
+                            count.to_string(),
+                            egui::FontId::proportional(12.0),
+                            egui::Color32::from_white_alpha(200),
+                        );
+                    }
+                }
+            }
+
+            // ゲームパッド操作中はOSカーソルが動かないため、仮想カーソルを描画する
+            if ui_data.gamepad_name.is_some() {
+                if let Some((x, y)) = ui_data.cursor_position {
+                    let pixels_per_point = ctx.pixels_per_point();
+                    let center = egui::pos2(x as f32 / pixels_per_point, y as f32 / pixels_per_point);
+                    ctx.layer_painter(egui::LayerId::new(
+                        egui::Order::Foreground,
+                        egui::Id::new("gamepad_cursor"),
+                    ))
+                    .circle_stroke(center, 6.0, egui::Stroke::new(1.5, egui::Color32::WHITE));
+                }
+            }
+
             // ホバーした物質の情報を表示するウィンドウ
             if let Some(material) = &ui_data.selected_material {
                 let window_title = ui_data
@@ -98,6 +1522,45 @@ impl Gui {
                         egui::ScrollArea::vertical().show(ui, |ui| {
                             if let Some(dna) = &ui_data.selected_dot_dna {
                                 ui.label(format!("Seed: {}", dna.seed));
+                                if let Some(half_life) = dna.half_life() {
+                                    ui.colored_label(egui::Color32::LIGHT_GREEN, format!("Radioactive (half-life {:.1}s)", half_life));
+                                } else if dna.unstable {
+                                    ui.colored_label(egui::Color32::YELLOW, "Unstable (decays soon)");
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.button("Compare as A").clicked() {
+                                        material_compare.set_a(dna.clone());
+                                    }
+                                    if ui.button("Compare as B").clicked() {
+                                        material_compare.set_b(dna.clone());
+                                    }
+                                });
+                            }
+
+                            egui::ComboBox::from_label("Group")
+                                .selected_text(group_name(&ui_data.groups, ui_data.selected_dot_group))
+                                .show_ui(ui, |ui| {
+                                    for id in std::iter::once(None).chain(ui_data.groups.iter().map(|group| Some(group.id))) {
+                                        if ui
+                                            .selectable_label(ui_data.selected_dot_group == id, group_name(&ui_data.groups, id))
+                                            .clicked()
+                                        {
+                                            actions.group_action = Some(GroupAction::AssignSelected(id));
+                                        }
+                                    }
+                                });
+
+                            // メモ (選択が変わったら編集欄を読み直す)
+                            if let Some(id) = ui_data.selected_dot_id {
+                                let saved = ui_data.selected_dot_note.clone().unwrap_or_default();
+                                if note_draft.0 != Some(id) {
+                                    *note_draft = (Some(id), saved.clone());
+                                }
+                                ui.label("Note:");
+                                ui.add(egui::TextEdit::multiline(&mut note_draft.1).desired_rows(2).hint_text("Describe the experiment"));
+                                if ui.add_enabled(note_draft.1.trim() != saved, egui::Button::new("Save note")).clicked() {
+                                    actions.set_note = Some((id, note_draft.1.clone()));
+                                }
                             }
                             
                             ui.separator();
@@ -135,7 +1598,7 @@ impl Gui {
                                     ui.heading("Thermal");
                                     ui.end_row();
                                     ui.label("Temperature");
-                                    ui.label(format!("{:.2}", material.temperature));
+                                    ui.label(settings.temperature.format(material.temperature));
                                     ui.end_row();
                                     
                                     ui.label("Heat Conductivity");
@@ -147,6 +1610,34 @@ impl Gui {
                                     ui.label("Heat Capacity Low");
                                     ui.label(format!("{:.2}", material.heat_capacity_low));
                                     ui.end_row();
+                                    ui.label("Thermal Expansion");
+                                    ui.label(format!("{:.2}", material.thermal_expansion));
+                                    ui.end_row();
+                                    ui.label("Melting Point");
+                                    ui.label(settings.temperature.format(material.melting_point));
+                                    ui.end_row();
+                                    ui.label("Boiling Point");
+                                    ui.label(settings.temperature.format(material.boiling_point));
+                                    ui.end_row();
+                                    ui.label("Flammability");
+                                    ui.label(format!("{:.2}", material.flammability));
+                                    ui.end_row();
+
+                                    // --- Electromagnetic ---
+                                    ui.heading("Electromagnetic");
+                                    ui.end_row();
+                                    ui.label("Conductivity");
+                                    ui.label(format!("{:.2}", material.conductivity));
+                                    ui.end_row();
+                                    ui.label("Magnetism");
+                                    ui.label(format!("{:.2}", material.magnetism));
+                                    ui.end_row();
+                                    ui.label("Charge");
+                                    ui.label(format!("{:+.2}", material.charge));
+                                    ui.end_row();
+                                    ui.label("Radioactivity");
+                                    ui.label(format!("{:.2}", material.radioactivity));
+                                    ui.end_row();
 
                                     // --- Optical ---
                                     ui.heading("Optical");
@@ -218,6 +1709,59 @@ impl Gui {
         self.renderer
             .render(&mut render_pass, &tris, &screen_descriptor);
 
-        (randomize_button_clicked, clear_button_clicked) // 戻り値を変更
+        actions
     }
 }
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Window", "Toggle gallery", Command::ToggleGallery);
+    registry.register("Window", "Toggle reaction network graph", Command::ToggleReactionGraph);
+    registry.register("Window", "Toggle material comparison", Command::ToggleMaterialCompare);
+    registry.register("Window", "Toggle DNA editor", Command::ToggleDnaEditor);
+    registry.register("Debug", "Toggle heat flow field", Command::ToggleFlowField);
+    registry.register("Rendering", "Toggle skip glow / composite", Command::ToggleSkipComposite);
+    registry.register("Window", "Toggle log", Command::ToggleLog);
+    registry.register("Window", "Toggle notes", Command::ToggleNotes);
+    registry.register("Window", "Toggle journal", Command::ToggleJournal);
+    registry.register("Window", "Toggle macros", Command::ToggleMacros);
+    registry.register("Window", "Toggle material library", Command::ToggleMaterialLibrary);
+}
+
+/// プリセットの縮小図 (ワールド全体を 128x96 に縮める)
+fn preset_thumbnail(ui: &mut egui::Ui, preset: &Preset) -> egui::Response {
+    let size = egui::vec2(128.0, 96.0);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::BLACK);
+
+    let scale = size.x / crate::app::WIDTH as f32;
+    let to_screen = |x: f64, y: f64| rect.min + egui::vec2(x as f32, y as f32) * scale;
+    for fill in &preset.fills {
+        let (r, g, b) = fill.material().get_color_rgb();
+        let color = egui::Color32::from_rgb(r, g, b);
+        match fill.shape {
+            Shape::Rect { x, y, width, height } => {
+                painter.rect_filled(egui::Rect::from_min_max(to_screen(x, y), to_screen(x + width, y + height)), 0.0, color);
+            }
+            Shape::Circle { x, y, radius } => {
+                painter.circle_filled(to_screen(x, y), radius as f32 * scale, color);
+            }
+        }
+    }
+    response
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= KIB * KIB {
+        format!("{:.1} MiB", bytes / (KIB * KIB))
+    } else {
+        format!("{:.1} KiB", bytes / KIB)
+    }
+}
+
+fn group_name(groups: &[GroupSummary], id: Option<GroupId>) -> String {
+    id.and_then(|id| groups.iter().find(|group| group.id == id))
+        .map_or_else(|| "(none)".to_string(), |group| group.name.clone())
+}
@@ -0,0 +1,159 @@
+//! 2つの物質の比較表示
+//!
+//! 選択した物質を A / B として並べ、すべてのパラメータとその差を表示する。差の大きい行は
+//! 強調し、最後の列には2つを衝突させたときにできる物質 (ブレンド結果) を表示するので、
+//! 反応で何が変わったのかを確認しやすい (突然変異も同じ設定で反映する)。
+
+use super::temperature::TemperatureScale;
+use crate::material::{decide_reaction_type, from_dna, BaseMaterialParams, MaterialDNA};
+
+const HIGHLIGHT_DIFF: f32 = 0.2; // これ以上差がある行を強調する
+const HIGHLIGHT_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 200, 80);
+
+#[derive(Default)]
+pub struct MaterialCompareView {
+    pub open: bool,
+    a: Option<MaterialDNA>,
+    b: Option<MaterialDNA>,
+}
+
+type Field = fn(&BaseMaterialParams) -> f32;
+
+/// 表示する数値パラメータ (見出し, 取り出し方)
+const ROWS: [(&str, Field); 23] = [
+    ("Density", |m| m.density),
+    ("Viscosity", |m| m.viscosity),
+    ("Hardness", |m| m.hardness),
+    ("Elasticity", |m| m.elasticity),
+    ("Temperature", |m| m.temperature),
+    ("Heat Conductivity", |m| m.heat_conductivity),
+    ("Heat Capacity High", |m| m.heat_capacity_high),
+    ("Heat Capacity Low", |m| m.heat_capacity_low),
+    ("Thermal Expansion", |m| m.thermal_expansion),
+    ("Melting Point", |m| m.melting_point),
+    ("Boiling Point", |m| m.boiling_point),
+    ("Flammability", |m| m.flammability),
+    ("Conductivity", |m| m.conductivity),
+    ("Magnetism", |m| m.magnetism),
+    ("Charge", |m| m.charge),
+    ("Radioactivity", |m| m.radioactivity),
+    ("Color Hue", |m| m.color_hue),
+    ("Color Saturation", |m| m.color_saturation),
+    ("Color Luminance", |m| m.color_luminance),
+    ("Luminescence", |m| m.luminescence),
+    ("Entropy Bias", |m| m.entropy_bias),
+    ("Volatility", |m| m.volatility),
+    ("Cohesion", |m| m.cohesion),
+];
+
+impl MaterialCompareView {
+    pub fn set_a(&mut self, dna: MaterialDNA) {
+        self.a = Some(dna);
+        self.open = true;
+    }
+
+    pub fn set_b(&mut self, dna: MaterialDNA) {
+        self.b = Some(dna);
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, temperature: &TemperatureScale) {
+        if !self.open {
+            return;
+        }
+        let mut open = self.open;
+        egui::Window::new("Compare Materials")
+            .open(&mut open)
+            .default_pos(egui::pos2(320.0, 80.0))
+            .resizable(true)
+            .show(ctx, |ui| {
+                if self.a.is_some() && self.b.is_some() && ui.button("Swap A / B").clicked() {
+                    std::mem::swap(&mut self.a, &mut self.b);
+                }
+                let (Some(dna_a), Some(dna_b)) = (&self.a, &self.b) else {
+                    ui.weak("Hover a material and press \"Compare as A\" / \"Compare as B\".");
+                    return;
+                };
+
+                let a = from_dna(dna_a);
+                let b = from_dna(dna_b);
+                // 衝突時と同じ規則でブレンドする (同じ seed 同士は反応しない)
+                let blend = (dna_a.seed != dna_b.seed).then(|| dna_a.blend(dna_b, 0.5).mutated(crate::material::mutation::settings()));
+                let blend_params = blend.as_ref().map(from_dna);
+
+                egui::Grid::new("material_compare_grid")
+                    .num_columns(5)
+                    .spacing([16.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.strong("A");
+                        ui.strong("B");
+                        ui.strong("Δ");
+                        ui.strong("Blend");
+                        ui.end_row();
+
+                        ui.label("Name");
+                        ui.label(crate::naming::generate_name(dna_a));
+                        ui.label(crate::naming::generate_name(dna_b));
+                        ui.label("");
+                        match &blend {
+                            Some(dna) => ui.label(crate::naming::generate_name(dna)),
+                            None => ui.weak("(same seed)"),
+                        };
+                        ui.end_row();
+
+                        ui.label("Seed");
+                        ui.label(dna_a.seed.to_string());
+                        ui.label(dna_b.seed.to_string());
+                        ui.label("");
+                        ui.label(blend.as_ref().map_or(String::new(), |dna| dna.seed.to_string()));
+                        ui.end_row();
+
+                        if a.state == b.state {
+                            ui.label("State");
+                        } else {
+                            ui.colored_label(HIGHLIGHT_COLOR, "State");
+                        }
+                        ui.label(format!("{:?}", a.state));
+                        ui.label(format!("{:?}", b.state));
+                        ui.label("");
+                        ui.label(blend_params.as_ref().map_or(String::new(), |m| format!("{:?}", m.state)));
+                        ui.end_row();
+
+                        ui.label("Reaction");
+                        ui.label("");
+                        ui.label("");
+                        ui.label("");
+                        ui.label(format!("{:?}", decide_reaction_type(a.state, b.state)));
+                        ui.end_row();
+
+                        for (name, value) in ROWS {
+                            let (value_a, value_b) = (value(&a), value(&b));
+                            let diff = value_b - value_a;
+                            let format = |v: f32| {
+                                if matches!(name, "Temperature" | "Melting Point" | "Boiling Point") {
+                                    temperature.format(v)
+                                } else {
+                                    format!("{:.2}", v)
+                                }
+                            };
+                            if diff.abs() >= HIGHLIGHT_DIFF {
+                                ui.colored_label(HIGHLIGHT_COLOR, name);
+                                ui.colored_label(HIGHLIGHT_COLOR, format(value_a));
+                                ui.colored_label(HIGHLIGHT_COLOR, format(value_b));
+                                ui.colored_label(HIGHLIGHT_COLOR, format!("{:+.2}", diff));
+                            } else {
+                                ui.label(name);
+                                ui.label(format(value_a));
+                                ui.label(format(value_b));
+                                ui.weak(format!("{:+.2}", diff));
+                            }
+                            ui.label(blend_params.as_ref().map_or(String::new(), |m| format(value(m))));
+                            ui.end_row();
+                        }
+                    });
+            });
+        self.open = open;
+    }
+}
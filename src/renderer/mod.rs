@@ -1,5 +1,17 @@
+pub mod camera;
+pub mod camera_path;
+pub mod command_palette;
+pub mod dna_editor;
+pub mod frame_graph;
 pub mod gui;
+pub mod material_compare;
 pub mod wgpu_render;
 pub mod orchestrator;
+pub mod picking;
+pub mod reaction_view;
+pub mod shader_permutation;
+pub mod temperature;
+#[cfg(feature = "hot-reload")]
+pub mod shader_watcher;
 
 pub use orchestrator::Renderer;
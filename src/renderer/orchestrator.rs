@@ -1,9 +1,50 @@
-use super::gui::{Gui, UiData};
-use super::wgpu_render::WgpuRenderer;
+use super::camera::Camera;
+use super::gui::{Gui, UiActions, UiData};
+use super::shader_permutation::ShaderFeatures;
+use super::temperature::TemperatureScale;
+use super::wgpu_render::{BrushPreview, PortalRim, RenderQuality, RendererMemory, WgpuRenderer};
 use crate::app::{Dot, HEIGHT, WIDTH};
 use std::sync::Arc;
 use winit::window::Window;
 
+/// HDR から SDR へのトーンマッピング方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tonemapping {
+    #[default]
+    Reinhard,
+    Aces,
+}
+
+/// GUI から変更できる描画オプション
+#[derive(Debug, Clone, Copy)]
+pub struct RenderSettings {
+    pub tonemapping: Tonemapping,
+    pub skip_composite: bool, // グロー・合成パスを省略して直接描画する
+    pub quality: RenderQuality, // 起動時に決定 (表示のみ)
+    pub show_flow: bool,        // 温度による流れ場を重ねて表示する (デバッグ用)
+    pub temperature: TemperatureScale, // GUI での温度の表示単位と換算の幅
+}
+
+impl RenderSettings {
+    fn new(quality: RenderQuality) -> Self {
+        Self {
+            tonemapping: Tonemapping::default(),
+            skip_composite: false,
+            quality,
+            show_flow: false,
+            temperature: TemperatureScale::default(),
+        }
+    }
+
+    /// シェーダーに注入する機能フラグに変換する
+    pub fn shader_features(&self) -> ShaderFeatures {
+        let mut features = ShaderFeatures::NONE;
+        features.set(ShaderFeatures::TONEMAP_ACES, self.tonemapping == Tonemapping::Aces);
+        features.set(ShaderFeatures::NO_GLOW, self.skip_composite);
+        features
+    }
+}
+
 pub struct Renderer {
     surface: Arc<wgpu::Surface<'static>>,
     device: wgpu::Device,
@@ -11,6 +52,7 @@ pub struct Renderer {
     config: wgpu::SurfaceConfiguration,
     wgpu_renderer: WgpuRenderer,
     pub gui: Gui,
+    pub settings: RenderSettings,
 }
 
 impl Renderer {
@@ -21,6 +63,35 @@ impl Renderer {
     pub fn get_queue(&self) -> &wgpu::Queue {
         &self.queue
     }
+
+    pub fn shader_error(&self) -> Option<String> {
+        self.wgpu_renderer.shader_error()
+    }
+
+    pub fn memory_usage(&self) -> RendererMemory {
+        self.wgpu_renderer.memory_usage()
+    }
+
+    /// ピッキングするカーソル位置 (画面座標) を設定する
+    pub fn set_pick_position(&mut self, position: Option<(f64, f64)>) {
+        self.wgpu_renderer.picker.set_position(position);
+    }
+
+    /// 次のフレームで重ねるブラシのプレビュー (カーソルが GUI の上にあるときは描かない)
+    pub fn set_brush_preview(&mut self, preview: Option<&BrushPreview>) {
+        let over_gui = self.gui.ctx.is_pointer_over_area();
+        self.wgpu_renderer.set_brush_preview(preview.filter(|_| !over_gui));
+    }
+
+    /// 次のフレームから描くポータルの縁
+    pub fn set_portal_rims(&mut self, rims: &[PortalRim]) {
+        self.wgpu_renderer.set_portal_rims(rims);
+    }
+
+    /// GPUピッキングで得られたカーソル下のドットID (下位32bit)
+    pub fn hovered_dot_id(&self) -> Option<u32> {
+        self.wgpu_renderer.picker.hovered_dot_id()
+    }
 }
 
 impl Renderer {
@@ -79,7 +150,17 @@ impl Renderer {
         };
         surface.configure(&device, &config);
 
-        let wgpu_renderer = WgpuRenderer::new(&device, config.format);
+        let quality = RenderQuality::from_adapter(&adapter);
+        let adapter_info = adapter.get_info();
+        tracing::info!(
+            target: crate::logging::RENDERER,
+            adapter = %adapter_info.name,
+            backend = ?adapter_info.backend,
+            ?quality,
+            format = ?config.format,
+            "renderer initialized"
+        );
+        let wgpu_renderer = WgpuRenderer::new(&device, config.format, quality);
         let gui = Gui::new(event_loop, &device, config.format);
 
         Self {
@@ -89,6 +170,7 @@ impl Renderer {
             config,
             wgpu_renderer,
             gui,
+            settings: RenderSettings::new(quality),
         }
     }
 
@@ -97,10 +179,16 @@ impl Renderer {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
+            self.wgpu_renderer.picker.resize_surface(new_size.width, new_size.height);
         }
     }
 
-    pub fn render(&mut self, window: &Window, dots: &[Dot], ui_data: &UiData, time: f32) -> (bool, bool) {
+    /// interpolation は物理ステップ間の位置の補間率 (Dot::render_position を参照)
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(&mut self, window: &Window, dots: &[Dot], interpolation: f64, camera: &Camera, ui_data: &UiData, time: f32) -> UiActions {
+        // 前フレームのピッキング結果を取り込む
+        self.wgpu_renderer.picker.poll(&self.device);
+
         let frame = self
             .surface
             .get_current_texture()
@@ -131,23 +219,28 @@ impl Renderer {
             &mut encoder,
             &view,
             dots,
+            interpolation,
+            camera,
+            self.settings.shader_features(),
             time,
             max_volatility,
             max_entropy_bias,
         );
 
-        let (randomize_clicked, clear_clicked) = self.gui.render(
+        let actions = self.gui.render(
             window,
             &self.device,
             &self.queue,
             &mut encoder,
             &view,
             ui_data,
+            &mut self.settings,
         );
 
         self.queue.submit(std::iter::once(encoder.finish()));
+        self.wgpu_renderer.picker.after_submit();
         frame.present();
 
-        (randomize_clicked, clear_clicked)
+        actions
     }
 }
\ No newline at end of file
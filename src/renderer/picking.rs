@@ -0,0 +1,194 @@
+//! GPU の ID バッファによるドットのピッキング
+//!
+//! ドット描画パスでインスタンスごとの ID (ドットID + 1、背景は 0) を R32Uint のテクスチャに書き込み、
+//! カーソル下の1ピクセルだけをステージングバッファにコピーして非同期に読み戻す。
+//! 結果は1~2フレーム遅れるが、描画された形状どおりに判定できる。
+//! ID テクスチャはドット描画パスの出力先 (中間テクスチャかサーフェス) と同じサイズに合わせる。
+
+use crate::app::{HEIGHT, WIDTH};
+use std::sync::mpsc;
+
+pub const ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+
+pub struct DotPicker {
+    id_texture: wgpu::Texture,
+    pub id_texture_view: wgpu::TextureView,
+    size: (u32, u32),
+    surface_size: (u32, u32),
+    staging_buffer: wgpu::Buffer,
+    requested_position: Option<(f64, f64)>, // ウィンドウ座標
+    copy_encoded: bool, // このフレームでコピーを記録したか
+    in_flight: bool,    // map_async の完了待ちか
+    map_result_tx: mpsc::Sender<Result<(), wgpu::BufferAsyncError>>,
+    map_result_rx: mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    hovered_dot_id: Option<u32>,
+}
+
+impl DotPicker {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let (id_texture, id_texture_view) = Self::create_id_texture(device, (WIDTH, HEIGHT));
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Dot ID Staging Buffer"),
+            size: std::mem::size_of::<u32>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let (map_result_tx, map_result_rx) = mpsc::channel();
+
+        Self {
+            id_texture,
+            id_texture_view,
+            size: (WIDTH, HEIGHT),
+            surface_size: (WIDTH, HEIGHT),
+            staging_buffer,
+            requested_position: None,
+            copy_encoded: false,
+            in_flight: false,
+            map_result_tx,
+            map_result_rx,
+            hovered_dot_id: None,
+        }
+    }
+
+    fn create_id_texture(device: &wgpu::Device, (width, height): (u32, u32)) -> (wgpu::Texture, wgpu::TextureView) {
+        let id_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Dot ID Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ID_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let id_texture_view = id_texture.create_view(&Default::default());
+        (id_texture, id_texture_view)
+    }
+
+    pub fn resize_surface(&mut self, width: u32, height: u32) {
+        self.surface_size = (width, height);
+    }
+
+    pub fn surface_size(&self) -> (u32, u32) {
+        self.surface_size
+    }
+
+    /// ドット描画パスの出力先と同じサイズの ID テクスチャを用意する
+    pub fn prepare(&mut self, device: &wgpu::Device, size: (u32, u32)) {
+        if self.size != size {
+            (self.id_texture, self.id_texture_view) = Self::create_id_texture(device, size);
+            self.size = size;
+        }
+    }
+
+    pub fn texture_size_bytes(&self) -> u64 {
+        self.size.0 as u64 * self.size.1 as u64 * ID_FORMAT.block_copy_size(None).unwrap_or(4) as u64
+    }
+
+    pub fn staging_size_bytes(&self) -> u64 {
+        self.staging_buffer.size()
+    }
+
+    /// ピッキングするカーソル位置 (ウィンドウ座標)。None ならピッキングしない
+    pub fn set_position(&mut self, position: Option<(f64, f64)>) {
+        self.requested_position = position;
+        if position.is_none() {
+            self.hovered_dot_id = None;
+        }
+    }
+
+    /// ウィンドウ座標を ID テクスチャのピクセルに変換する
+    fn requested_pixel(&self) -> Option<(u32, u32)> {
+        let (x, y) = self.requested_position?;
+        let x = x * self.size.0 as f64 / self.surface_size.0 as f64;
+        let y = y * self.size.1 as f64 / self.surface_size.1 as f64;
+        if x < 0.0 || y < 0.0 || x >= self.size.0 as f64 || y >= self.size.1 as f64 {
+            return None;
+        }
+        Some((x as u32, y as u32))
+    }
+
+    /// 最後に読み戻せたカーソル下のドットID (下位32bit)
+    pub fn hovered_dot_id(&self) -> Option<u32> {
+        self.hovered_dot_id
+    }
+
+    /// 前のフレームで要求した読み戻しが完了していれば結果を取り込む
+    pub fn poll(&mut self, device: &wgpu::Device) {
+        if !self.in_flight {
+            return;
+        }
+        device.poll(wgpu::Maintain::Poll);
+
+        let Ok(result) = self.map_result_rx.try_recv() else {
+            return;
+        };
+        self.in_flight = false;
+        if result.is_err() {
+            return;
+        }
+
+        let pick_id = {
+            let mapped = self.staging_buffer.slice(..).get_mapped_range();
+            u32::from_ne_bytes([mapped[0], mapped[1], mapped[2], mapped[3]])
+        };
+        self.staging_buffer.unmap();
+
+        // カーソルが外れた後に届いた結果は捨てる
+        if self.requested_position.is_some() {
+            self.hovered_dot_id = pick_id.checked_sub(1);
+        }
+    }
+
+    /// ID テクスチャの描画後に呼ぶ。読み戻し中でなければカーソル下のピクセルをコピーする
+    pub fn encode_copy(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some((x, y)) = self.requested_pixel().filter(|_| !self.in_flight) else {
+            return;
+        };
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.id_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &self.staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: None, // 1行のみなので不要
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.copy_encoded = true;
+    }
+
+    /// コマンドの送信後に呼ぶ。コピーを記録していれば読み戻しを開始する
+    pub fn after_submit(&mut self) {
+        if !self.copy_encoded {
+            return;
+        }
+        self.copy_encoded = false;
+        self.in_flight = true;
+
+        let tx = self.map_result_tx.clone();
+        self.staging_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
+    }
+}
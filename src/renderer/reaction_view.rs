@@ -0,0 +1,208 @@
+//! 反応ネットワークのノードグラフ表示
+//!
+//! 見つかった物質をその色のノード、ブレンドを親から子への矢印として描く。
+//! ドラッグでパン、ホイールでズームでき、ノードをクリックするとその物質をブラシに設定する。
+//! ノードの配置は新しい物質を親の下に置き、ばねモデルで少しずつ整える。
+
+use crate::material::MaterialDNA;
+use crate::reaction_graph::ReactionGraph;
+use std::collections::HashMap;
+
+const NODE_RADIUS: f32 = 8.0;
+const EDGE_LENGTH: f32 = 60.0; // ばねの自然長 (グラフ座標)
+const MAX_LAYOUT_NODES: usize = 300; // これより多い場合は配置の調整を止める (O(n^2) のため)
+
+pub struct ReactionGraphView {
+    pub open: bool,
+    positions: HashMap<u64, egui::Vec2>, // seed -> グラフ座標
+    pan: egui::Vec2,
+    zoom: f32,
+}
+
+impl Default for ReactionGraphView {
+    fn default() -> Self {
+        Self {
+            open: false,
+            positions: HashMap::new(),
+            pan: egui::Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl ReactionGraphView {
+    /// ウィンドウを表示し、クリックされた物質を返す
+    pub fn show(&mut self, ctx: &egui::Context, graph: &ReactionGraph) -> Option<MaterialDNA> {
+        if !self.open {
+            return None;
+        }
+        self.update_layout(graph);
+
+        let mut open = self.open;
+        let mut picked = None;
+        egui::Window::new("Reaction Network")
+            .open(&mut open)
+            .default_size([420.0, 320.0])
+            .default_pos(egui::pos2(200.0, 120.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Materials: {}  Reactions: {}", graph.nodes.len(), graph.edges.len()));
+                    if ui.button("Reset view").clicked() {
+                        self.pan = egui::Vec2::ZERO;
+                        self.zoom = 1.0;
+                    }
+                });
+                ui.weak("Drag: pan / Wheel: zoom / Click: set brush");
+
+                let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+                let center = response.rect.center();
+
+                if response.dragged() {
+                    self.pan += response.drag_delta();
+                }
+                // カーソル位置を固定したままズームする
+                if let Some(pointer) = response.hover_pos() {
+                    let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+                    if scroll != 0.0 {
+                        let anchor = (pointer - center - self.pan) / self.zoom;
+                        self.zoom = (self.zoom * (scroll * 0.002).exp()).clamp(0.2, 4.0);
+                        self.pan = pointer - center - anchor * self.zoom;
+                    }
+                }
+
+                let to_screen = |position: egui::Vec2| center + self.pan + position * self.zoom;
+                let radius = NODE_RADIUS * self.zoom.max(0.5);
+
+                // --- 辺 ---
+                let edge_stroke = egui::Stroke::new(1.0, egui::Color32::from_gray(140));
+                for edge in &graph.edges {
+                    let Some(child) = self.positions.get(&edge.child).map(|&p| to_screen(p)) else {
+                        continue;
+                    };
+                    for parent in edge.parents {
+                        let Some(parent) = self.positions.get(&parent).map(|&p| to_screen(p)) else {
+                            continue;
+                        };
+                        let direction = child - parent;
+                        let length = direction.length();
+                        if length > radius * 2.0 {
+                            // ノードの縁から縁まで矢印を引く
+                            let unit = direction / length;
+                            painter.arrow(parent + unit * radius, unit * (length - radius * 2.0), edge_stroke);
+                        }
+                    }
+                }
+
+                // --- ノード ---
+                let hovered = response.hover_pos().and_then(|pointer| {
+                    graph.nodes.iter().find(|node| {
+                        self.positions
+                            .get(&node.seed)
+                            .is_some_and(|&p| to_screen(p).distance(pointer) <= radius)
+                    })
+                });
+                for node in &graph.nodes {
+                    let Some(&position) = self.positions.get(&node.seed) else {
+                        continue;
+                    };
+                    let (r, g, b) = node.color;
+                    let is_hovered = hovered.is_some_and(|hovered| hovered.seed == node.seed);
+                    let stroke = if is_hovered {
+                        egui::Stroke::new(2.0, egui::Color32::WHITE)
+                    } else {
+                        egui::Stroke::new(1.0, egui::Color32::from_gray(60))
+                    };
+                    painter.circle(to_screen(position), radius, egui::Color32::from_rgb(r, g, b), stroke);
+                }
+
+                if let Some(node) = hovered {
+                    let position = to_screen(self.positions[&node.seed]);
+                    painter.text(
+                        position + egui::vec2(0.0, radius + 2.0),
+                        egui::Align2::CENTER_TOP,
+                        format!("{} ({:?})", node.name, node.state),
+                        egui::FontId::proportional(12.0),
+                        egui::Color32::WHITE,
+                    );
+                    if response.clicked() {
+                        picked = Some(node.dna.clone());
+                    }
+                }
+            });
+        self.open = open;
+
+        picked
+    }
+
+    /// 新しいノードに初期位置を与え、ばねモデルで配置を1ステップ整える
+    fn update_layout(&mut self, graph: &ReactionGraph) {
+        // 子 -> 親 (最初に見つかった反応)
+        let mut parents_of: HashMap<u64, [u64; 2]> = HashMap::new();
+        for edge in &graph.edges {
+            parents_of.entry(edge.child).or_insert(edge.parents);
+        }
+
+        for (index, node) in graph.nodes.iter().enumerate() {
+            if self.positions.contains_key(&node.seed) {
+                continue;
+            }
+            // seed から決まるずれ (-20 ~ 20) で重なりを避ける
+            let jitter = (node.seed % 41) as f32 - 20.0;
+            let parent_positions: Vec<egui::Vec2> = parents_of
+                .get(&node.seed)
+                .into_iter()
+                .flatten()
+                .filter_map(|parent| self.positions.get(parent).copied())
+                .collect();
+            let position = if parent_positions.is_empty() {
+                // 親が分からない物質 (手で置いたもの) は上段に並べる
+                egui::vec2((index % 10) as f32 * 50.0 - 225.0, (index / 10) as f32 * 50.0 - 120.0)
+            } else {
+                let sum = parent_positions.iter().fold(egui::Vec2::ZERO, |sum, &p| sum + p);
+                sum / parent_positions.len() as f32 + egui::vec2(jitter, EDGE_LENGTH)
+            };
+            self.positions.insert(node.seed, position);
+        }
+
+        if graph.nodes.len() > MAX_LAYOUT_NODES {
+            return;
+        }
+
+        let seeds: Vec<u64> = graph.nodes.iter().map(|node| node.seed).collect();
+        let mut forces = vec![egui::Vec2::ZERO; seeds.len()];
+        let index_of: HashMap<u64, usize> = seeds.iter().enumerate().map(|(i, &seed)| (seed, i)).collect();
+
+        // ノード同士の反発
+        for i in 0..seeds.len() {
+            for j in (i + 1)..seeds.len() {
+                let delta = self.positions[&seeds[i]] - self.positions[&seeds[j]];
+                let distance_sq = delta.length_sq().max(1.0);
+                let force = delta / distance_sq.sqrt() * (EDGE_LENGTH * EDGE_LENGTH / distance_sq);
+                forces[i] += force;
+                forces[j] -= force;
+            }
+        }
+        // 反応でつながったノードを自然長に引き寄せる
+        for edge in &graph.edges {
+            let child = index_of[&edge.child];
+            for parent in edge.parents {
+                let parent = index_of[&parent];
+                if parent == child {
+                    continue;
+                }
+                let delta = self.positions[&seeds[child]] - self.positions[&seeds[parent]];
+                let distance = delta.length().max(1.0);
+                let force = delta / distance * (distance - EDGE_LENGTH) * 0.05;
+                forces[parent] += force;
+                forces[child] -= force;
+            }
+        }
+
+        for (seed, force) in seeds.iter().zip(forces) {
+            if let Some(position) = self.positions.get_mut(seed) {
+                // 1フレームの移動量を制限して振動を防ぐ
+                *position += force.clamp(egui::vec2(-2.0, -2.0), egui::vec2(2.0, 2.0));
+            }
+        }
+    }
+}
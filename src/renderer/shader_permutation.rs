@@ -0,0 +1,112 @@
+//! シェーダーのパーミュテーション (機能フラグによる #ifdef 切り替え) とパイプラインキャッシュ
+//!
+//! WGSL にはプリプロセッサが無いため、読み込み時に `#ifdef NAME` / `#ifndef NAME` /
+//! `#else` / `#endif` を解釈して有効な行だけを残す。
+//! パイプラインは機能フラグの組み合わせごとに初回使用時に作成し、以降は使い回す。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// シェーダーに注入する機能フラグのビットセット
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ShaderFeatures(u32);
+
+impl ShaderFeatures {
+    pub const NONE: Self = Self(0);
+    /// Reinhard の代わりに ACES Filmic でトーンマッピングする (composite.wgsl)
+    pub const TONEMAP_ACES: Self = Self(1 << 0);
+    /// グローを出力せずサーフェスに直接描画する (dot.wgsl、ブラー・合成パスを省略)
+    pub const NO_GLOW: Self = Self(1 << 1);
+
+    /// フラグと WGSL 側の定義名の対応
+    const DEFINES: &'static [(Self, &'static str)] = &[
+        (Self::TONEMAP_ACES, "TONEMAP_ACES"),
+        (Self::NO_GLOW, "NO_GLOW"),
+    ];
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn set(&mut self, other: Self, enabled: bool) {
+        if enabled {
+            self.0 |= other.0;
+        } else {
+            self.0 &= !other.0;
+        }
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    fn is_defined(self, name: &str) -> bool {
+        Self::DEFINES
+            .iter()
+            .any(|(flag, define)| *define == name && self.contains(*flag))
+    }
+}
+
+/// 機能フラグに応じて条件ブロックを展開する
+/// 取り除いた行は空行に置き換え、コンパイルエラーの行番号が元ファイルと一致するようにする
+pub fn preprocess(source: &str, features: ShaderFeatures) -> String {
+    // (親ブロックが有効か, このブロックが有効か)
+    let mut stack: Vec<(bool, bool)> = Vec::new();
+    let mut output = String::with_capacity(source.len());
+
+    for line in source.lines() {
+        let trimmed = line.trim();
+        let active = stack.last().is_none_or(|&(parent, current)| parent && current);
+
+        if let Some(name) = trimmed.strip_prefix("#ifdef ") {
+            stack.push((active, features.is_defined(name.trim())));
+        } else if let Some(name) = trimmed.strip_prefix("#ifndef ") {
+            stack.push((active, !features.is_defined(name.trim())));
+        } else if trimmed == "#else" {
+            if let Some((_, current)) = stack.last_mut() {
+                *current = !*current;
+            }
+        } else if trimmed == "#endif" {
+            stack.pop();
+        } else if active {
+            output.push_str(line);
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// 機能フラグの組み合わせごとのレンダーパイプラインのキャッシュ
+pub struct PipelineCache {
+    relevant: ShaderFeatures, // このパイプラインに影響するフラグ (それ以外はキーから除外)
+    pipelines: HashMap<ShaderFeatures, Arc<wgpu::RenderPipeline>>,
+}
+
+impl PipelineCache {
+    pub fn new(relevant: ShaderFeatures) -> Self {
+        Self {
+            relevant,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /// キャッシュ済みならそれを返し、無ければ create で作成して登録する
+    pub fn get_or_create(
+        &mut self,
+        features: ShaderFeatures,
+        create: impl FnOnce(ShaderFeatures) -> wgpu::RenderPipeline,
+    ) -> Arc<wgpu::RenderPipeline> {
+        let key = features.intersection(self.relevant);
+        self.pipelines
+            .entry(key)
+            .or_insert_with(|| Arc::new(create(key)))
+            .clone()
+    }
+
+    /// シェーダーのソースが変わった場合などに全パイプラインを破棄する
+    #[cfg_attr(not(feature = "hot-reload"), allow(dead_code))]
+    pub fn clear(&mut self) {
+        self.pipelines.clear();
+    }
+}
@@ -0,0 +1,61 @@
+//! 開発用: shaders/ ディレクトリの変更監視 (`hot-reload` フィーチャー)
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct ShaderWatcher {
+    dir: PathBuf,
+    modified: HashMap<PathBuf, SystemTime>,
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> Self {
+        let mut watcher = Self {
+            dir: PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/shaders")),
+            modified: HashMap::new(),
+            last_poll: Instant::now(),
+        };
+        // 起動時点の更新時刻を記録しておき、最初のポーリングで再コンパイルしないようにする
+        watcher.scan();
+        watcher
+    }
+
+    /// 一定間隔で .wgsl ファイルの更新時刻を確認し、変更があれば true を返す
+    pub fn poll_changed(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+        self.scan()
+    }
+
+    /// シェーダーのソースを読み込む。読めない場合は空文字列 (コンパイルエラーとして報告される)
+    pub fn read(&self, file_name: &str) -> String {
+        std::fs::read_to_string(self.dir.join(file_name)).unwrap_or_default()
+    }
+
+    fn scan(&mut self) -> bool {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return false;
+        };
+
+        let mut changed = false;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("wgsl") {
+                continue;
+            }
+            let Ok(modified) = entry.metadata().and_then(|meta| meta.modified()) else {
+                continue;
+            };
+            if self.modified.insert(path, modified) != Some(modified) {
+                changed = true;
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,73 @@
+//! 温度の表示 (見た目だけ)
+//!
+//! シミュレーション内の温度は -1.0 ~ 1.0 の抽象的な値なので、GUI では 0.0 を常温とした
+//! 擬似的な摂氏 (または華氏) に換算して表示する。換算の幅は GUI から変更できる。
+
+/// GUI に表示する温度の単位
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    Raw, // シミュレーション内の値 (-1.0 ~ 1.0) のまま
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    pub const ALL: [TemperatureUnit; 3] = [TemperatureUnit::Raw, TemperatureUnit::Celsius, TemperatureUnit::Fahrenheit];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TemperatureUnit::Raw => "Raw",
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+}
+
+/// 温度 -1.0 / 0.0 / 1.0 をそれぞれ何 °C として表示するか
+/// (間は線形に補間する。常温の前後で幅が違ってもよい)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperatureScale {
+    pub unit: TemperatureUnit,
+    pub cold_celsius: f32,
+    pub ambient_celsius: f32,
+    pub hot_celsius: f32,
+}
+
+impl Default for TemperatureScale {
+    fn default() -> Self {
+        Self {
+            unit: TemperatureUnit::default(),
+            cold_celsius: -100.0,
+            ambient_celsius: 20.0,
+            hot_celsius: 1000.0,
+        }
+    }
+}
+
+impl TemperatureScale {
+    /// シミュレーション内の温度を擬似的な摂氏に換算する
+    pub fn celsius(&self, temperature: f32) -> f32 {
+        let t = temperature.clamp(-1.0, 1.0);
+        if t >= 0.0 {
+            self.ambient_celsius + (self.hot_celsius - self.ambient_celsius) * t
+        } else {
+            self.ambient_celsius + (self.ambient_celsius - self.cold_celsius) * t
+        }
+    }
+
+    /// 選択中の単位で温度を表示する文字列 (すべての GUI パネルでこれを使う)
+    pub fn format(&self, temperature: f32) -> String {
+        match self.unit {
+            TemperatureUnit::Raw => format!("{:.2}", temperature),
+            TemperatureUnit::Celsius => format!("{:.0} °C", self.celsius(temperature)),
+            TemperatureUnit::Fahrenheit => format!("{:.0} °F", self.celsius(temperature) * 9.0 / 5.0 + 32.0),
+        }
+    }
+}
+
+/// 温度を表示色にする (青=低温, 赤=高温)。流れ場と凡例で共通
+pub fn heat_color(temperature: f32) -> egui::Color32 {
+    let heat = ((temperature + 1.0) / 2.0).clamp(0.0, 1.0);
+    egui::Color32::from_rgb((heat * 255.0) as u8, 64, ((1.0 - heat) * 255.0) as u8)
+}
@@ -1,3 +1,7 @@
+use super::camera::Camera;
+use super::frame_graph::{Binding, DrawCall, FrameGraph, Target, TextureDesc, TexturePool};
+use super::picking::{DotPicker, ID_FORMAT};
+use super::shader_permutation::{preprocess, PipelineCache, ShaderFeatures};
 use crate::app::{Dot, HEIGHT, WIDTH};
 use bytemuck::{Pod, Zeroable};
 use wgpu::util::DeviceExt;
@@ -20,32 +24,141 @@ struct CompositeUniforms {
 struct DotUniforms {
     time: f32,
     max_entropy_bias: f32,
-    _padding: [f32; 2],
+    camera_center: [f32; 2],
+    camera_zoom: f32,
+    _padding: [f32; 3],
+}
+
+// インスタンスデータ1つあたりのバイトサイズ (position, color, luminescence, is_selected, temperature, state, cohesion, entropy_bias, pick_id, radius, radioactivity)
+const DOT_INSTANCE_STRIDE: wgpu::BufferAddress =
+    (2 + 3 + 1 + 1 + 2 + 1 + 1 + 1 + 1 + 1) as wgpu::BufferAddress * std::mem::size_of::<f32>() as wgpu::BufferAddress;
+
+const DOT_INSTANCE_ATTRIBUTES: [wgpu::VertexAttribute; 11] = [
+    wgpu::VertexAttribute { offset: 0, shader_location: 1, format: wgpu::VertexFormat::Float32x2, }, // position
+    wgpu::VertexAttribute { offset: (2 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 2, format: wgpu::VertexFormat::Float32x3, }, // color
+    wgpu::VertexAttribute { offset: (5 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 3, format: wgpu::VertexFormat::Float32, }, // luminescence
+    wgpu::VertexAttribute { offset: (6 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 4, format: wgpu::VertexFormat::Float32, }, // is_selected
+    wgpu::VertexAttribute { offset: (7 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 5, format: wgpu::VertexFormat::Float32, }, // temperature
+    wgpu::VertexAttribute { offset: (8 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 6, format: wgpu::VertexFormat::Float32, }, // state
+    wgpu::VertexAttribute { offset: (9 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 7, format: wgpu::VertexFormat::Float32, }, // cohesion
+    wgpu::VertexAttribute { offset: (10 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 8, format: wgpu::VertexFormat::Float32, }, // entropy_bias
+    wgpu::VertexAttribute { offset: (11 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 9, format: wgpu::VertexFormat::Uint32, }, // pick_id
+    wgpu::VertexAttribute { offset: (12 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 10, format: wgpu::VertexFormat::Float32, }, // radius
+    wgpu::VertexAttribute { offset: (13 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 11, format: wgpu::VertexFormat::Float32, }, // radioactivity
+];
+
+// ブラシのプレビューのインスタンスデータ (position, color, radius, state)
+const PREVIEW_INSTANCE_STRIDE: wgpu::BufferAddress =
+    (2 + 3 + 1 + 1) as wgpu::BufferAddress * std::mem::size_of::<f32>() as wgpu::BufferAddress;
+
+const PREVIEW_INSTANCE_ATTRIBUTES: [wgpu::VertexAttribute; 4] = [
+    wgpu::VertexAttribute { offset: 0, shader_location: 1, format: wgpu::VertexFormat::Float32x2, }, // position
+    wgpu::VertexAttribute { offset: (2 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 2, format: wgpu::VertexFormat::Float32x3, }, // color
+    wgpu::VertexAttribute { offset: (5 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 3, format: wgpu::VertexFormat::Float32, }, // radius
+    wgpu::VertexAttribute { offset: (6 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 4, format: wgpu::VertexFormat::Float32, }, // state
+];
+
+/// クリックしたときにブラシが置くドット (半透明で重ねて描く)
+#[derive(Debug, Clone)]
+pub struct BrushPreview {
+    pub positions: Vec<(f64, f64)>, // ワールド座標
+    pub color: (u8, u8, u8),
+    pub radius: f64,
+    pub state: crate::material::State,
+}
+
+/// ポータルの縁 (光るリングとしてドットと一緒に描き、グローパスでにじませる)
+#[derive(Debug, Clone, Copy)]
+pub struct PortalRim {
+    pub center: (f64, f64), // ワールド座標
+    pub radius: f64,
+    pub color: (u8, u8, u8),
+}
+
+const RIM_SPACING: f64 = 3.0; // 縁に並べる光点の間隔
+const RIM_DOT_RADIUS: f32 = 1.2;
+
+/// 描画品質 (起動時にアダプタの種類と制限から自動で選択する)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderQuality {
+    /// Rgba16Float の中間バッファ、フル解像度のグロー
+    High,
+    /// Rgba8Unorm の中間バッファ、半解像度のグロー (内蔵GPU向け)
+    Low,
+}
+
+impl RenderQuality {
+    pub fn from_adapter(adapter: &wgpu::Adapter) -> Self {
+        let is_weak_device = matches!(
+            adapter.get_info().device_type,
+            wgpu::DeviceType::IntegratedGpu | wgpu::DeviceType::Cpu
+        );
+        // WebGL2 / GLES 相当の制限しか持たないアダプタも低スペック扱い
+        let has_low_limits = adapter.limits().max_texture_dimension_2d
+            < wgpu::Limits::default().max_texture_dimension_2d;
+
+        if is_weak_device || has_low_limits {
+            RenderQuality::Low
+        } else {
+            RenderQuality::High
+        }
+    }
+
+    fn intermediate_format(self) -> wgpu::TextureFormat {
+        match self {
+            RenderQuality::High => wgpu::TextureFormat::Rgba16Float,
+            RenderQuality::Low => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    /// グローをぼかす解像度の縮小率
+    fn glow_divisor(self) -> u32 {
+        match self {
+            RenderQuality::High => 1,
+            RenderQuality::Low => 2,
+        }
+    }
+}
+
+/// WgpuRenderer が確保しているメモリ (バイト数)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RendererMemory {
+    pub instance_data: usize, // CPU 側のインスタンスデータ
+    pub gpu_buffers: u64,
+    pub gpu_textures: u64,
 }
 
 #[allow(dead_code)]
 pub struct WgpuRenderer {
-    dot_render_pipeline: wgpu::RenderPipeline,
+    dot_pipelines: PipelineCache,
+    dot_source: String,
     dot_pipeline_layout: wgpu::PipelineLayout,
-    dot_bind_group: wgpu::BindGroup,
+    dot_bind_group_layout: wgpu::BindGroupLayout,
     dot_uniform_buffer: wgpu::Buffer,
     square_vertex_buffer: wgpu::Buffer,
 
     dot_instance_buffer: Option<wgpu::Buffer>,
     dot_instance_buffer_capacity: usize,
     dot_instance_data_size_bytes: wgpu::BufferAddress, // インスタンスデータ1つあたりのバイトサイズ
+    instance_data: Vec<f32>, // 毎フレーム詰め直す (フレームをまたいで再利用する)
+
+    preview_pipeline: wgpu::RenderPipeline,
+    preview_instance_buffer: Option<wgpu::Buffer>,
+    preview_instance_buffer_capacity: usize,
+    preview_data: Vec<f32>, // 空ならプレビューを描かない
+    rim_data: Vec<f32>,     // ポータルの縁 (ドットのインスタンスデータの後ろに足す)
 
-    scene_texture: wgpu::Texture,
-    scene_texture_view: wgpu::TextureView,
-    glow_texture: wgpu::Texture,
-    glow_texture_view: wgpu::TextureView,
-    blur_ping_pong_texture: wgpu::Texture,
-    blur_ping_pong_texture_view: wgpu::TextureView,
+    texture_pool: TexturePool, // 中間テクスチャ (scene / glow / blur) の実体
+    pub picker: DotPicker,
+    scene_desc: TextureDesc,
+    glow_desc: TextureDesc, // ブラーはグロー解像度 (低品質時は半分) で行う
 
     texture_sampler: wgpu::Sampler,
 
-    composite_pipeline: wgpu::RenderPipeline,
-    composite_bind_group: wgpu::BindGroup,
+    composite_pipelines: PipelineCache,
+    composite_source: String,
+    composite_pipeline_layout: wgpu::PipelineLayout,
+    composite_bind_group_layout: wgpu::BindGroupLayout,
     composite_uniform_buffer: wgpu::Buffer,
 
     blur_pipeline_layout: wgpu::PipelineLayout,
@@ -53,54 +166,47 @@ pub struct WgpuRenderer {
     blur_bind_group_layout: wgpu::BindGroupLayout,
     blur_horizontal_pipeline: wgpu::RenderPipeline,
     blur_vertical_pipeline: wgpu::RenderPipeline,
-    blur_bind_group_horizontal: wgpu::BindGroup,
-    blur_bind_group_vertical: wgpu::BindGroup,
+
+    intermediate_format: wgpu::TextureFormat,
+    surface_format: wgpu::TextureFormat,
+
+    #[cfg(feature = "hot-reload")]
+    shader_watcher: super::shader_watcher::ShaderWatcher,
+    #[cfg(feature = "hot-reload")]
+    shader_error: Option<String>,
 }
 
 impl WgpuRenderer {
-    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
-        // --- テクスチャの作成 ---
-        let texture_desc = wgpu::TextureDescriptor {
-            label: Some("Scene Texture"),
-            size: wgpu::Extent3d {
-                width: WIDTH,
-                height: HEIGHT,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba16Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat, quality: RenderQuality) -> Self {
+        // --- 中間テクスチャの形式 (実体はフレームグラフが割り当てる) ---
+        let intermediate_format = quality.intermediate_format();
+        let scene_desc = TextureDesc { width: WIDTH, height: HEIGHT, format: intermediate_format };
+        let glow_desc = TextureDesc {
+            width: WIDTH / quality.glow_divisor(),
+            height: HEIGHT / quality.glow_divisor(),
+            format: intermediate_format,
         };
-        let scene_texture = device.create_texture(&texture_desc);
-        let scene_texture_view = scene_texture.create_view(&Default::default());
-
-        let glow_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Glow Texture"),
-            ..texture_desc
-        });
-        let glow_texture_view = glow_texture.create_view(&Default::default());
-
-        let blur_ping_pong_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Blur Ping-Pong Texture"),
-            ..texture_desc
-        });
-        let blur_ping_pong_texture_view = blur_ping_pong_texture.create_view(&Default::default());
 
+        // 半解像度のグローを拡大する場合は線形補間でサンプリングする
+        let filter_mode = match quality {
+            RenderQuality::High => wgpu::FilterMode::Nearest,
+            RenderQuality::Low => wgpu::FilterMode::Linear,
+        };
         let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Texture Sampler"),
+            mag_filter: filter_mode,
+            min_filter: filter_mode,
             ..Default::default()
         });
 
-        // --- ドット描画パイプライン ---
-        let dot_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Dot shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/dot.wgsl").into()),
-        });
-
-        let dot_uniforms = DotUniforms { time: 0.0, max_entropy_bias: 0.0, _padding: [0.0; 2] };
+        // --- ドット描画パイプライン (パイプライン本体は初回描画時に作成) ---
+        let dot_uniforms = DotUniforms {
+            time: 0.0,
+            max_entropy_bias: 0.0,
+            camera_center: [WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0],
+            camera_zoom: 1.0,
+            _padding: [0.0; 3],
+        };
         let dot_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Dot Uniform Buffer"),
             contents: bytemuck::bytes_of(&dot_uniforms),
@@ -122,72 +228,12 @@ impl WgpuRenderer {
                 }],
             });
 
-        let dot_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Dot Bind Group"),
-            layout: &dot_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: dot_uniform_buffer.as_entire_binding(),
-            }],
-        });
-
         let dot_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Dot Pipeline Layout"),
             bind_group_layouts: &[&dot_bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let instance_layout_stride = (2 + 3 + 1 + 1 + 2 + 1 + 1) as wgpu::BufferAddress
-            * std::mem::size_of::<f32>() as wgpu::BufferAddress;
-
-        let instance_layout = wgpu::VertexBufferLayout {
-            array_stride: instance_layout_stride,
-            step_mode: wgpu::VertexStepMode::Instance,
-            attributes: &[
-                wgpu::VertexAttribute { offset: 0, shader_location: 1, format: wgpu::VertexFormat::Float32x2, }, // position
-                wgpu::VertexAttribute { offset: (2 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 2, format: wgpu::VertexFormat::Float32x3, }, // color
-                wgpu::VertexAttribute { offset: (5 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 3, format: wgpu::VertexFormat::Float32, }, // luminescence
-                wgpu::VertexAttribute { offset: (6 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 4, format: wgpu::VertexFormat::Float32, }, // is_selected
-                wgpu::VertexAttribute { offset: (7 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 5, format: wgpu::VertexFormat::Float32, }, // temperature
-                wgpu::VertexAttribute { offset: (8 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 6, format: wgpu::VertexFormat::Float32, }, // state
-                wgpu::VertexAttribute { offset: (9 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 7, format: wgpu::VertexFormat::Float32, }, // cohesion
-                wgpu::VertexAttribute { offset: (10 * std::mem::size_of::<f32>()) as wgpu::BufferAddress, shader_location: 8, format: wgpu::VertexFormat::Float32, }, // entropy_bias
-            ],
-        };
-
-        let dot_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Dot render pipeline"),
-            layout: Some(&dot_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &dot_shader_module,
-                entry_point: "vs_main",
-                buffers: &[
-                    wgpu::VertexBufferLayout {
-                        array_stride: 2 * std::mem::size_of::<f32>() as wgpu::BufferAddress,
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &[wgpu::VertexAttribute {
-                            offset: 0,
-                            shader_location: 0,
-                            format: wgpu::VertexFormat::Float32x2,
-                        }],
-                    },
-                    instance_layout,
-                ],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &dot_shader_module,
-                entry_point: "fs_main",
-                targets: &[
-                    Some(wgpu::ColorTargetState { format: texture_desc.format, blend: Some(wgpu::BlendState::REPLACE), write_mask: wgpu::ColorWrites::ALL, }),
-                    Some(wgpu::ColorTargetState { format: texture_desc.format, blend: Some(wgpu::BlendState::REPLACE), write_mask: wgpu::ColorWrites::ALL, }),
-                ],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState { topology: wgpu::PrimitiveTopology::TriangleStrip, ..Default::default() },
-            depth_stencil: None, multisample: wgpu::MultisampleState::default(), multiview: None,
-        });
-
         let square_vertex_data: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
         let square_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Square Vertex Buffer"),
@@ -195,6 +241,13 @@ impl WgpuRenderer {
             usage: wgpu::BufferUsages::VERTEX,
         });
 
+        // --- ブラシのプレビューのパイプライン (ドットと同じユニフォームを使う) ---
+        let preview_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Brush preview shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/brush_preview.wgsl").into()),
+        });
+        let preview_pipeline = Self::create_preview_pipeline(device, &dot_pipeline_layout, &preview_shader_module, surface_format);
+
         // --- ブラーパイプライン ---
         let blur_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Blur shader"),
@@ -246,60 +299,10 @@ impl WgpuRenderer {
             push_constant_ranges: &[],
         });
 
-        let blur_horizontal_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Blur Horizontal Pipeline"),
-            layout: Some(&blur_pipeline_layout),
-            vertex: wgpu::VertexState { module: &blur_shader_module, entry_point: "vs_main", buffers: &[], compilation_options: Default::default(), },
-            fragment: Some(wgpu::FragmentState {
-                module: &blur_shader_module,
-                entry_point: "fs_horizontal_blur",
-                targets: &[Some(wgpu::ColorTargetState { format: texture_desc.format, blend: Some(wgpu::BlendState::REPLACE), write_mask: wgpu::ColorWrites::ALL, })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState { topology: wgpu::PrimitiveTopology::TriangleList, ..Default::default() },
-            depth_stencil: None, multisample: wgpu::MultisampleState::default(), multiview: None,
-        });
-
-        let blur_vertical_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Blur Vertical Pipeline"),
-            layout: Some(&blur_pipeline_layout),
-            vertex: wgpu::VertexState { module: &blur_shader_module, entry_point: "vs_main", buffers: &[], compilation_options: Default::default(), },
-            fragment: Some(wgpu::FragmentState {
-                module: &blur_shader_module,
-                entry_point: "fs_vertical_blur",
-                targets: &[Some(wgpu::ColorTargetState { format: texture_desc.format, blend: Some(wgpu::BlendState::REPLACE), write_mask: wgpu::ColorWrites::ALL, })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState { topology: wgpu::PrimitiveTopology::TriangleList, ..Default::default() },
-            depth_stencil: None, multisample: wgpu::MultisampleState::default(), multiview: None,
-        });
-
-        let blur_bind_group_horizontal = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Blur Horizontal Bind Group"),
-            layout: &blur_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&glow_texture_view) },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&texture_sampler) },
-                wgpu::BindGroupEntry { binding: 2, resource: blur_uniform_buffer.as_entire_binding() },
-            ],
-        });
-
-        let blur_bind_group_vertical = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Blur Vertical Bind Group"),
-            layout: &blur_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&blur_ping_pong_texture_view) },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&texture_sampler) },
-                wgpu::BindGroupEntry { binding: 2, resource: blur_uniform_buffer.as_entire_binding() },
-            ],
-        });
-
-        // --- 合成パイプライン ---
-        let composite_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Composite shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/composite.wgsl").into()),
-        });
+        let (blur_horizontal_pipeline, blur_vertical_pipeline) =
+            Self::create_blur_pipelines(device, &blur_pipeline_layout, &blur_shader_module, intermediate_format);
 
+        // --- 合成パイプライン (パイプライン本体は初回描画時に作成) ---
         let composite_uniforms = CompositeUniforms {
             falloff_exponent: 1.5, // デフォルト値
         };
@@ -327,51 +330,327 @@ impl WgpuRenderer {
                 push_constant_ranges: &[],
             });
 
-        let composite_pipeline =
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Composite render pipeline"),
-                layout: Some(&composite_pipeline_layout),
-                vertex: wgpu::VertexState { module: &composite_shader_module, entry_point: "vs_main", buffers: &[], compilation_options: Default::default(), },
-                fragment: Some(wgpu::FragmentState {
-                    module: &composite_shader_module,
-                    entry_point: "fs_main",
-                    targets: &[Some(wgpu::ColorTargetState { format: surface_format, blend: Some(wgpu::BlendState::REPLACE), write_mask: wgpu::ColorWrites::ALL, })],
-                    compilation_options: Default::default(),
-                }),
-                primitive: wgpu::PrimitiveState { topology: wgpu::PrimitiveTopology::TriangleList, ..Default::default() },
-                depth_stencil: None, multisample: wgpu::MultisampleState::default(), multiview: None,
-            });
-
-        let composite_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Composite Bind Group"),
-            layout: &composite_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&scene_texture_view), },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&glow_texture_view), },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&texture_sampler), },
-                wgpu::BindGroupEntry { binding: 3, resource: composite_uniform_buffer.as_entire_binding(), },
-            ],
-        });
-
         Self {
-            dot_render_pipeline, dot_pipeline_layout, dot_bind_group, dot_uniform_buffer, square_vertex_buffer,
+            dot_pipelines: PipelineCache::new(ShaderFeatures::NO_GLOW),
+            dot_source: include_str!("../../shaders/dot.wgsl").to_string(),
+            dot_pipeline_layout, dot_bind_group_layout, dot_uniform_buffer, square_vertex_buffer,
             dot_instance_buffer: None,
             dot_instance_buffer_capacity: 0,
-            dot_instance_data_size_bytes: instance_layout_stride,
-            scene_texture, scene_texture_view,
-            glow_texture, glow_texture_view,
-            blur_ping_pong_texture, blur_ping_pong_texture_view,
+            instance_data: Vec::new(),
+            dot_instance_data_size_bytes: DOT_INSTANCE_STRIDE,
+            preview_pipeline,
+            preview_instance_buffer: None,
+            preview_instance_buffer_capacity: 0,
+            preview_data: Vec::new(),
+            rim_data: Vec::new(),
+            texture_pool: TexturePool::default(),
+            picker: DotPicker::new(device),
+            scene_desc, glow_desc,
             texture_sampler,
-            composite_pipeline, composite_bind_group, composite_uniform_buffer,
+            composite_pipelines: PipelineCache::new(ShaderFeatures::TONEMAP_ACES),
+            composite_source: include_str!("../../shaders/composite.wgsl").to_string(),
+            composite_pipeline_layout, composite_bind_group_layout, composite_uniform_buffer,
             blur_pipeline_layout, blur_uniform_buffer, blur_bind_group_layout,
             blur_horizontal_pipeline, blur_vertical_pipeline,
-            blur_bind_group_horizontal, blur_bind_group_vertical,
+            intermediate_format,
+            surface_format,
+            #[cfg(feature = "hot-reload")]
+            shader_watcher: super::shader_watcher::ShaderWatcher::new(),
+            #[cfg(feature = "hot-reload")]
+            shader_error: None,
+        }
+    }
+
+    /// 機能フラグを展開してシェーダーモジュールを作成する
+    fn create_shader_module(device: &wgpu::Device, label: &str, source: &str, features: ShaderFeatures) -> wgpu::ShaderModule {
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(preprocess(source, features).into()),
+        })
+    }
+
+    /// NO_GLOW 時はサーフェスに直接描画するため、出力先のフォーマットと数が変わる
+    fn create_dot_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        dot_shader_module: &wgpu::ShaderModule,
+        features: ShaderFeatures,
+        intermediate_format: wgpu::TextureFormat,
+        surface_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let scene_target = |format| Some(wgpu::ColorTargetState { format, blend: Some(wgpu::BlendState::REPLACE), write_mask: wgpu::ColorWrites::ALL, });
+        // 最後のターゲットはピッキング用のID (整数テクスチャはブレンド不可)
+        let id_target = Some(wgpu::ColorTargetState { format: ID_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL, });
+        let targets = if features.contains(ShaderFeatures::NO_GLOW) {
+            vec![scene_target(surface_format), id_target]
+        } else {
+            vec![scene_target(intermediate_format), scene_target(intermediate_format), id_target]
+        };
+
+        let instance_layout = wgpu::VertexBufferLayout {
+            array_stride: DOT_INSTANCE_STRIDE,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &DOT_INSTANCE_ATTRIBUTES,
+        };
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Dot render pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: dot_shader_module,
+                entry_point: "vs_main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: 2 * std::mem::size_of::<f32>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                        }],
+                    },
+                    instance_layout,
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: dot_shader_module,
+                entry_point: "fs_main",
+                targets: &targets,
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState { topology: wgpu::PrimitiveTopology::TriangleStrip, ..Default::default() },
+            depth_stencil: None, multisample: wgpu::MultisampleState::default(), multiview: None,
+        })
+    }
+
+    /// プレビューは合成後のサーフェスにアルファブレンドで重ねる (ピッキング用IDは書かない)
+    fn create_preview_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        preview_shader_module: &wgpu::ShaderModule,
+        surface_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Brush preview pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: preview_shader_module,
+                entry_point: "vs_main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: 2 * std::mem::size_of::<f32>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[wgpu::VertexAttribute { offset: 0, shader_location: 0, format: wgpu::VertexFormat::Float32x2, }],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: PREVIEW_INSTANCE_STRIDE,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &PREVIEW_INSTANCE_ATTRIBUTES,
+                    },
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: preview_shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState { format: surface_format, blend: Some(wgpu::BlendState::ALPHA_BLENDING), write_mask: wgpu::ColorWrites::ALL, })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState { topology: wgpu::PrimitiveTopology::TriangleStrip, ..Default::default() },
+            depth_stencil: None, multisample: wgpu::MultisampleState::default(), multiview: None,
+        })
+    }
+
+    fn create_blur_pipelines(
+        device: &wgpu::Device,
+        blur_pipeline_layout: &wgpu::PipelineLayout,
+        blur_shader_module: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+    ) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+        let blur_horizontal_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Blur Horizontal Pipeline"),
+            layout: Some(blur_pipeline_layout),
+            vertex: wgpu::VertexState { module: blur_shader_module, entry_point: "vs_main", buffers: &[], compilation_options: Default::default(), },
+            fragment: Some(wgpu::FragmentState {
+                module: blur_shader_module,
+                entry_point: "fs_horizontal_blur",
+                targets: &[Some(wgpu::ColorTargetState { format, blend: Some(wgpu::BlendState::REPLACE), write_mask: wgpu::ColorWrites::ALL, })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState { topology: wgpu::PrimitiveTopology::TriangleList, ..Default::default() },
+            depth_stencil: None, multisample: wgpu::MultisampleState::default(), multiview: None,
+        });
+
+        let blur_vertical_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Blur Vertical Pipeline"),
+            layout: Some(blur_pipeline_layout),
+            vertex: wgpu::VertexState { module: blur_shader_module, entry_point: "vs_main", buffers: &[], compilation_options: Default::default(), },
+            fragment: Some(wgpu::FragmentState {
+                module: blur_shader_module,
+                entry_point: "fs_vertical_blur",
+                targets: &[Some(wgpu::ColorTargetState { format, blend: Some(wgpu::BlendState::REPLACE), write_mask: wgpu::ColorWrites::ALL, })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState { topology: wgpu::PrimitiveTopology::TriangleList, ..Default::default() },
+            depth_stencil: None, multisample: wgpu::MultisampleState::default(), multiview: None,
+        });
+
+        (blur_horizontal_pipeline, blur_vertical_pipeline)
+    }
+
+    fn create_composite_pipeline(
+        device: &wgpu::Device,
+        composite_pipeline_layout: &wgpu::PipelineLayout,
+        composite_shader_module: &wgpu::ShaderModule,
+        surface_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Composite render pipeline"),
+            layout: Some(composite_pipeline_layout),
+            vertex: wgpu::VertexState { module: composite_shader_module, entry_point: "vs_main", buffers: &[], compilation_options: Default::default(), },
+            fragment: Some(wgpu::FragmentState {
+                module: composite_shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState { format: surface_format, blend: Some(wgpu::BlendState::REPLACE), write_mask: wgpu::ColorWrites::ALL, })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState { topology: wgpu::PrimitiveTopology::TriangleList, ..Default::default() },
+            depth_stencil: None, multisample: wgpu::MultisampleState::default(), multiview: None,
+        })
+    }
+
+    /// シェーダーのコンパイルエラー (ホットリロード時のみ)
+    #[cfg(feature = "hot-reload")]
+    pub fn shader_error(&self) -> Option<String> {
+        self.shader_error.clone()
+    }
+
+    #[cfg(not(feature = "hot-reload"))]
+    pub fn shader_error(&self) -> Option<String> {
+        None
+    }
+
+    pub fn memory_usage(&self) -> RendererMemory {
+        let gpu_buffers = [
+            Some(&self.dot_uniform_buffer),
+            Some(&self.square_vertex_buffer),
+            Some(&self.blur_uniform_buffer),
+            Some(&self.composite_uniform_buffer),
+            self.dot_instance_buffer.as_ref(),
+            self.preview_instance_buffer.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(wgpu::Buffer::size)
+        .sum::<u64>();
+        RendererMemory {
+            instance_data: (self.instance_data.capacity() + self.preview_data.capacity() + self.rim_data.capacity())
+                * std::mem::size_of::<f32>(),
+            gpu_buffers: gpu_buffers + self.picker.staging_size_bytes(),
+            gpu_textures: self.texture_pool.size_bytes() + self.picker.texture_size_bytes(),
         }
     }
 
-    fn create_dot_instance_data(dots: &[Dot]) -> Vec<f32> {
-        let mut instance_data: Vec<f32> = Vec::with_capacity(dots.len() * 11);
-        for dot in dots {
+    /// shaders/ の変更を検知したら dot / blur / composite パイプラインを作り直す
+    /// コンパイルに失敗した場合は古いパイプラインを使い続け、エラーを保持する
+    /// 検証には現在の機能フラグのパーミュテーションを使い、成功したらキャッシュを破棄する
+    #[cfg(feature = "hot-reload")]
+    fn reload_shaders_if_changed(&mut self, device: &wgpu::Device, features: ShaderFeatures) {
+        if !self.shader_watcher.poll_changed() {
+            return;
+        }
+
+        let dot_source = self.shader_watcher.read("dot.wgsl");
+        let composite_source = self.shader_watcher.read("composite.wgsl");
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let dot_shader_module = Self::create_shader_module(device, "Dot shader", &dot_source, features);
+        let blur_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blur shader"),
+            source: wgpu::ShaderSource::Wgsl(self.shader_watcher.read("blur.wgsl").into()),
+        });
+        let composite_shader_module = Self::create_shader_module(device, "Composite shader", &composite_source, features);
+        let dot_render_pipeline = Self::create_dot_pipeline(
+            device,
+            &self.dot_pipeline_layout,
+            &dot_shader_module,
+            features,
+            self.intermediate_format,
+            self.surface_format,
+        );
+        let (blur_horizontal_pipeline, blur_vertical_pipeline) =
+            Self::create_blur_pipelines(device, &self.blur_pipeline_layout, &blur_shader_module, self.intermediate_format);
+        let composite_pipeline = Self::create_composite_pipeline(
+            device,
+            &self.composite_pipeline_layout,
+            &composite_shader_module,
+            self.surface_format,
+        );
+
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(error) => {
+                tracing::warn!(target: crate::logging::RENDERER, "shader reload failed: {}", error);
+                self.shader_error = Some(error.to_string());
+            }
+            None => {
+                self.dot_source = dot_source;
+                self.composite_source = composite_source;
+                self.dot_pipelines.clear();
+                self.composite_pipelines.clear();
+                self.dot_pipelines.get_or_create(features, |_| dot_render_pipeline);
+                self.composite_pipelines.get_or_create(features, |_| composite_pipeline);
+                self.blur_horizontal_pipeline = blur_horizontal_pipeline;
+                self.blur_vertical_pipeline = blur_vertical_pipeline;
+                self.shader_error = None;
+                tracing::info!(target: crate::logging::RENDERER, "shaders reloaded");
+            }
+        }
+    }
+
+    /// 次のフレームで描くブラシのプレビュー (None なら描かない)
+    pub fn set_brush_preview(&mut self, preview: Option<&BrushPreview>) {
+        self.preview_data.clear();
+        let Some(preview) = preview else {
+            return;
+        };
+        let (r, g, b) = preview.color;
+        let color = [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0];
+        let state_f32 = match preview.state {
+            crate::material::State::Solid => 0.0,
+            crate::material::State::Liquid => 1.0,
+            crate::material::State::Gas => 2.0,
+        };
+        for &(x, y) in &preview.positions {
+            self.preview_data.extend_from_slice(&[x as f32, y as f32]);
+            self.preview_data.extend_from_slice(&color);
+            self.preview_data.push(preview.radius as f32);
+            self.preview_data.push(state_f32);
+        }
+    }
+
+    /// 次のフレームから描くポータルの縁 (ピッキングの対象にはしない)
+    pub fn set_portal_rims(&mut self, rims: &[PortalRim]) {
+        self.rim_data.clear();
+        for rim in rims {
+            let (r, g, b) = rim.color;
+            let color = [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0];
+            let count = ((std::f64::consts::TAU * rim.radius / RIM_SPACING).ceil() as usize).max(8);
+            for k in 0..count {
+                let (sin, cos) = (std::f64::consts::TAU * k as f64 / count as f64).sin_cos();
+                let (x, y) = (rim.center.0 + rim.radius * cos, rim.center.1 + rim.radius * sin);
+                self.rim_data.extend_from_slice(&[x as f32, y as f32]);
+                self.rim_data.extend_from_slice(&color);
+                // 自発光 1.0 / 非選択 / 温度 0 / 固体 / 凝集力・エントロピー 0 / ピッキング ID 0 (背景) / 放射能 0
+                self.rim_data.extend_from_slice(&[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, f32::from_bits(0), RIM_DOT_RADIUS, 0.0]);
+            }
+        }
+    }
+
+    fn fill_dot_instance_data(instance_data: &mut Vec<f32>, dots: &[Dot], interpolation: f64) {
+        instance_data.clear();
+        // 非表示グループのドットは描画しない (ピッキングの対象からも外れる)
+        for dot in dots.iter().filter(|dot| !dot.hidden) {
             let (r, g, b) = dot.material.get_color_rgb();
             let color = [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0];
             let state_f32 = match dot.material.state {
@@ -379,8 +658,9 @@ impl WgpuRenderer {
                 crate::material::State::Liquid => 1.0,
                 crate::material::State::Gas => 2.0,
             };
-            instance_data.push(dot.x as f32);
-            instance_data.push(dot.y as f32);
+            let (x, y) = dot.render_position(interpolation);
+            instance_data.push(x as f32);
+            instance_data.push(y as f32);
             instance_data.extend_from_slice(&color);
             instance_data.push(dot.material.luminescence);
             let is_selected = if dot.is_selected { 1.0 } else { 0.0 };
@@ -389,13 +669,36 @@ impl WgpuRenderer {
             instance_data.push(state_f32);
             instance_data.push(dot.material.cohesion);
             instance_data.push(dot.material.entropy_bias);
+            // ピッキング用ID (0 は背景)。シェーダー側では u32 として読むのでビット列のまま格納する
+            instance_data.push(f32::from_bits((dot.id as u32).wrapping_add(1)));
+            instance_data.push(dot.radius() as f32);
+            instance_data.push(dot.material.radioactivity);
         }
-        instance_data
     }
 
-    pub fn render(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, dots: &[Dot], time: f32, max_volatility: f32, max_entropy_bias: f32) {
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, dots: &[Dot], interpolation: f64, camera: &Camera, features: ShaderFeatures, time: f32, max_volatility: f32, max_entropy_bias: f32) {
+        #[cfg(feature = "hot-reload")]
+        self.reload_shaders_if_changed(device, features);
+
+        // --- 機能フラグに対応するパイプラインを取得 (未作成ならここで作成) ---
+        let dot_render_pipeline = self.dot_pipelines.get_or_create(features, |features| {
+            let module = Self::create_shader_module(device, "Dot shader", &self.dot_source, features);
+            Self::create_dot_pipeline(device, &self.dot_pipeline_layout, &module, features, self.intermediate_format, self.surface_format)
+        });
+        let composite_pipeline = self.composite_pipelines.get_or_create(features, |features| {
+            let module = Self::create_shader_module(device, "Composite shader", &self.composite_source, features);
+            Self::create_composite_pipeline(device, &self.composite_pipeline_layout, &module, self.surface_format)
+        });
+
         // --- Dot/Blur ユニフォームの更新 ---
-        let dot_uniforms = DotUniforms { time, max_entropy_bias, _padding: [0.0; 2] };
+        let dot_uniforms = DotUniforms {
+            time,
+            max_entropy_bias,
+            camera_center: [camera.center.0 as f32, camera.center.1 as f32],
+            camera_zoom: camera.zoom as f32,
+            _padding: [0.0; 3],
+        };
         queue.write_buffer(&self.dot_uniform_buffer, 0, bytemuck::bytes_of(&dot_uniforms));
 
         let blur_strength = if max_volatility > 0.5 {
@@ -408,10 +711,11 @@ impl WgpuRenderer {
 
 
         // --- ドット描画パス ---
-        if !dots.is_empty() {
-            let instance_data = Self::create_dot_instance_data(dots);
-            let instance_data_bytes = bytemuck::cast_slice(&instance_data);
-            let num_dots = dots.len();
+        Self::fill_dot_instance_data(&mut self.instance_data, dots, interpolation);
+        self.instance_data.extend_from_slice(&self.rim_data);
+        let num_dots = self.instance_data.len() * std::mem::size_of::<f32>() / DOT_INSTANCE_STRIDE as usize;
+        if num_dots > 0 {
+            let instance_data_bytes = bytemuck::cast_slice(&self.instance_data);
 
             // バッファが存在しないか、容量が不足している場合は再作成
             if self.dot_instance_buffer.is_none() || num_dots > self.dot_instance_buffer_capacity {
@@ -428,98 +732,127 @@ impl WgpuRenderer {
                 self.dot_instance_buffer_capacity = new_capacity;
             }
 
-            // データが空でない場合のみ書き込み
-            if !instance_data.is_empty() {
-                if let Some(buffer) = &self.dot_instance_buffer {
-                    queue.write_buffer(buffer, 0, instance_data_bytes);
-                }
+            if let Some(buffer) = &self.dot_instance_buffer {
+                queue.write_buffer(buffer, 0, instance_data_bytes);
             }
+        }
 
-            let instance_buffer_slice = if let Some(buffer) = &self.dot_instance_buffer {
-                buffer.slice(..(num_dots * self.dot_instance_data_size_bytes as usize) as wgpu::BufferAddress)
-            } else {
-                // ドットがない場合、空のスライスを渡す
-                self.square_vertex_buffer.slice(..0) // square_vertex_buffer はダミーとして使用
-            };
-
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Dot Render Pass"),
-                color_attachments: &[
-                    Some(wgpu::RenderPassColorAttachment { view: &self.scene_texture_view, resolve_target: None, ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store, }, }),
-                    Some(wgpu::RenderPassColorAttachment { view: &self.glow_texture_view, resolve_target: None, ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store, }, }),
-                ],
-                depth_stencil_attachment: None, timestamp_writes: None, occlusion_query_set: None,
-            });
+        // --- ブラシのプレビュー (ドットとは別のインスタンスバッファ) ---
+        let num_preview = self.preview_data.len() * std::mem::size_of::<f32>() / PREVIEW_INSTANCE_STRIDE as usize;
+        if num_preview > self.preview_instance_buffer_capacity {
+            let new_capacity = num_preview * 2;
+            self.preview_instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Brush Preview Instance Buffer"),
+                size: new_capacity as wgpu::BufferAddress * PREVIEW_INSTANCE_STRIDE,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            self.preview_instance_buffer_capacity = new_capacity;
+        }
+        if let Some(buffer) = self.preview_instance_buffer.as_ref().filter(|_| num_preview > 0) {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.preview_data));
+        }
 
-            render_pass.set_pipeline(&self.dot_render_pipeline);
-            render_pass.set_bind_group(0, &self.dot_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.square_vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, instance_buffer_slice);
-            render_pass.draw(0..4, 0..dots.len() as u32);
+        // ピッキング用IDはドット描画パスの出力先と同じサイズで書き込む
+        let dot_target_size = if features.contains(ShaderFeatures::NO_GLOW) {
+            self.picker.surface_size()
+        } else {
+            (self.scene_desc.width, self.scene_desc.height)
+        };
+        self.picker.prepare(device, dot_target_size);
+
+        // --- フレームグラフの構築 ---
+        let mut graph = FrameGraph::new();
+        let scene = graph.create_texture("Scene Texture", self.scene_desc);
+        let glow = graph.create_texture("Glow Texture", self.scene_desc);
+        let blur_ping_pong = graph.create_texture("Blur Ping-Pong Texture", self.glow_desc);
+        let blurred_glow = graph.create_texture("Blurred Glow Texture", self.glow_desc);
+
+        // NO_GLOW 時はブラー・合成パスを省略し、サーフェスへ直接描画する
+        let mut dot_pass = graph.add_pass("Dot Render Pass");
+        dot_pass = if features.contains(ShaderFeatures::NO_GLOW) {
+            dot_pass.clear(Target::Surface)
         } else {
-            // ドットがない場合もテクスチャをクリアする
-            let mut _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Clear Pass"),
-                color_attachments: &[
-                    Some(wgpu::RenderPassColorAttachment {
-                        view: &self.scene_texture_view,
-                        resolve_target: None,
-                        ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store, },
-                    }),
-                    Some(wgpu::RenderPassColorAttachment {
-                        view: &self.glow_texture_view,
-                        resolve_target: None,
-                        ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store, },
-                    }),
+            dot_pass.clear(scene).clear(glow)
+        };
+        dot_pass = dot_pass.clear(Target::Imported(&self.picker.id_texture_view));
+        dot_pass = dot_pass
+            .pipeline(&dot_render_pipeline)
+            .bind_group("Dot Bind Group", &self.dot_bind_group_layout, vec![Binding::Buffer(&self.dot_uniform_buffer)]);
+        // ドットがない場合はクリアのみ
+        if let Some(buffer) = self.dot_instance_buffer.as_ref().filter(|_| num_dots > 0) {
+            dot_pass = dot_pass.draw(DrawCall {
+                vertex_buffers: vec![
+                    self.square_vertex_buffer.slice(..),
+                    buffer.slice(..(num_dots * self.dot_instance_data_size_bytes as usize) as wgpu::BufferAddress),
                 ],
-                depth_stencil_attachment: None, timestamp_writes: None, occlusion_query_set: None,
+                vertices: 0..4,
+                instances: 0..num_dots as u32,
             });
         }
+        dot_pass.finish();
+
+        if !features.contains(ShaderFeatures::NO_GLOW) {
+            // --- ブラーパス ---
+            graph
+                .add_pass("Horizontal Blur Pass")
+                .clear(blur_ping_pong)
+                .pipeline(&self.blur_horizontal_pipeline)
+                .bind_group("Blur Horizontal Bind Group", &self.blur_bind_group_layout, vec![
+                    Binding::Texture(glow),
+                    Binding::Sampler(&self.texture_sampler),
+                    Binding::Buffer(&self.blur_uniform_buffer),
+                ])
+                .draw(DrawCall::fullscreen_triangle())
+                .finish();
+            graph
+                .add_pass("Vertical Blur Pass")
+                .clear(blurred_glow)
+                .pipeline(&self.blur_vertical_pipeline)
+                .bind_group("Blur Vertical Bind Group", &self.blur_bind_group_layout, vec![
+                    Binding::Texture(blur_ping_pong),
+                    Binding::Sampler(&self.texture_sampler),
+                    Binding::Buffer(&self.blur_uniform_buffer),
+                ])
+                .draw(DrawCall::fullscreen_triangle())
+                .finish();
+
+            // --- 合成パス ---
+            graph
+                .add_pass("Composite Pass")
+                .load(Target::Surface)
+                .pipeline(&composite_pipeline)
+                .bind_group("Composite Bind Group", &self.composite_bind_group_layout, vec![
+                    Binding::Texture(scene),
+                    Binding::Texture(blurred_glow),
+                    Binding::Sampler(&self.texture_sampler),
+                    Binding::Buffer(&self.composite_uniform_buffer),
+                ])
+                .draw(DrawCall::fullscreen_triangle())
+                .finish();
+        }
 
-        // --- ブラーパス ---
-        // 横ブラー
-        let mut blur_pass_h = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Horizontal Blur Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.blur_ping_pong_texture_view,
-                resolve_target: None,
-                ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store, },
-            })],
-            depth_stencil_attachment: None, timestamp_writes: None, occlusion_query_set: None,
-        });
-        blur_pass_h.set_pipeline(&self.blur_horizontal_pipeline);
-        blur_pass_h.set_bind_group(0, &self.blur_bind_group_horizontal, &[]);
-        blur_pass_h.draw(0..3, 0..1);
-        drop(blur_pass_h);
-
-        // 縦ブラー
-        let mut blur_pass_v = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Vertical Blur Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.glow_texture_view, // 結果をglow_textureに書き戻す
-                resolve_target: None,
-                ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store, },
-            })],
-            depth_stencil_attachment: None, timestamp_writes: None, occlusion_query_set: None,
-        });
-        blur_pass_v.set_pipeline(&self.blur_vertical_pipeline);
-        blur_pass_v.set_bind_group(0, &self.blur_bind_group_vertical, &[]);
-        blur_pass_v.draw(0..3, 0..1);
-        drop(blur_pass_v);
-
-        // --- 合成パス ---
-        let mut composite_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Composite Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target: None,
-                ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: wgpu::StoreOp::Store, },
-            })],
-            depth_stencil_attachment: None, timestamp_writes: None, occlusion_query_set: None,
-        });
+        // --- ブラシのプレビューパス (最後にサーフェスへ重ねる) ---
+        if let Some(buffer) = self.preview_instance_buffer.as_ref().filter(|_| num_preview > 0) {
+            graph
+                .add_pass("Brush Preview Pass")
+                .load(Target::Surface)
+                .pipeline(&self.preview_pipeline)
+                .bind_group("Brush Preview Bind Group", &self.dot_bind_group_layout, vec![Binding::Buffer(&self.dot_uniform_buffer)])
+                .draw(DrawCall {
+                    vertex_buffers: vec![
+                        self.square_vertex_buffer.slice(..),
+                        buffer.slice(..num_preview as wgpu::BufferAddress * PREVIEW_INSTANCE_STRIDE),
+                    ],
+                    vertices: 0..4,
+                    instances: 0..num_preview as u32,
+                })
+                .finish();
+        }
+
+        graph.execute(device, encoder, view, &mut self.texture_pool);
 
-        composite_pass.set_pipeline(&self.composite_pipeline);
-        composite_pass.set_bind_group(0, &self.composite_bind_group, &[]);
-        composite_pass.draw(0..3, 0..1);
+        // --- カーソル下のIDを読み戻す ---
+        self.picker.encode_copy(encoder);
     }
 }
\ No newline at end of file
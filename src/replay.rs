@@ -0,0 +1,244 @@
+//! 入力のリプレイ (記録と再生)
+//!
+//! 記録を始めるとワールドを空にして物理を決定論モードにし、ワールドを変える入力 (ドットの配置・
+//! ブラシの物質・消しゴムとその取り消し・掘る/かき混ぜるツール・排水口/コンベア/ポータル・
+//! ドットの DNA の編集・物理の設定) を、記録開始からの物理ステップ数とともに replay.json に
+//! 書き出す。再生では同じ seed から始めて、同じステップで同じ操作をやり直す。ブラシの物質と形は
+//! 変わったときだけ、ドットを置く直前に記録する。領域と物理の設定は、変わったフレームの最初に丸ごと記録する。
+//! シーン・プリセット・地形の読み込みとグループの操作は記録できないので、使うとその直前で記録を終える。
+
+use crate::brush::Brush;
+use crate::commands::{Command, CommandRegistry};
+use crate::material::mutation::Mutation;
+use crate::material::MaterialDNA;
+use crate::physics::conveyor::Conveyor;
+use crate::physics::drain::Drain;
+use crate::physics::interaction::InteractionMatrix;
+use crate::physics::portal::Portal;
+use crate::physics::thermostat::WallThermostat;
+use crate::tools::Tool;
+use crate::weather::WeatherSettings;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const REPLAY_FILE: &str = "replay.json";
+const FORMAT_VERSION: u32 = 2;
+
+/// コマンドパレットと GUI からの操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayControl {
+    StartRecording,
+    StopRecording, // REPLAY_FILE に保存する
+    Play,          // REPLAY_FILE を再生する
+    StopPlayback,
+}
+
+/// 記録される入力
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ReplayInput {
+    Stamp { x: f64, y: f64 }, // ブラシを1回当てた位置 (ワールド座標)
+    SetMaterial(MaterialDNA),
+    SetBrush(Brush),
+    Randomize(u64), // ブラシの物質をこの seed にランダム化した
+    Clear,
+    Erase { x: f64, y: f64, radius: f64, spare: Option<u64> }, // 消しゴムを当てた位置と消さないドット (消えたドットがあったときだけ)
+    FinishErase,                           // 消しゴムのストロークの終わり (取り消しの単位)
+    UndoErase,
+    HoldTool { tool: Tool, x: f64, y: f64 }, // 掘る・かき混ぜるツールを使い始めた、または動かした
+    ReleaseTool,
+    EditDot { id: u64, dna: MaterialDNA }, // DNA エディタでドットの物質を書き換えた
+    SetDrains(Vec<Drain>),
+    SetConveyors(Vec<Conveyor>),
+    SetPortals(Vec<Portal>),
+    SetSettings(Box<ReplaySettings>),
+}
+
+/// 物理に効く設定 (記録の最初と、変わったときに記録する)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplaySettings {
+    pub convection: bool,
+    pub combustion: bool,
+    pub electricity: bool,
+    pub thermostat: WallThermostat,
+    pub weather: WeatherSettings,
+    pub weather_material: MaterialDNA,
+    pub interactions: InteractionMatrix,
+    pub mutation: Mutation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    pub step: u64,    // 記録開始からの物理ステップ数 (再生はこれに合わせる)
+    pub elapsed: f64, // 記録開始からの秒数 (参考)
+    pub input: ReplayInput,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64, // 物理の乱数の seed
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let replay: Replay = serde_json::from_str(&json).map_err(std::io::Error::other)?;
+        if replay.version > FORMAT_VERSION {
+            return Err(std::io::Error::other(format!("unsupported replay version {}", replay.version)));
+        }
+        Ok(replay)
+    }
+
+    /// 最後の入力のステップ数
+    pub fn duration_steps(&self) -> u64 {
+        self.events.last().map_or(0, |event| event.step)
+    }
+}
+
+pub struct ReplayRecorder {
+    replay: Replay,
+    started: std::time::Instant,
+    material: Option<MaterialDNA>, // 最後に記録したブラシの物質
+    brush: Option<Brush>,          // 最後に記録したブラシの形
+    settings: Option<ReplaySettings>, // 最後に記録した物理の設定
+    drains: Vec<Drain>,            // 最後に記録した領域
+    conveyors: Vec<Conveyor>,
+    portals: Vec<Portal>,
+}
+
+impl ReplayRecorder {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            replay: Replay {
+                version: FORMAT_VERSION,
+                seed,
+                events: Vec::new(),
+            },
+            started: std::time::Instant::now(),
+            material: None,
+            brush: None,
+            settings: None,
+            drains: Vec::new(),
+            conveyors: Vec::new(),
+            portals: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, step: u64, input: ReplayInput) {
+        match &input {
+            ReplayInput::SetMaterial(dna) => self.material = Some(dna.clone()),
+            ReplayInput::SetBrush(brush) => self.brush = Some(*brush),
+            ReplayInput::Randomize(seed) => self.material = Some(crate::material::to_dna(&crate::material::from_seed(*seed), *seed)),
+            ReplayInput::SetSettings(settings) => self.settings = Some((**settings).clone()),
+            ReplayInput::SetDrains(drains) => self.drains = drains.clone(),
+            ReplayInput::SetConveyors(conveyors) => self.conveyors = conveyors.clone(),
+            ReplayInput::SetPortals(portals) => self.portals = portals.clone(),
+            _ => {}
+        }
+        self.replay.events.push(ReplayEvent {
+            step,
+            elapsed: self.started.elapsed().as_secs_f64(),
+            input,
+        });
+    }
+
+    /// ブラシを当てたことを記録する (物質と形が前回から変わっていれば先に記録する)
+    pub fn record_stamp(&mut self, step: u64, x: f64, y: f64, material: MaterialDNA, brush: Brush) {
+        if self.material.as_ref() != Some(&material) {
+            self.record(step, ReplayInput::SetMaterial(material));
+        }
+        if self.brush != Some(brush) {
+            self.record(step, ReplayInput::SetBrush(brush));
+        }
+        self.record(step, ReplayInput::Stamp { x, y });
+    }
+
+    /// 物理の設定と領域のうち、前回の記録から変わったものを記録する
+    pub fn record_world(&mut self, step: u64, settings: ReplaySettings, drains: &[Drain], conveyors: &[Conveyor], portals: &[Portal]) {
+        if self.settings.as_ref() != Some(&settings) {
+            self.record(step, ReplayInput::SetSettings(Box::new(settings)));
+        }
+        if self.drains != drains {
+            self.record(step, ReplayInput::SetDrains(drains.to_vec()));
+        }
+        if self.conveyors != conveyors {
+            self.record(step, ReplayInput::SetConveyors(conveyors.to_vec()));
+        }
+        if self.portals != portals {
+            self.record(step, ReplayInput::SetPortals(portals.to_vec()));
+        }
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.replay.events.len()
+    }
+
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+pub struct ReplayPlayer {
+    replay: Replay,
+    next: usize, // 次に実行するイベント
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next: 0 }
+    }
+
+    /// step までに実行するはずの次の入力
+    pub fn next_due(&mut self, step: u64) -> Option<ReplayInput> {
+        let event = self.replay.events.get(self.next).filter(|event| event.step <= step)?;
+        self.next += 1;
+        Some(event.input.clone())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.events.len()
+    }
+
+    /// (実行したイベント数, 全イベント数)
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.replay.events.len())
+    }
+}
+
+#[derive(Default)]
+pub enum ReplayMode {
+    #[default]
+    Idle,
+    Recording(Box<ReplayRecorder>),
+    Playing(ReplayPlayer),
+}
+
+impl ReplayMode {
+    pub fn is_playing(&self) -> bool {
+        matches!(self, ReplayMode::Playing(_))
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self, ReplayMode::Recording(_))
+    }
+
+    /// 記録中なら入力を記録する
+    pub fn record(&mut self, step: u64, input: ReplayInput) {
+        if let ReplayMode::Recording(recorder) = self {
+            recorder.record(step, input);
+        }
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Replay", "Start recording", Command::Replay(ReplayControl::StartRecording));
+    registry.register("Replay", format!("Stop recording and save to {}", REPLAY_FILE), Command::Replay(ReplayControl::StopRecording));
+    registry.register("Replay", format!("Play {}", REPLAY_FILE), Command::Replay(ReplayControl::Play));
+    registry.register("Replay", "Stop playback", Command::Replay(ReplayControl::StopPlayback));
+}
@@ -0,0 +1,67 @@
+//! 発見した物質のパレット (シードバンク) の書き出しと読み込み
+//!
+//! 反応ネットワークに記録された物質 (seed, DNA, 名前) を1つの JSON ファイルにまとめ、
+//! 他の人と交換できるようにする。読み込みは既にある物質 (同じ seed) を除いて追加する。
+
+use crate::commands::{Command, CommandRegistry};
+use crate::material::MaterialDNA;
+use crate::reaction_graph::ReactionGraph;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const BANK_FILE: &str = "seed_bank.json";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeedBank {
+    pub version: u32,
+    pub materials: Vec<BankEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BankEntry {
+    pub seed: u64,
+    pub name: String,
+    pub dna: MaterialDNA,
+}
+
+impl SeedBank {
+    pub fn from_graph(graph: &ReactionGraph) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            materials: graph
+                .nodes
+                .iter()
+                .map(|node| BankEntry { seed: node.seed, name: node.name.clone(), dna: node.dna.clone() })
+                .collect(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let bank: SeedBank = serde_json::from_str(&json).map_err(std::io::Error::other)?;
+        if bank.version > FORMAT_VERSION {
+            return Err(std::io::Error::other(format!("unsupported seed bank version {}", bank.version)));
+        }
+        Ok(bank)
+    }
+
+    /// 反応ネットワークにまだない物質を追加し、追加した数を返す
+    pub fn merge_into(self, graph: &mut ReactionGraph) -> usize {
+        self.materials
+            .into_iter()
+            .filter(|entry| entry.dna.seed == entry.seed)
+            .filter(|entry| graph.add_material(&entry.dna, entry.name.clone()))
+            .count()
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Seed bank", "Export palette", Command::ExportSeedBank);
+    registry.register("Seed bank", "Import palette", Command::ImportSeedBank);
+}
@@ -0,0 +1,128 @@
+//! シミュレーションのスナップショットと差分 (開発用)
+//!
+//! 数フレーム離れた2つのスナップショットを比較し、大きく動いたドット、温度の変化、
+//! 物質が変わったドット、増減したドットをレポートにまとめる。物理の変更で
+//! 不安定になった箇所を探すのに使う。
+
+use crate::app::Dot;
+use crate::commands::{Command, CommandRegistry};
+use crate::material::State;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// この距離 (ワールド座標) 以上動いたドットを報告する
+pub const MOVE_THRESHOLD: f64 = 20.0;
+/// 2つ目のスナップショットを撮るまでのフレーム数
+pub const DIFF_FRAMES: u32 = 60;
+/// 差分レポートの出力先 (カレントディレクトリ)
+pub const REPORT_FILE: &str = "snapshot_diff.txt";
+const MAX_LISTED: usize = 20; // 一覧に載せる件数
+
+struct DotSnapshot {
+    x: f64,
+    y: f64,
+    temperature: f32,
+    seed: u64,
+    name: String,
+    state: State,
+}
+
+pub struct Snapshot {
+    elapsed: f64, // 起動からの秒数
+    dots: HashMap<u64, DotSnapshot>,
+}
+
+impl Snapshot {
+    pub fn capture(dots: &[Dot], elapsed: f64) -> Self {
+        let dots = dots
+            .iter()
+            .map(|dot| {
+                let snapshot = DotSnapshot {
+                    x: dot.x,
+                    y: dot.y,
+                    temperature: dot.material.temperature,
+                    seed: dot.material_dna.seed,
+                    name: dot.name.clone(),
+                    state: dot.material.state,
+                };
+                (dot.id, snapshot)
+            })
+            .collect();
+        Self { elapsed, dots }
+    }
+
+    /// self から later への差分をテキストのレポートにする
+    pub fn diff_report(&self, later: &Snapshot) -> String {
+        let mut moved: Vec<(u64, f64)> = Vec::new();
+        let mut changed: Vec<(u64, &DotSnapshot, &DotSnapshot)> = Vec::new();
+        let mut drift_sum = 0.0f32;
+        let mut drift_max = (0u64, 0.0f32);
+        let mut common = 0usize;
+
+        for (id, before) in &self.dots {
+            let Some(after) = later.dots.get(id) else {
+                continue;
+            };
+            common += 1;
+
+            let distance = ((after.x - before.x).powi(2) + (after.y - before.y).powi(2)).sqrt();
+            if distance >= MOVE_THRESHOLD {
+                moved.push((*id, distance));
+            }
+
+            let drift = after.temperature - before.temperature;
+            drift_sum += drift;
+            if drift.abs() > drift_max.1.abs() {
+                drift_max = (*id, drift);
+            }
+
+            if after.seed != before.seed || after.state != before.state {
+                changed.push((*id, before, after));
+            }
+        }
+        moved.sort_by(|a, b| b.1.total_cmp(&a.1));
+        changed.sort_by_key(|(id, _, _)| *id);
+
+        let vanished = self.dots.keys().filter(|id| !later.dots.contains_key(id)).count();
+        let appeared = later.dots.keys().filter(|id| !self.dots.contains_key(id)).count();
+
+        let mut report = String::new();
+        let _ = writeln!(report, "snapshot diff: {:.2}s -> {:.2}s", self.elapsed, later.elapsed);
+        let _ = writeln!(
+            report,
+            "dots: {} -> {} (vanished {}, appeared {})",
+            self.dots.len(),
+            later.dots.len(),
+            vanished,
+            appeared
+        );
+        let _ = writeln!(report);
+
+        let _ = writeln!(report, "--- moved >= {:.1} ({} dots) ---", MOVE_THRESHOLD, moved.len());
+        for (id, distance) in moved.iter().take(MAX_LISTED) {
+            let _ = writeln!(report, "#{:<8} {:.1}", id, distance);
+        }
+        let _ = writeln!(report);
+
+        let _ = writeln!(report, "--- temperature drift ---");
+        if common > 0 {
+            let _ = writeln!(report, "mean: {:+.4}", drift_sum / common as f32);
+            let _ = writeln!(report, "max:  {:+.4} (#{})", drift_max.1, drift_max.0);
+        }
+        let _ = writeln!(report);
+
+        let _ = writeln!(report, "--- materials changed ({} dots) ---", changed.len());
+        for (id, before, after) in changed.iter().take(MAX_LISTED) {
+            let _ = writeln!(
+                report,
+                "#{:<8} {} ({:?}) -> {} ({:?})",
+                id, before.name, before.state, after.name, after.state
+            );
+        }
+        report
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Debug", format!("Snapshot diff ({} frames)", DIFF_FRAMES), Command::SnapshotDiff);
+}
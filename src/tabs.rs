@@ -0,0 +1,137 @@
+//! 複数のシミュレーションをタブで切り替える
+//!
+//! タブごとにドット・物理・乱数・グループと、壁の温度などの環境、差分を待っているスナップショットを持つ。表示しているタブの状態は
+//! App のフィールドに入れ替えて使う (ブラシやカメラ、反応ネットワークはタブをまたいで共有する)。
+//! 裏のタブは止めておくか、遅い速さで進める。ドットの ID はタブごとに別の範囲から払い出すので、
+//! ワーカーから届いたブレンド結果はどのタブのドットにもそのまま適用できる。
+
+use crate::app::Dot;
+use crate::commands::{Command, CommandRegistry};
+use crate::groups::DotGroups;
+use crate::physics::conveyor::Conveyor;
+use crate::physics::drain::Drain;
+use crate::physics::portal::Portal;
+use crate::physics::thermostat::WallThermostat;
+use crate::physics::{GameRng, Physics};
+use crate::snapshot::Snapshot;
+use crate::weather::Weather;
+
+pub const MAX_TABS: usize = 8;
+pub const BACKGROUND_SPEED: f64 = 0.25; // 裏のタブを進める速さ (表のタブに対する割合)
+const ID_RANGE_BITS: u32 = 40; // タブごとのドット ID の範囲 (2^40 個)
+
+/// id を払い出したタブの ID の範囲の先頭
+pub fn id_range_start(id: u64) -> u64 {
+    id >> ID_RANGE_BITS << ID_RANGE_BITS
+}
+
+/// Tabs バーとコマンドパレットからの操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabAction {
+    New,
+    Switch(usize),
+    Next,
+    Close(usize),
+    CloseActive,
+    SetBackgroundRunning(bool),
+}
+
+/// タブごとのシミュレーションの状態 (表示中のタブの分は App が持つ)
+pub struct SimState {
+    pub dots: Vec<Dot>,
+    pub physics: Physics,
+    pub gravity: f64,
+    pub is_updating: bool,
+    pub physics_accumulator: f64,
+    pub next_dot_id: u64,
+    pub rng: GameRng,
+    pub physics_steps: u64,
+    pub groups: DotGroups,
+    pub selected_dot_id: Option<u64>,
+    pub erase_history: Vec<Vec<Dot>>,
+    pub thermostat: WallThermostat,
+    pub weather: Weather,
+    pub drains: Vec<Drain>,
+    pub conveyors: Vec<Conveyor>,
+    pub portals: Vec<Portal>,
+    pub portal_entrance: Option<(f64, f64)>, // 出口がまだない入口
+    pub pending_snapshot: Option<(Snapshot, u32)>, // 差分の基準になるスナップショットと残りフレーム数
+}
+
+impl SimState {
+    /// serial 番目に作られたタブの空のシミュレーション
+    pub fn new(physics: Physics, gravity: f64, rng: GameRng, serial: u64) -> Self {
+        Self {
+            dots: Vec::new(),
+            physics,
+            gravity,
+            is_updating: false,
+            physics_accumulator: 0.0,
+            next_dot_id: serial << ID_RANGE_BITS,
+            rng,
+            physics_steps: 0,
+            groups: DotGroups::default(),
+            selected_dot_id: None,
+            erase_history: Vec::new(),
+            thermostat: WallThermostat::default(),
+            weather: Weather::default(),
+            drains: Vec::new(),
+            conveyors: Vec::new(),
+            portals: Vec::new(),
+            portal_entrance: None,
+            pending_snapshot: None,
+        }
+    }
+}
+
+pub struct SimTab {
+    pub name: String,
+    pub state: Option<SimState>, // 表示中のタブでは None (App のフィールドに入っている)
+}
+
+pub struct Tabs {
+    pub tabs: Vec<SimTab>,
+    pub active: usize,
+    pub background_running: bool, // 裏のタブを進めるか
+    created: u64,                  // これまでに作ったタブの数 (ID の範囲と名前に使う)
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Self {
+            tabs: vec![SimTab {
+                name: "Sim 1".to_string(),
+                state: None,
+            }],
+            active: 0,
+            background_running: true,
+            created: 1,
+        }
+    }
+}
+
+impl Tabs {
+    /// 新しいタブの名前と ID の範囲の番号を払い出す
+    pub fn next_serial(&mut self) -> (String, u64) {
+        let serial = self.created;
+        self.created += 1;
+        (format!("Sim {}", serial + 1), serial)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.tabs.iter().map(|tab| tab.name.clone()).collect()
+    }
+
+    /// 裏のタブの状態
+    pub fn background_mut(&mut self) -> impl Iterator<Item = &mut SimState> {
+        self.tabs.iter_mut().filter_map(|tab| tab.state.as_mut())
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("Tab", "New simulation", Command::Tab(TabAction::New));
+    registry.register("Tab", "Next simulation", Command::Tab(TabAction::Next));
+    registry.register("Tab", "Close simulation", Command::Tab(TabAction::CloseActive));
+    registry.register("Tab", "Run background tabs", Command::Tab(TabAction::SetBackgroundRunning(true)));
+    registry.register("Tab", "Pause background tabs", Command::Tab(TabAction::SetBackgroundRunning(false)));
+}
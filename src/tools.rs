@@ -0,0 +1,152 @@
+//! マウス (ゲームパッドの配置ボタン) で使うツール
+//!
+//! Brush はブラシの物質のドットを置き、Dig はカーソル周辺の固体を掘る。
+//! 硬い固体ほど崩れにくく、崩れた固体はまず瓦礫 (さらさらした粒) になって落ち、
+//! 柔らかくなったものから取り除かれる。
+//! Stir はカーソルを中心とした渦でかき混ぜる。粘性が高く重いドットほど動きにくい。
+//! Drain はドラッグした矩形に排水口を置く (physics::drain)。
+//! Portal は1回目のクリックで入口、2回目で出口を置く (physics::portal)。
+//! Conveyor はドラッグした矩形にコンベアを置く (physics::conveyor)。
+//! 右クリックはドットの選択で、そのまま少しドラッグするとツールに関係なく消しゴムになり、
+//! ブラシの半径内のドットを取り除く (押したときに選んだドットはそのストロークでは消さない)。
+
+use crate::app::Dot;
+use crate::commands::{Command, CommandRegistry};
+use crate::material::State;
+use crate::physics::{GameRng, Physics};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub const DIG_RADIUS: f64 = 12.0;
+const DIG_RATE: f64 = 6.0; // 硬さ 0 の固体が1秒あたりに崩れる回数の期待値
+const MIN_DIG_RATE: f64 = 0.05; // 硬さ 1 でも少しずつは掘れる
+const REMOVE_HARDNESS: f32 = 0.3; // これより柔らかい固体は掘ると取り除かれる
+const RUBBLE_VISCOSITY: f32 = 0.1; // 瓦礫は粘性を下げて崩れ広がるようにする
+const RUBBLE_SPEED: f64 = 40.0; // 瓦礫がカーソルから弾かれる速さ
+
+pub const ERASE_MIN_RADIUS: f64 = 4.0; // ブラシの半径が 0 でもドット1つ分は消す
+pub const ERASE_DRAG_THRESHOLD: f64 = 8.0; // 右ボタンを押してから画面上でこれだけ動かすと消しゴムになる
+pub const MAX_ERASE_HISTORY: usize = 20; // 取り消せる消しゴムのストロークの数
+
+pub const STIR_RADIUS: f64 = 30.0;
+const STIR_ACCELERATION: f64 = 600.0; // 渦の接線方向の加速度 (中心付近、抵抗なしの場合)
+const VISCOSITY_RESISTANCE: f64 = 8.0; // 粘性 1.0 で加速度が 1/9 になる
+const MASS_RESISTANCE: f64 = 2.0; // 密度 1.0 で加速度が 1/3 になる
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tool {
+    #[default]
+    Brush,
+    Dig,
+    Stir,
+    Drain,
+    Portal,
+    Conveyor,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 6] = [Tool::Brush, Tool::Dig, Tool::Stir, Tool::Drain, Tool::Portal, Tool::Conveyor];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Tool::Brush => "Brush",
+            Tool::Dig => "Dig",
+            Tool::Stir => "Stir",
+            Tool::Drain => "Drain",
+            Tool::Portal => "Portal",
+            Tool::Conveyor => "Conveyor",
+        }
+    }
+
+    /// カーソル周辺に作用するツールの半径 (GUI で範囲を表示する)
+    pub fn radius(self) -> Option<f64> {
+        match self {
+            Tool::Brush | Tool::Drain | Tool::Portal | Tool::Conveyor => None,
+            Tool::Dig => Some(DIG_RADIUS),
+            Tool::Stir => Some(STIR_RADIUS),
+        }
+    }
+}
+
+/// (x, y) の周辺の固体を dt 秒分だけ掘る。取り除いたドットの数を返す
+/// 空間グリッドを使うので、直近の update_collision 以降にドットを増減させたら rebuild_grid しておくこと
+pub fn dig(physics: &Physics, dots: &mut Vec<Dot>, x: f64, y: f64, dt: f64, rng: &mut GameRng) -> usize {
+    let hits: Vec<usize> = physics
+        .query_circle(dots, x, y, DIG_RADIUS)
+        .filter(|&i| dots[i].material.state == State::Solid && !dots[i].hidden)
+        .collect();
+
+    let mut removed = Vec::new();
+    for i in hits {
+        let dot = &mut dots[i];
+        let rate = (DIG_RATE * (1.0 - dot.material.hardness as f64)).max(DIG_RATE * MIN_DIG_RATE);
+        if rng.gen::<f64>() >= rate * dt {
+            continue;
+        }
+        if dot.material.hardness < REMOVE_HARDNESS {
+            removed.push(i);
+        } else {
+            // 瓦礫にする: 硬さを半分にし、カーソルから外向きに弾く
+            dot.material.hardness *= 0.5;
+            dot.material.viscosity = RUBBLE_VISCOSITY;
+            let (dx, dy) = (dot.x - x, dot.y - y);
+            let distance = (dx * dx + dy * dy).sqrt().max(1e-6);
+            dot.vx += dx / distance * RUBBLE_SPEED;
+            dot.vy += dy / distance * RUBBLE_SPEED;
+        }
+    }
+
+    // 降順に削除してインデックスのズレを防ぐ
+    removed.sort_unstable_by(|a, b| b.cmp(a));
+    for &i in &removed {
+        dots.remove(i);
+    }
+    removed.len()
+}
+
+/// (x, y) から radius 以内のドットを spare 以外取り除き、取り除いたドットを返す (取り消し用)
+/// カーソル移動のたびに呼ばれ、空間グリッドが古くなっていることがあるので全件を調べる
+pub fn erase(dots: &mut Vec<Dot>, x: f64, y: f64, radius: f64, spare: Option<u64>) -> Vec<Dot> {
+    let radius_sq = radius * radius;
+    let hit = |dot: &Dot| {
+        !dot.hidden && Some(dot.id) != spare && (dot.x - x).powi(2) + (dot.y - y).powi(2) <= radius_sq
+    };
+    if !dots.iter().any(hit) {
+        return Vec::new();
+    }
+    let (erased, kept): (Vec<Dot>, Vec<Dot>) = std::mem::take(dots).into_iter().partition(hit);
+    *dots = kept;
+    erased
+}
+
+/// (x, y) を中心に時計回りの渦を dt 秒分だけ加える
+/// 中心から離れるほど弱く、粘性と密度 (質量) が大きいドットほど加速しにくい
+pub fn stir(physics: &Physics, dots: &mut [Dot], x: f64, y: f64, dt: f64) {
+    let hits: Vec<usize> = physics.query_circle(dots, x, y, STIR_RADIUS).collect();
+    for i in hits {
+        let dot = &mut dots[i];
+        if dot.hidden {
+            continue;
+        }
+        let (dx, dy) = (dot.x - x, dot.y - y);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance < 1e-6 {
+            continue;
+        }
+        let falloff = 1.0 - distance / STIR_RADIUS;
+        let resistance = 1.0
+            + dot.material.viscosity as f64 * VISCOSITY_RESISTANCE
+            + dot.material.density as f64 * MASS_RESISTANCE;
+        let acceleration = STIR_ACCELERATION * falloff / resistance;
+        // 画面座標は y が下向きなので (-dy, dx) が時計回りの接線
+        dot.vx += -dy / distance * acceleration * dt;
+        dot.vy += dx / distance * acceleration * dt;
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    for tool in Tool::ALL {
+        registry.register("Tool", tool.label(), Command::SelectTool(tool));
+    }
+    registry.register("Tool", "Undo erase", Command::UndoErase);
+}
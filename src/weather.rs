@@ -0,0 +1,162 @@
+//! 雨と雪 (上端から物質を降らせる環境のスポナー)
+//!
+//! 選んだ物質を、雨なら液体・雪なら固体にして画面の上端から一定の割合で降らせる。
+//! 降らせたドットは風で横に流され、床に着くと消える (地面に染み込む・溶ける) ので、
+//! 降らせ続けてもドットは増え続けない。床に届かずに積もった分も MAX_DOTS で頭打ちにする。
+//! 天気はタブごとに持ち、裏のタブでも進めている間は降らせ続ける。
+
+use crate::app::Dot;
+use crate::material::{from_dna, to_dna, BaseMaterialParams, MaterialDNA};
+use crate::physics::{GameRng, HEIGHT, WIDTH};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const MAX_RATE: f64 = 200.0; // 1秒あたりに降らせる数
+pub const MAX_WIND: f64 = 150.0;
+const MAX_DOTS: usize = 1500; // 同時に存在できる雨・雪のドットの数
+const FLOOR_MARGIN: f64 = 1.0; // 床からこの距離まで近づいたら消す
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Precipitation {
+    #[default]
+    Rain,
+    Snow,
+}
+
+impl Precipitation {
+    pub const ALL: [Precipitation; 2] = [Precipitation::Rain, Precipitation::Snow];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Precipitation::Rain => "Rain",
+            Precipitation::Snow => "Snow",
+        }
+    }
+
+    /// 状態の遺伝子 (to_dna と同じ値)
+    fn state_gene(self) -> f32 {
+        match self {
+            Precipitation::Rain => 0.495,
+            Precipitation::Snow => 0.165,
+        }
+    }
+
+    /// 降り始めの速さ
+    fn fall_speed(self) -> f64 {
+        match self {
+            Precipitation::Rain => 120.0,
+            Precipitation::Snow => 20.0,
+        }
+    }
+
+    /// 風に流されやすさ (1秒あたりに風の速さへ近づく割合)
+    fn drag(self) -> f64 {
+        match self {
+            Precipitation::Rain => 0.5,
+            Precipitation::Snow => 3.0,
+        }
+    }
+}
+
+/// Weather パネルで編集する設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeatherSettings {
+    pub enabled: bool,
+    pub kind: Precipitation,
+    pub rate: f64, // 1秒あたりに降らせる数
+    pub wind: f64, // 横向きの風の速さ (正で右)
+}
+
+impl Default for WeatherSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: Precipitation::default(),
+            rate: 30.0,
+            wind: 0.0,
+        }
+    }
+}
+
+pub struct Weather {
+    pub settings: WeatherSettings,
+    pub material: MaterialDNA, // 降らせる物質 (状態は kind で上書きする)
+    accumulator: f64,          // 次の1つを降らせるまでにたまった数
+    spawned: HashSet<u64>,     // 降らせたドットの ID
+}
+
+impl Default for Weather {
+    /// 既定の物質を降らせる (止まった状態)
+    fn default() -> Self {
+        Self::new(to_dna(&BaseMaterialParams::default(), 0))
+    }
+}
+
+impl Weather {
+    pub fn new(material: MaterialDNA) -> Self {
+        Self {
+            settings: WeatherSettings::default(),
+            material,
+            accumulator: 0.0,
+            spawned: HashSet::new(),
+        }
+    }
+
+    /// 実際に降らせる物質 (雨なら液体、雪なら固体)
+    pub fn drop_dna(&self) -> MaterialDNA {
+        let mut dna = self.material.clone();
+        dna.genes[0] = self.settings.kind.state_gene();
+        dna
+    }
+
+    /// 降らせたドットを風で流し、床に着いたものを消してから、新しく降らせる
+    pub fn update(&mut self, dots: &mut Vec<Dot>, dt: f64, next_id: &mut u64, rng: &mut GameRng) {
+        if !self.spawned.is_empty() {
+            let drag = (self.settings.kind.drag() * dt).min(1.0);
+            let wind = self.settings.wind;
+            let mut alive = HashSet::with_capacity(self.spawned.len());
+            dots.retain_mut(|dot| {
+                if !self.spawned.contains(&dot.id) {
+                    return true;
+                }
+                if dot.y >= HEIGHT as f64 - dot.radius() - FLOOR_MARGIN {
+                    return false;
+                }
+                dot.vx += (wind - dot.vx) * drag;
+                alive.insert(dot.id);
+                true
+            });
+            self.spawned = alive; // 反応で消えたドットも外れる
+        }
+
+        if !self.settings.enabled {
+            self.accumulator = 0.0;
+            return;
+        }
+        self.accumulator += self.settings.rate * dt;
+        let dna = self.drop_dna();
+        let material = from_dna(&dna);
+        while self.accumulator >= 1.0 && self.spawned.len() < MAX_DOTS {
+            self.accumulator -= 1.0;
+            let x = rng.gen_range(0.0..WIDTH as f64);
+            let mut dot = Dot::new(*next_id, x, 0.0, material.clone(), dna.clone());
+            *next_id += 1;
+            dot.vx = self.settings.wind;
+            dot.vy = self.settings.kind.fall_speed();
+            self.spawned.insert(dot.id);
+            dots.push(dot);
+        }
+        self.accumulator = self.accumulator.min(1.0); // 上限に達している間はためない
+    }
+
+    /// 降らせている、または降らせたドットが残っているか (止まっていても物理更新を続ける)
+    pub fn is_active(&self) -> bool {
+        self.settings.enabled || !self.spawned.is_empty()
+    }
+
+    /// 降らせて残っているドットの数
+    pub fn active_count(&self) -> usize {
+        self.spawned.len()
+    }
+}
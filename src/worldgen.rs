@@ -0,0 +1,178 @@
+//! 地形の自動生成 ("Generate landscape")
+//!
+//! ノイズで決めた地表の下を砂・土・岩の層で埋め、谷には水たまり、
+//! 深い所にはガスだまりを作る。掘って遊ぶための初期ワールドになる。
+
+use crate::app::{HEIGHT, WIDTH};
+use crate::commands::{Command, CommandRegistry};
+use crate::material::{from_seed, BaseMaterialParams, State};
+use crate::presets::MaterialOverrides;
+
+const SPACING: f64 = 4.0; // ドットの間隔 (DOT_RADIUS * 2)
+const SURFACE_LEVEL: f64 = HEIGHT as f64 * 0.55; // 地表の平均の高さ
+const SURFACE_AMPLITUDE: f64 = 70.0;
+const WATER_LEVEL: f64 = SURFACE_LEVEL + 20.0; // これより低い谷に水がたまる
+const SAND_DEPTH: f64 = 8.0;
+const DIRT_DEPTH: f64 = 40.0;
+const GAS_MIN_DEPTH: f64 = 60.0;
+const GAS_THRESHOLD: f64 = 0.72; // ノイズがこれを超えた所をガスだまりにする
+
+/// 生成する物質の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terrain {
+    Rock,
+    Dirt,
+    Sand,
+    Water,
+    Gas,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 5] = [Terrain::Rock, Terrain::Dirt, Terrain::Sand, Terrain::Water, Terrain::Gas];
+
+    /// 物質の元になる seed (同じ種類は常に同じ物質になる)
+    pub fn seed(self) -> u64 {
+        match self {
+            Terrain::Rock => 701,
+            Terrain::Dirt => 702,
+            Terrain::Sand => 703,
+            Terrain::Water => 704,
+            Terrain::Gas => 705,
+        }
+    }
+
+    pub fn material(self) -> BaseMaterialParams {
+        let overrides = match self {
+            Terrain::Rock => MaterialOverrides {
+                state: Some(State::Solid),
+                density: Some(0.9),
+                hardness: Some(0.9),
+                color_hue: Some(0.08),
+                color_saturation: Some(0.05),
+                color_luminance: Some(0.35),
+                ..Self::inert()
+            },
+            Terrain::Dirt => MaterialOverrides {
+                state: Some(State::Solid),
+                density: Some(0.6),
+                hardness: Some(0.4),
+                color_hue: Some(0.07),
+                color_saturation: Some(0.45),
+                color_luminance: Some(0.3),
+                ..Self::inert()
+            },
+            Terrain::Sand => MaterialOverrides {
+                state: Some(State::Solid),
+                density: Some(0.5),
+                hardness: Some(0.1),
+                color_hue: Some(0.12),
+                color_saturation: Some(0.55),
+                color_luminance: Some(0.65),
+                ..Self::inert()
+            },
+            Terrain::Water => MaterialOverrides {
+                state: Some(State::Liquid),
+                density: Some(0.4),
+                viscosity: Some(0.1),
+                color_hue: Some(0.58),
+                color_saturation: Some(0.7),
+                color_luminance: Some(0.45),
+                ..Self::inert()
+            },
+            Terrain::Gas => MaterialOverrides {
+                state: Some(State::Gas),
+                density: Some(0.1),
+                temperature: Some(0.3),
+                color_hue: Some(0.3),
+                color_saturation: Some(0.6),
+                color_luminance: Some(0.6),
+                ..Self::inert()
+            },
+        };
+        overrides.apply(from_seed(self.seed()))
+    }
+
+    /// 勝手に反応・爆発しない、常温・非発光の特性
+    fn inert() -> MaterialOverrides {
+        MaterialOverrides {
+            temperature: Some(0.0),
+            luminescence: Some(0.0),
+            volatility: Some(0.0),
+            entropy_bias: Some(0.0),
+            ..MaterialOverrides::default()
+        }
+    }
+}
+
+/// seed から地形を生成し、ドットの位置と種類を返す
+pub fn generate(seed: u64) -> Vec<(f64, f64, Terrain)> {
+    let mut cells = Vec::new();
+    let cols = (WIDTH as f64 / SPACING) as usize;
+    let rows = (HEIGHT as f64 / SPACING) as usize;
+
+    for col in 0..cols {
+        let x = (col as f64 + 0.5) * SPACING;
+        let surface = SURFACE_LEVEL + (fbm(seed, x / 160.0, 0.0) - 0.5) * 2.0 * SURFACE_AMPLITUDE;
+        let sand_depth = SAND_DEPTH * (0.5 + fbm(seed ^ 0x5a5a, x / 40.0, 0.0));
+        let dirt_depth = DIRT_DEPTH * (0.5 + fbm(seed ^ 0xd1d1, x / 80.0, 0.0));
+
+        for row in 0..rows {
+            let y = (row as f64 + 0.5) * SPACING;
+            if y < surface {
+                if y >= WATER_LEVEL {
+                    cells.push((x, y, Terrain::Water));
+                }
+                continue;
+            }
+
+            let depth = y - surface;
+            let terrain = if depth > GAS_MIN_DEPTH && fbm(seed ^ 0x6a5, x / 50.0, y / 50.0) > GAS_THRESHOLD {
+                Terrain::Gas
+            } else if depth < sand_depth {
+                Terrain::Sand
+            } else if depth < dirt_depth {
+                Terrain::Dirt
+            } else {
+                Terrain::Rock
+            };
+            cells.push((x, y, terrain));
+        }
+    }
+    cells
+}
+
+/// 格子点ごとの乱数 (0.0 ~ 1.0)
+fn lattice(seed: u64, x: i64, y: i64) -> f64 {
+    let mut h = seed ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// 格子点の乱数を滑らかに補間したノイズ (0.0 ~ 1.0)
+fn value_noise(seed: u64, x: f64, y: f64) -> f64 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+    let (ix, iy) = (x0 as i64, y0 as i64);
+    let top = lattice(seed, ix, iy) * (1.0 - tx) + lattice(seed, ix + 1, iy) * tx;
+    let bottom = lattice(seed, ix, iy + 1) * (1.0 - tx) + lattice(seed, ix + 1, iy + 1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+/// 周波数を倍にしながら重ねたノイズ (0.0 ~ 1.0)
+fn fbm(seed: u64, x: f64, y: f64) -> f64 {
+    let (mut sum, mut amplitude, mut frequency, mut total) = (0.0, 1.0, 1.0, 0.0);
+    for octave in 0..4 {
+        sum += value_noise(seed.wrapping_add(octave), x * frequency, y * frequency) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / total
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register("World", "Generate landscape", Command::GenerateLandscape);
+}